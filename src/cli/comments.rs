@@ -0,0 +1,178 @@
+//! Implementation of the `bt comments` command
+//!
+//! Shows the discussion threads of every review in the current stack,
+//! grouped per branch from the bottom of the stack to the top. Diff threads
+//! show the file and line they are attached to.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::comments::run_comments;
+//!
+//! // Only threads that still need to be addressed
+//! run_comments(true, false)?;
+//! ```
+
+use crate::cli::common;
+use crate::cli::output::{self, progress};
+use crate::core::metadata::{self, Metadata};
+use crate::core::{environment, git, stack};
+use crate::error::{Error, Result};
+use crate::providers::Discussion;
+use serde::Serialize;
+
+/// Discussions of one branch's review
+#[derive(Debug, Serialize)]
+struct BranchComments {
+    branch: String,
+    review_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    review_url: Option<String>,
+    discussions: Vec<Discussion>,
+}
+
+/// Run the comments command
+///
+/// # Arguments
+///
+/// * `unresolved_only` - Only show resolvable threads that aren't resolved
+/// * `json` - Output JSON instead of text
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The repository isn't initialized
+/// - The current branch isn't part of a stack
+/// - The provider can't be reached
+pub fn run_comments(unresolved_only: bool, json: bool) -> Result<()> {
+    environment::check_basic_environment()?;
+
+    let current = git::get_current_branch()?;
+    let mut metadata = metadata::load_metadata()?;
+    let branches = stack_branches(&metadata, &current)?;
+
+    let reviewed: Vec<(String, String, Option<String>)> = branches
+        .iter()
+        .filter_map(|branch| {
+            let meta = metadata.get_branch(branch)?;
+            let review_id = meta.review_id.clone()?;
+            Some((branch.clone(), review_id, meta.review_url.clone()))
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    if !reviewed.is_empty() {
+        let mut provider = common::connect_provider(&mut metadata)?;
+        for (branch, review_id, review_url) in reviewed {
+            let mut discussions = provider.list_discussions(&review_id)?;
+            if unresolved_only {
+                discussions.retain(Discussion::is_unresolved);
+            }
+            results.push(BranchComments {
+                branch,
+                review_id,
+                review_url,
+                discussions,
+            });
+        }
+    }
+
+    if json {
+        output::emit(&results)?;
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        progress!("No reviews in the current stack. Run 'bt submit' first.");
+        return Ok(());
+    }
+
+    for (index, entry) in results.iter().enumerate() {
+        if index > 0 {
+            progress!();
+        }
+        print_branch(entry, unresolved_only);
+    }
+
+    Ok(())
+}
+
+/// Get the branches of the stack containing `current`, bottom first
+///
+/// On the base branch, this is every tracked branch.
+fn stack_branches(metadata: &Metadata, current: &str) -> Result<Vec<String>> {
+    if current == metadata.base_branch {
+        return Ok(stack::descendants(metadata, current));
+    }
+    if !metadata.has_branch(current) {
+        return Err(Error::invalid_stack(format!(
+            "Branch '{}' is not tracked by basalt",
+            current
+        )));
+    }
+
+    let mut branches: Vec<String> = stack::ancestors(metadata, current)
+        .into_iter()
+        .rev()
+        .collect();
+    branches.push(current.to_string());
+    branches.extend(stack::descendants(metadata, current));
+    Ok(branches)
+}
+
+/// Print the threads of one branch's review
+fn print_branch(entry: &BranchComments, unresolved_only: bool) {
+    let unresolved = entry
+        .discussions
+        .iter()
+        .filter(|discussion| discussion.is_unresolved())
+        .count();
+    progress!(
+        "📋 {}  {}  ({} unresolved)",
+        entry.branch,
+        entry.review_id,
+        unresolved
+    );
+
+    if entry.discussions.is_empty() {
+        if unresolved_only {
+            progress!("  No unresolved threads");
+        } else {
+            progress!("  No comments");
+        }
+        return;
+    }
+
+    for discussion in &entry.discussions {
+        let marker = if discussion.is_unresolved() {
+            "⚠️ "
+        } else if discussion.resolved {
+            "✓"
+        } else {
+            "💬"
+        };
+        let location = match (&discussion.file, discussion.line) {
+            (Some(file), Some(line)) => format!("{}:{}", file, line),
+            (Some(file), None) => file.clone(),
+            _ => "general".to_string(),
+        };
+        let status = if discussion.resolved {
+            " (resolved)"
+        } else {
+            ""
+        };
+        progress!("  {} {}{}", marker, location, status);
+
+        for comment in &discussion.comments {
+            let mut lines = comment.body.lines();
+            progress!(
+                "      {}: {}",
+                comment.author,
+                lines.next().unwrap_or_default()
+            );
+            for line in lines {
+                progress!("      {}", line);
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Helpers shared by several CLI commands
+
+use crate::core::config;
+use crate::core::metadata::{self, Metadata};
+use crate::error::Result;
+use crate::providers::{self, Provider, ProviderType, ReviewState};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Create an authenticated provider from repository metadata
+///
+/// Uses the stored base URL, project path and token when available.
+/// If authentication produced a new token, it is persisted back into
+/// the metadata file.
+///
+/// # Errors
+///
+/// Returns an error if the provider can't be created or authentication fails
+pub fn connect_provider(metadata: &mut Metadata) -> Result<Box<dyn Provider>> {
+    match metadata.provider {
+        ProviderType::GitLab => {
+            let mut gitlab = gitlab_provider(metadata)?;
+            gitlab.authenticate()?;
+
+            let token = gitlab.get_auth_token();
+            if token.is_some() && token != metadata.auth_token {
+                metadata.auth_token = token;
+                metadata::save_metadata(metadata)?;
+            }
+
+            Ok(Box::new(gitlab))
+        }
+        ProviderType::GitHub => {
+            let mut provider = providers::create_provider(ProviderType::GitHub)?;
+            provider.authenticate()?;
+            Ok(provider)
+        }
+    }
+}
+
+/// Create a provider from the token cached in the metadata
+///
+/// Unlike [`connect_provider`], this never prompts and doesn't check the
+/// token, so requests fail if it has expired. Returns `None` if no token
+/// is cached.
+///
+/// # Errors
+///
+/// Returns an error if the provider can't be created
+pub fn cached_provider(metadata: &Metadata) -> Result<Option<Box<dyn Provider>>> {
+    match metadata.provider {
+        ProviderType::GitLab if metadata.auth_token.is_some() => {
+            Ok(Some(Box::new(gitlab_provider(metadata)?)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Create a GitLab provider for the repository, with its cached token
+fn gitlab_provider(metadata: &Metadata) -> Result<providers::gitlab::GitLabProvider> {
+    let base_url = metadata::get_base_url(metadata)?;
+    let project_path = metadata::get_project_path(metadata)?;
+
+    let mut gitlab = providers::gitlab::GitLabProvider::with_http_config(
+        &base_url,
+        &config::load_http_config(&base_url)?,
+    )?;
+    gitlab.set_project_path(project_path);
+    if let Some(token) = &metadata.auth_token {
+        gitlab.set_auth_token(token.clone());
+    }
+    Ok(gitlab)
+}
+
+/// Prompt the user for a line of input on stderr
+///
+/// Returns the trimmed answer, or `default` if the answer is empty.
+///
+/// # Errors
+///
+/// Returns an error if reading from stdin fails
+pub fn prompt(message: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(default) => eprint!("{} [{}]: ", message, default),
+        None => eprint!("{}: ", message),
+    }
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    let answer = answer.trim();
+    if answer.is_empty() {
+        Ok(default.unwrap_or_default().to_string())
+    } else {
+        Ok(answer.to_string())
+    }
+}
+
+/// Check whether any of `branches` has a review attached
+pub fn any_review(metadata: &Metadata, branches: &[String]) -> bool {
+    branches.iter().any(|branch| {
+        metadata
+            .get_branch(branch)
+            .is_some_and(|meta| meta.review_id.is_some())
+    })
+}
+
+/// Fetch the provider state of every review among `branches`
+///
+/// The reviews are fetched together, in parallel where the provider
+/// supports it. Without `authenticate`, only a cached token is used and
+/// failures are silent. Returns the states that could be fetched; states
+/// are only informational, so callers show what they have.
+pub fn fetch_review_states(
+    metadata: &mut Metadata,
+    branches: &[String],
+    authenticate: bool,
+) -> HashMap<String, ReviewState> {
+    let mut states = HashMap::new();
+    if !any_review(metadata, branches) {
+        return states;
+    }
+
+    let provider = if authenticate {
+        connect_provider(metadata).map(Some)
+    } else {
+        cached_provider(metadata)
+    };
+    let mut provider = match provider {
+        Ok(Some(provider)) => provider,
+        Ok(None) => return states,
+        Err(e) => {
+            if authenticate {
+                eprintln!("⚠️  Could not fetch review states: {}", e);
+            }
+            return states;
+        }
+    };
+
+    let (reviewed, review_ids): (Vec<&String>, Vec<String>) = branches
+        .iter()
+        .filter_map(|branch| {
+            let review_id = metadata.get_branch(branch)?.review_id.clone()?;
+            Some((branch, review_id))
+        })
+        .unzip();
+    let reviews = provider.get_reviews(&review_ids);
+
+    for ((branch, review_id), review) in reviewed.into_iter().zip(&review_ids).zip(reviews) {
+        match review {
+            Ok(review) => {
+                states.insert(branch.clone(), review.state);
+            }
+            Err(e) if authenticate => {
+                eprintln!("⚠️  Could not fetch review {}: {}", review_id, e)
+            }
+            Err(_) => {}
+        }
+    }
+
+    states
+}
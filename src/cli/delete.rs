@@ -0,0 +1,136 @@
+//! Implementation of the `bt delete` command
+//!
+//! Deletes a branch while keeping the stack consistent:
+//! - Children are reparented onto the deleted branch's parent and restacked
+//!   so they no longer contain its commits
+//! - Children's reviews are retargeted to their new parent
+//! - The branch's own review can optionally be closed
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::delete::run_delete;
+//!
+//! // Delete a merged branch
+//! run_delete(Some("feature-part-1".to_string()), false, false, None)?;
+//! ```
+
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, Rebase};
+use crate::core::{environment, git, metadata, stack};
+use crate::error::{Error, Result};
+
+/// Run the delete command
+///
+/// # Arguments
+///
+/// * `branch` - Branch to delete (defaults to the current branch)
+/// * `force` - Delete even if the branch isn't merged into the base branch
+/// * `close_review` - Close the branch's review
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The environment isn't ready for stack operations
+/// - The branch is the base branch or doesn't exist
+/// - The branch isn't merged and `force` is not set
+/// - Restacking the children or updating reviews fails
+pub fn run_delete(
+    branch: Option<String>,
+    force: bool,
+    close_review: bool,
+    dry_run: Option<PlanFormat>,
+) -> Result<()> {
+    environment::check_stack_operation_environment()?;
+
+    let current = git::get_current_branch()?;
+    let branch = branch.unwrap_or_else(|| current.clone());
+    let mut metadata = metadata::load_metadata()?;
+
+    if branch == metadata.base_branch {
+        return Err(Error::invalid_stack(format!(
+            "Cannot delete the base branch '{}'",
+            branch
+        )));
+    }
+    if !git::local_branch_exists(&branch)? {
+        return Err(Error::BranchNotFound { branch });
+    }
+    if !force && !git::is_ancestor(&branch, &metadata.base_branch)? {
+        return Err(Error::invalid_stack(format!(
+            "Branch '{}' is not merged into '{}'. Use --force to delete it anyway.",
+            branch, metadata.base_branch
+        )));
+    }
+
+    let parent = metadata
+        .get_branch(&branch)
+        .map(|meta| meta.parent.clone())
+        .unwrap_or_else(|| metadata.base_branch.clone());
+
+    let mut plan = Plan::new("delete");
+
+    // Children are based on the branch being deleted; replay them onto
+    // its parent while the branch still exists
+    let children = stack::children(&metadata, &branch);
+    let mut rebases = Vec::new();
+    for child in &children {
+        rebases.push(Rebase {
+            branch: child.clone(),
+            onto: parent.clone(),
+            upstream: branch.clone(),
+        });
+        for name in stack::descendants(&metadata, child) {
+            if let Some(meta) = metadata.get_branch(&name) {
+                rebases.push(Rebase {
+                    branch: name.clone(),
+                    onto: meta.parent.clone(),
+                    upstream: meta.parent.clone(),
+                });
+            }
+        }
+    }
+    if !rebases.is_empty() {
+        plan.push(Action::Restack { rebases });
+    }
+
+    let return_to = if current == branch {
+        parent.clone()
+    } else {
+        current
+    };
+    plan.push(Action::Checkout { branch: return_to });
+    plan.push(Action::DeleteBranch {
+        branch: branch.clone(),
+    });
+    plan.push(Action::UntrackBranch {
+        branch: branch.clone(),
+    });
+    for child in &children {
+        plan.push(Action::SetParent {
+            branch: child.clone(),
+            parent: parent.clone(),
+        });
+    }
+
+    let review_to_close = metadata
+        .get_branch(&branch)
+        .and_then(|meta| meta.review_id.clone())
+        .filter(|_| close_review);
+    if let Some(review_id) = review_to_close {
+        plan.push(Action::CloseReview { review_id });
+    }
+    let planned = plan.simulate(&metadata);
+    plan::retarget_reviews(&mut plan, &planned, &children);
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+
+    progress!("🗑️  Deleting '{}'...", branch);
+    plan::execute(&plan, &mut metadata, None)?;
+
+    progress!("\n✨ Deleted '{}'", branch);
+    Ok(())
+}
@@ -0,0 +1,426 @@
+//! Implementation of the `bt doctor` command
+//!
+//! Diagnoses the repository setup: the git repository, the metadata and
+//! its consistency with the local branches, the remote, the provider
+//! token and any operation left in progress. Each check passes, warns or
+//! fails, with a hint on how to fix it.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::doctor::run_doctor;
+//!
+//! run_doctor(false)?;
+//! ```
+
+use crate::cli::output::{self, progress};
+use crate::cli::plan::REMOTE;
+use crate::core::metadata::{self, Metadata};
+use crate::core::{config, environment, git};
+use crate::error::{Error, Result};
+use crate::providers::{self, ProviderType};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Outcome of a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of one diagnostic check
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    status: CheckStatus,
+    message: String,
+    /// How to fix a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Run the doctor command
+///
+/// # Arguments
+///
+/// * `json` - Output JSON instead of text
+///
+/// # Errors
+///
+/// Returns an error if any check failed, after printing every result
+pub fn run_doctor(json: bool) -> Result<()> {
+    let checks = run_checks();
+
+    if json {
+        output::emit(&checks)?;
+    } else {
+        for check in &checks {
+            let marker = match check.status {
+                CheckStatus::Pass => "✓",
+                CheckStatus::Warn => "⚠️ ",
+                CheckStatus::Fail => "✗",
+            };
+            progress!("{} {}: {}", marker, check.name, check.message);
+            if let Some(hint) = &check.hint {
+                for line in hint.lines() {
+                    progress!("    {}", line);
+                }
+            }
+        }
+    }
+
+    let failed = count(&checks, CheckStatus::Fail);
+    let warned = count(&checks, CheckStatus::Warn);
+    if failed > 0 {
+        return Err(Error::other(format!(
+            "{} check(s) failed, {} warning(s)",
+            failed, warned
+        )));
+    }
+    if !json {
+        if warned > 0 {
+            progress!("\n✨ No problems found, {} warning(s)", warned);
+        } else {
+            progress!("\n✨ No problems found");
+        }
+    }
+    Ok(())
+}
+
+/// Count the checks with the given status
+fn count(checks: &[Check], status: CheckStatus) -> usize {
+    checks.iter().filter(|check| check.status == status).count()
+}
+
+/// Run every check, skipping those that depend on a failed one
+fn run_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    match environment::require_git_repository() {
+        Ok(root) => checks.push(Check::pass(
+            "repository",
+            format!(
+                "Git repository at {}",
+                root.canonicalize().unwrap_or(root).display()
+            ),
+        )),
+        Err(e) => {
+            checks.push(Check::fail(
+                "repository",
+                e.to_string(),
+                "Run bt from inside a git repository",
+            ));
+            return checks;
+        }
+    }
+
+    let metadata = match check_metadata() {
+        Ok(metadata) => {
+            checks.push(Check::pass(
+                "metadata",
+                format!(
+                    "Version {}, {} tracked branch(es)",
+                    metadata.version,
+                    metadata.branches.len()
+                ),
+            ));
+            Some(metadata)
+        }
+        Err(check) => {
+            checks.push(check);
+            None
+        }
+    };
+
+    checks.push(check_working_directory());
+    checks.push(check_rebase());
+
+    let Some(metadata) = metadata else {
+        return checks;
+    };
+    checks.push(check_base_branch(&metadata));
+    checks.push(check_consistency(&metadata));
+    checks.push(check_orphans(&metadata));
+    checks.push(check_remote());
+    checks.push(check_token(&metadata));
+    checks
+}
+
+/// Load the metadata, turning errors into a failed check
+fn check_metadata() -> std::result::Result<Metadata, Check> {
+    match metadata::load_metadata() {
+        Ok(metadata) => Ok(metadata),
+        Err(Error::MetadataNotFound) => Err(Check::fail(
+            "metadata",
+            "Repository is not initialized",
+            "Run 'bt init'",
+        )),
+        Err(e @ Error::UnsupportedMetadataVersion { .. }) => Err(Check::fail(
+            "metadata",
+            e.to_string(),
+            "Upgrade bt, or move .git/basalt away and run 'bt init' again",
+        )),
+        Err(e) => Err(Check::fail(
+            "metadata",
+            e.to_string(),
+            "Fix or remove .git/basalt/metadata.yml, then run 'bt init' again",
+        )),
+    }
+}
+
+fn check_working_directory() -> Check {
+    match environment::require_clean_working_directory() {
+        Ok(()) => Check::pass("working directory", "No uncommitted changes"),
+        Err(Error::UncommittedChanges { changes }) => Check::warn(
+            "working directory",
+            "Uncommitted changes",
+            format!(
+                "{}\nCommit or stash them before restacking",
+                changes.trim_start()
+            ),
+        ),
+        Err(e) => Check::fail("working directory", e.to_string(), "Check 'git status'"),
+    }
+}
+
+fn check_rebase() -> Check {
+    match environment::is_rebase_in_progress() {
+        Ok(false) => Check::pass("rebase", "No rebase in progress"),
+        Ok(true) => Check::warn(
+            "rebase",
+            format!(
+                "Rebase of '{}' in progress",
+                git::rebasing_branch().unwrap_or_else(|_| "HEAD".to_string())
+            ),
+            "Resolve the conflicts and run 'bt restack --continue', or run 'bt restack --abort'",
+        ),
+        Err(e) => Check::fail("rebase", e.to_string(), "Check 'git status'"),
+    }
+}
+
+fn check_base_branch(metadata: &Metadata) -> Check {
+    match git::local_branch_exists(&metadata.base_branch) {
+        Ok(true) => Check::pass("base branch", format!("'{}' exists", metadata.base_branch)),
+        Ok(false) => Check::fail(
+            "base branch",
+            format!("Base branch '{}' doesn't exist", metadata.base_branch),
+            format!(
+                "Create it with 'git branch {0} {1}/{0}', or set base_branch in .git/basalt/metadata.yml",
+                metadata.base_branch, REMOTE
+            ),
+        ),
+        Err(e) => Check::fail("base branch", e.to_string(), "Check 'git branch'"),
+    }
+}
+
+fn check_consistency(metadata: &Metadata) -> Check {
+    let problems = consistency_problems(metadata);
+    if problems.is_empty() {
+        return Check::pass(
+            "stack",
+            "Every branch's parent is tracked or the base branch",
+        );
+    }
+    Check::fail(
+        "stack",
+        problems.join("; "),
+        "Move the affected branches onto an existing branch with 'bt move <branch> --onto <parent>'",
+    )
+}
+
+/// Find tracked branches whose parent chain is broken
+///
+/// A parent must be the base branch or another tracked branch, and
+/// following parents must end at the base branch rather than loop.
+fn consistency_problems(metadata: &Metadata) -> Vec<String> {
+    let mut names: Vec<&String> = metadata.branches.keys().collect();
+    names.sort();
+
+    let mut problems = Vec::new();
+    for name in names {
+        let parent = &metadata.branches[name].parent;
+        if name == &metadata.base_branch {
+            problems.push(format!(
+                "base branch '{}' is tracked as a stack branch",
+                name
+            ));
+        } else if parent != &metadata.base_branch && !metadata.has_branch(parent) {
+            problems.push(format!("parent '{}' of '{}' is not tracked", parent, name));
+        } else if in_cycle(metadata, name) {
+            problems.push(format!("'{}' is its own ancestor", name));
+        }
+    }
+    problems
+}
+
+/// Check whether following the parents of `branch` leads back to it
+fn in_cycle(metadata: &Metadata, branch: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut current = branch;
+    while let Some(meta) = metadata.get_branch(current) {
+        if meta.parent == branch {
+            return true;
+        }
+        if !seen.insert(current) {
+            return false;
+        }
+        current = &meta.parent;
+    }
+    false
+}
+
+fn check_orphans(metadata: &Metadata) -> Check {
+    let mut missing = Vec::new();
+    for name in metadata.branches.keys() {
+        match git::local_branch_exists(name) {
+            Ok(true) => {}
+            Ok(false) => missing.push(name.clone()),
+            Err(e) => return Check::fail("branches", e.to_string(), "Check 'git branch'"),
+        }
+    }
+    missing.sort();
+
+    if missing.is_empty() {
+        return Check::pass("branches", "Every tracked branch exists");
+    }
+    Check::warn(
+        "branches",
+        format!("Tracked but deleted outside basalt: {}", missing.join(", ")),
+        "Recreate them, or remove their entries from .git/basalt/metadata.yml",
+    )
+}
+
+fn check_remote() -> Check {
+    match git::list_remotes() {
+        Ok(remotes) if !remotes.iter().any(|remote| remote == REMOTE) => {
+            return Check::warn(
+                "remote",
+                format!("No '{}' remote", REMOTE),
+                format!("Add one with 'git remote add {} <url>'", REMOTE),
+            );
+        }
+        Ok(_) => {}
+        Err(e) => return Check::fail("remote", e.to_string(), "Check 'git remote -v'"),
+    }
+
+    match git::check_remote(REMOTE) {
+        Ok(()) => Check::pass("remote", format!("'{}' is reachable", REMOTE)),
+        Err(e) => Check::fail(
+            "remote",
+            format!("Can't reach '{}': {}", REMOTE, e),
+            "Check your network connection and git credentials",
+        ),
+    }
+}
+
+fn check_token(metadata: &Metadata) -> Check {
+    match metadata.provider {
+        ProviderType::GitLab => {
+            let Some(token) = &metadata.auth_token else {
+                return Check::warn(
+                    "token",
+                    "No GitLab token stored",
+                    "Run 'bt submit' to authenticate and store a token",
+                );
+            };
+            let base_url = match metadata::get_base_url(metadata) {
+                Ok(base_url) => base_url,
+                Err(e) => {
+                    return Check::fail(
+                        "token",
+                        e.to_string(),
+                        "Set base_url in .git/basalt/metadata.yml",
+                    );
+                }
+            };
+
+            // A broken config is not this check's concern
+            let http = config::load_http_config(&base_url).unwrap_or_default();
+            let result = providers::gitlab::GitLabProvider::with_http_config(&base_url, &http)
+                .and_then(|mut gitlab| {
+                    gitlab.set_auth_token(token.clone());
+                    gitlab.verify_token()
+                });
+            match result {
+                Ok(()) => Check::pass(
+                    "token",
+                    format!(
+                        "GitLab token for {} is active with the 'api' scope",
+                        base_url
+                    ),
+                ),
+                Err(e) => Check::fail(
+                    "token",
+                    e.to_string(),
+                    format!(
+                        "Create a token with the 'api' scope at {}/-/user_settings/personal_access_tokens, then run 'bt submit' to store it",
+                        base_url.trim_end_matches('/')
+                    ),
+                ),
+            }
+        }
+        ProviderType::GitHub => Check::warn(
+            "token",
+            "GitHub tokens can't be checked yet",
+            "GitHub support is not implemented yet",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::metadata::BranchMetadata;
+
+    #[test]
+    fn test_consistency_problems() {
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        metadata.set_branch("a".to_string(), BranchMetadata::new("main".to_string()));
+        metadata.set_branch("b".to_string(), BranchMetadata::new("a".to_string()));
+        assert!(consistency_problems(&metadata).is_empty());
+
+        metadata.set_branch("c".to_string(), BranchMetadata::new("gone".to_string()));
+        metadata.set_branch("x".to_string(), BranchMetadata::new("y".to_string()));
+        metadata.set_branch("y".to_string(), BranchMetadata::new("x".to_string()));
+        assert_eq!(
+            consistency_problems(&metadata),
+            vec![
+                "parent 'gone' of 'c' is not tracked",
+                "'x' is its own ancestor",
+                "'y' is its own ancestor",
+            ]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Implementation of the `bt fold` command
+//!
+//! Folds the current branch into its parent, collapsing two levels of the
+//! stack into one. By default the parent branch absorbs the current branch's
+//! commits and the current branch is deleted. With `--keep`, the current
+//! branch absorbs its parent instead and the parent is deleted.
+//!
+//! In both cases:
+//! - Children of the deleted branch are reparented onto the surviving branch
+//! - Siblings whose base changed are restacked
+//! - The deleted branch's review is closed, and the surviving review is
+//!   retargeted if its parent changed
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::fold::run_fold;
+//!
+//! // Fold the current branch into its parent
+//! run_fold(false, None)?;
+//! ```
+
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, Rebase};
+use crate::core::{environment, git, metadata, stack};
+use crate::error::{Error, Result};
+use crate::providers::UpdateReviewParams;
+
+/// Run the fold command
+///
+/// # Arguments
+///
+/// * `keep` - Keep the current branch's name instead of the parent's
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The environment isn't ready for stack operations
+/// - The current branch isn't tracked, or its parent is the base branch
+/// - The current branch isn't based on the tip of its parent (needs restack)
+/// - A rebase stops on conflicts
+/// - Closing or retargeting a review fails
+pub fn run_fold(keep: bool, dry_run: Option<PlanFormat>) -> Result<()> {
+    environment::check_stack_operation_environment()?;
+
+    let current = git::get_current_branch()?;
+    let mut metadata = metadata::load_metadata()?;
+
+    let parent = metadata
+        .get_branch(&current)
+        .map(|meta| meta.parent.clone())
+        .ok_or_else(|| {
+            Error::invalid_stack(format!("Branch '{}' is not tracked by basalt", current))
+        })?;
+
+    if !metadata.has_branch(&parent) {
+        return Err(Error::invalid_stack(format!(
+            "Cannot fold '{}' into '{}': it is not a stacked branch",
+            current, parent
+        )));
+    }
+
+    let parent_tip = git::get_branch_commit(&parent)?;
+    if git::merge_base(&parent, &current)? != parent_tip {
+        return Err(Error::invalid_stack(format!(
+            "Branch '{}' is not based on the tip of '{}'. Run 'bt restack' first.",
+            current, parent
+        )));
+    }
+
+    let (survivor, removed) = if keep {
+        (current.clone(), parent.clone())
+    } else {
+        (parent.clone(), current.clone())
+    };
+    let removed_meta = metadata
+        .get_branch(&removed)
+        .cloned()
+        .ok_or_else(|| Error::metadata(format!("Branch '{}' is not tracked", removed)))?;
+
+    let mut plan = Plan::new("fold");
+
+    // Siblings are based on the parent's current tip, which is about to
+    // become the current branch's tip; replay them onto it first
+    let mut rebases = Vec::new();
+    for sibling in stack::children(&metadata, &parent) {
+        if sibling == current {
+            continue;
+        }
+        rebases.push(Rebase {
+            branch: sibling.clone(),
+            onto: current.clone(),
+            upstream: parent.clone(),
+        });
+        for name in stack::descendants(&metadata, &sibling) {
+            if let Some(meta) = metadata.get_branch(&name) {
+                rebases.push(Rebase {
+                    branch: name.clone(),
+                    onto: meta.parent.clone(),
+                    upstream: meta.parent.clone(),
+                });
+            }
+        }
+    }
+    if !rebases.is_empty() {
+        plan.push(Action::Restack { rebases });
+    }
+
+    if keep {
+        plan.push(Action::DeleteBranch {
+            branch: parent.clone(),
+        });
+    } else {
+        plan.push(Action::SetBranchCommit {
+            branch: parent.clone(),
+            commit: git::get_branch_commit(&current)?,
+        });
+        plan.push(Action::Checkout {
+            branch: parent.clone(),
+        });
+        plan.push(Action::DeleteBranch {
+            branch: current.clone(),
+        });
+    }
+
+    plan.push(Action::UntrackBranch {
+        branch: removed.clone(),
+    });
+    if keep {
+        plan.push(Action::SetParent {
+            branch: survivor.clone(),
+            parent: removed_meta.parent.clone(),
+        });
+    }
+    for child in stack::children(&metadata, &removed) {
+        if child != survivor {
+            plan.push(Action::SetParent {
+                branch: child,
+                parent: survivor.clone(),
+            });
+        }
+    }
+    plan.push(Action::Checkout {
+        branch: survivor.clone(),
+    });
+
+    if let Some(review_id) = removed_meta.review_id.clone() {
+        plan.push(Action::CloseReview { review_id });
+    }
+    let survivor_review = metadata
+        .get_branch(&survivor)
+        .and_then(|meta| meta.review_id.clone());
+    if let Some(review_id) = survivor_review.filter(|_| keep) {
+        plan.push(Action::UpdateReview(UpdateReviewParams {
+            review_id,
+            target_branch: Some(removed_meta.parent.clone()),
+            ..Default::default()
+        }));
+    }
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+
+    progress!("🪗 Folding '{}' into '{}'...", current, parent);
+    plan::execute(&plan, &mut metadata, None)?;
+
+    progress!("\n✨ Folded '{}' into '{}'", removed, survivor);
+    Ok(())
+}
@@ -23,7 +23,8 @@
 //! run_init(None, Some("develop".to_string()))?;
 //! ```
 
-use crate::core::{environment, git, metadata};
+use crate::cli::output::progress;
+use crate::core::{config, environment, git, metadata};
 use crate::error::{Error, Result};
 use crate::providers::{Provider, ProviderType};
 
@@ -64,19 +65,19 @@ pub fn run_init(
         return Err(Error::AlreadyInitialized { path: basalt_dir });
     }
 
-    println!("🚀 Initializing basalt repository...\n");
+    progress!("🚀 Initializing basalt repository...\n");
 
     // Detect or validate provider
     let provider = detect_provider(provider_override)?;
-    println!("✓ Provider: {}", provider);
+    progress!("✓ Provider: {}", provider);
 
     // Detect or use base branch
     let base_branch = detect_base_branch(base_branch_override)?;
-    println!("✓ Base branch: {}", base_branch);
+    progress!("✓ Base branch: {}", base_branch);
 
     // Create basalt directory
     let basalt_dir = environment::create_basalt_dir()?;
-    println!("✓ Created metadata directory: {}", basalt_dir.display());
+    progress!("✓ Created metadata directory: {}", basalt_dir.display());
 
     // Extract provider base URL and project path from git remote (if available)
     // If no remote exists, these will be None and extracted later when needed
@@ -90,30 +91,30 @@ pub fn run_init(
 
     // Authenticate with provider and store token (unless skipped for testing)
     if !skip_auth {
-        println!();
+        progress!();
 
         // Only authenticate if we have remote info (base_url and project_path)
         if let (Some(url), Some(path)) = (&base_url, &project_path) {
             let auth_token = authenticate_provider(provider, url, path)?;
             metadata.auth_token = Some(auth_token);
         } else {
-            println!("⚠️  No git remote found - skipping authentication");
-            println!("   Authentication will be required when you first use bt commands");
+            progress!("⚠️  No git remote found - skipping authentication");
+            progress!("   Authentication will be required when you first use bt commands");
         }
     }
 
     metadata::save_metadata(&metadata)?;
-    println!(
+    progress!(
         "✓ Saved metadata: {}",
         basalt_dir.join("metadata.yml").display()
     );
 
-    println!("\n✨ Successfully initialized basalt!");
-    println!("\nRepository root: {}", repo_root.display());
-    println!("Next steps:");
-    println!("  1. Create a branch: git checkout -b feature-part-1");
-    println!("  2. Make changes and commit");
-    println!("  3. Submit your stack: bt submit");
+    progress!("\n✨ Successfully initialized basalt!");
+    progress!("\nRepository root: {}", repo_root.display());
+    progress!("Next steps:");
+    progress!("  1. Create a branch: git checkout -b feature-part-1");
+    progress!("  2. Make changes and commit");
+    progress!("  3. Submit your stack: bt submit");
 
     Ok(())
 }
@@ -136,12 +137,12 @@ fn detect_provider(provider_override: Option<String>) -> Result<ProviderType> {
     if let Some(provider_str) = provider_override {
         // Use explicit provider
         let provider = ProviderType::from_str(&provider_str)?;
-        println!("  Using explicitly specified provider");
+        progress!("  Using explicitly specified provider");
         return Ok(provider);
     }
 
     // Auto-detect from git remote
-    println!("  Auto-detecting provider from git remote...");
+    progress!("  Auto-detecting provider from git remote...");
 
     // Get list of remotes
     let remotes = git::list_remotes()?;
@@ -160,7 +161,7 @@ fn detect_provider(provider_override: Option<String>) -> Result<ProviderType> {
     };
 
     let remote_url = git::get_remote_url(remote_name)?;
-    println!("  Checking remote '{}': {}", remote_name, remote_url);
+    progress!("  Checking remote '{}': {}", remote_name, remote_url);
 
     ProviderType::from_remote_url(&remote_url)
 }
@@ -179,11 +180,11 @@ fn detect_provider(provider_override: Option<String>) -> Result<ProviderType> {
 /// Returns an error if git commands fail
 fn detect_base_branch(base_branch_override: Option<String>) -> Result<String> {
     if let Some(branch) = base_branch_override {
-        println!("  Using explicitly specified base branch");
+        progress!("  Using explicitly specified base branch");
         return Ok(branch);
     }
 
-    println!("  Auto-detecting base branch...");
+    progress!("  Auto-detecting base branch...");
     git::detect_default_branch()
 }
 
@@ -205,11 +206,14 @@ fn authenticate_provider(
     base_url: &str,
     project_path: &str,
 ) -> Result<String> {
-    println!("🔐 Authenticating with {}...", provider);
+    progress!("🔐 Authenticating with {}...", provider);
 
     match provider {
         ProviderType::GitLab => {
-            let mut gitlab = crate::providers::gitlab::GitLabProvider::new(base_url)?;
+            let mut gitlab = crate::providers::gitlab::GitLabProvider::with_http_config(
+                base_url,
+                &config::load_http_config(base_url)?,
+            )?;
             gitlab.set_project_path(project_path.to_string());
             gitlab.authenticate()?;
 
@@ -217,7 +221,7 @@ fn authenticate_provider(
                 Error::config("Failed to get authentication token after successful authentication")
             })?;
 
-            println!("✓ Successfully authenticated with {}", provider);
+            progress!("✓ Successfully authenticated with {}", provider);
             Ok(token)
         }
         ProviderType::GitHub => {
@@ -0,0 +1,236 @@
+//! Implementation of the `bt land` command
+//!
+//! Merges the current stack bottom-up through the provider. For each branch:
+//! 1. Merge its review (merge, squash or rebase)
+//! 2. Wait until the provider reports it as merged
+//! 3. Fast-forward the local base branch from the remote
+//! 4. Reparent its children onto the base branch, restack and push them
+//! 5. Retarget the children's reviews to the base branch
+//! 6. Delete the landed branch locally
+//!
+//! Each review's checks (approvals, pipeline, conflicts) are verified while
+//! planning and again right before merging it, since landing the branches
+//! below changes it. Before merging, landing waits for a running pipeline
+//! to finish, then stops at the first review that can't be merged. The
+//! merge is pinned to the commit basalt last pushed for the branch (or its
+//! local commit), so the provider refuses it if the review's head has
+//! moved since.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::land::run_land;
+//! use crate::providers::MergeMethod;
+//!
+//! // Land every branch up to the current one, squashing each review
+//! run_land(true, MergeMethod::Squash, None)?;
+//! ```
+
+use crate::cli::common;
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, REMOTE, Rebase};
+use crate::core::metadata::Metadata;
+use crate::core::{environment, git, metadata, stack};
+use crate::error::{Error, Result};
+use crate::providers::{MergeMethod, Provider, ReviewState};
+
+/// Run the land command
+///
+/// # Arguments
+///
+/// * `all` - Land every branch from the bottom of the stack up to the
+///   current branch, instead of only the bottom-most one
+/// * `method` - How reviews are merged
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The environment isn't ready for stack operations
+/// - A branch to land has no open, ready review
+/// - The provider refuses or doesn't finish a merge
+/// - Updating the local stack afterwards fails
+pub fn run_land(all: bool, method: MergeMethod, dry_run: Option<PlanFormat>) -> Result<()> {
+    environment::check_stack_operation_environment()?;
+
+    let current = git::get_current_branch()?;
+    let mut metadata = metadata::load_metadata()?;
+
+    if !metadata.has_branch(&current) {
+        return Err(Error::invalid_stack(format!(
+            "Branch '{}' is not tracked by basalt",
+            current
+        )));
+    }
+
+    let mut to_land: Vec<String> = stack::ancestors(&metadata, &current)
+        .into_iter()
+        .rev()
+        .collect();
+    to_land.push(current.clone());
+    if !all {
+        to_land.truncate(1);
+    }
+
+    // Check every review exists before touching anything
+    for branch in &to_land {
+        if metadata
+            .get_branch(branch)
+            .and_then(|meta| meta.review_id.as_ref())
+            .is_none()
+        {
+            return Err(Error::ReviewNotFound {
+                branch: branch.clone(),
+            });
+        }
+    }
+
+    let mut provider = common::connect_provider(&mut metadata)?;
+
+    let mut plan = Plan::new("land");
+    for branch in &to_land {
+        plan_land_branch(&mut plan, provider.as_mut(), &metadata, branch, method)?;
+    }
+
+    let return_to = if to_land.contains(&current) {
+        metadata.base_branch.clone()
+    } else {
+        current
+    };
+    plan.push(Action::Checkout { branch: return_to });
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+    plan::execute(&plan, &mut metadata, Some(provider))?;
+
+    progress!("\n✨ Landed {} branch(es)", to_land.len());
+    Ok(())
+}
+
+/// Plan merging one branch's review and updating the local stack
+///
+/// Branches planned to land before this one are taken into account, so
+/// their children are already stacked on the base branch.
+fn plan_land_branch(
+    plan: &mut Plan,
+    provider: &mut dyn Provider,
+    original: &Metadata,
+    branch: &str,
+    method: MergeMethod,
+) -> Result<()> {
+    let metadata = &plan.simulate(original);
+    let base_branch = metadata.base_branch.clone();
+    let meta = metadata
+        .get_branch(branch)
+        .cloned()
+        .ok_or_else(|| Error::invalid_stack(format!("Branch '{}' is not tracked", branch)))?;
+    let review_id = meta
+        .review_id
+        .clone()
+        .ok_or_else(|| Error::ReviewNotFound {
+            branch: branch.to_string(),
+        })?;
+
+    if meta.parent != base_branch {
+        return Err(Error::invalid_stack(format!(
+            "Cannot land '{}': its parent '{}' hasn't landed yet",
+            branch, meta.parent
+        )));
+    }
+
+    let review = provider.get_review(&review_id)?;
+    match review.state {
+        ReviewState::Closed => {
+            return Err(Error::provider_op(format!(
+                "Cannot land '{}': review {} is closed",
+                branch, review_id
+            )));
+        }
+        ReviewState::Open if review.draft => {
+            return Err(Error::provider_op(format!(
+                "Cannot land '{}': review {} is still a draft",
+                branch, review_id
+            )));
+        }
+        ReviewState::Open => {
+            // Landing waits for pipelines still running, e.g. the ones that
+            // restacking the branches below will start
+            plan::require_mergeable(provider, branch, &review_id, method, false)?;
+            plan.push(Action::MergeReview {
+                branch: branch.to_string(),
+                review_id,
+                method,
+            });
+        }
+        // Already merged: only the local stack needs updating
+        ReviewState::Merged => {}
+    }
+
+    plan.push(Action::Fetch {
+        remote: REMOTE.to_string(),
+    });
+    plan.push(Action::FastForward {
+        branch: base_branch.clone(),
+        target: format!("{}/{}", REMOTE, base_branch),
+    });
+
+    // Children are based on the landed branch; replay them onto the base
+    // branch while the landed branch still exists
+    let children = stack::children(metadata, branch);
+    let mut rebases = Vec::new();
+    for child in &children {
+        rebases.push(Rebase {
+            branch: child.clone(),
+            onto: base_branch.clone(),
+            upstream: branch.to_string(),
+        });
+        for name in stack::descendants(metadata, child) {
+            if let Some(meta) = metadata.get_branch(&name) {
+                rebases.push(Rebase {
+                    branch: name.clone(),
+                    onto: meta.parent.clone(),
+                    upstream: meta.parent.clone(),
+                });
+            }
+        }
+    }
+
+    plan.push(Action::UntrackBranch {
+        branch: branch.to_string(),
+    });
+    for child in &children {
+        plan.push(Action::SetParent {
+            branch: child.clone(),
+            parent: base_branch.clone(),
+        });
+    }
+
+    if !rebases.is_empty() {
+        let mut pushed = Vec::new();
+        for rebase in &rebases {
+            if git::has_upstream(&rebase.branch)? {
+                pushed.push(rebase.branch.clone());
+            }
+        }
+        let push = if pushed.is_empty() {
+            None
+        } else {
+            Some(plan::push_action(original, &pushed, false)?)
+        };
+        plan.push(Action::Restack { rebases });
+        plan.actions.extend(push);
+    }
+
+    let planned = plan.simulate(original);
+    plan::retarget_reviews(plan, &planned, &children);
+
+    plan.push(Action::Checkout {
+        branch: base_branch,
+    });
+    plan.push(Action::DeleteBranch {
+        branch: branch.to_string(),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,300 @@
+//! Implementation of the `bt log` command
+//!
+//! Shows every tracked stack in the repository as a tree rooted at the base
+//! branch, similar to Charcoal's `log` / `log short` / `log long`.
+//!
+//! Each branch shows its review id and state, whether it needs a restack,
+//! and (in graph and long modes) its own commits.
+//!
+//! Review states come from the provider. The log never prompts for
+//! credentials: states are only read with a token cached by an earlier
+//! command, and are left out if that fails. `--states` authenticates like
+//! other provider commands and reports failures.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::log::{run_log, LogFormat};
+//!
+//! // Graph of the current stack only
+//! run_log(LogFormat::Graph, true, false, false)?;
+//! ```
+
+use crate::cli::common;
+use crate::cli::output::{self, progress};
+use crate::core::git::{self, CommitInfo};
+use crate::core::metadata::{self, Metadata};
+use crate::core::{environment, stack};
+use crate::error::Result;
+use crate::providers::ReviewState;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Layout of the log output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Branch tree with each branch's commits
+    Graph,
+    /// Branch tree, one line per branch
+    Short,
+    /// Detailed block per branch
+    Long,
+}
+
+/// A branch as shown in the log
+#[derive(Debug, Serialize)]
+struct BranchEntry {
+    name: String,
+    parent: String,
+    current: bool,
+    needs_restack: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    review: Option<ReviewEntry>,
+    commits: Vec<CommitInfo>,
+}
+
+/// Review information for a branch
+#[derive(Debug, Serialize)]
+struct ReviewEntry {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<ReviewState>,
+}
+
+/// JSON output of the log command
+#[derive(Debug, Serialize)]
+struct LogOutput<'a> {
+    base_branch: &'a str,
+    current_branch: Option<&'a str>,
+    branches: Vec<&'a BranchEntry>,
+}
+
+/// Run the log command
+///
+/// # Arguments
+///
+/// * `format` - Output layout
+/// * `current_stack_only` - Only show the current branch's ancestors and descendants
+/// * `fetch_states` - Authenticate to fetch review states, instead of only
+///   using a cached token
+/// * `json` - Output JSON instead of text
+///
+/// # Errors
+///
+/// Returns an error if the repository isn't initialized or git fails.
+/// Failing to fetch review states only prints a warning, with `fetch_states`.
+pub fn run_log(
+    format: LogFormat,
+    current_stack_only: bool,
+    fetch_states: bool,
+    json: bool,
+) -> Result<()> {
+    environment::check_basic_environment()?;
+
+    let mut metadata = metadata::load_metadata()?;
+    let current = git::get_current_branch().ok();
+
+    let visible: Option<HashSet<String>> = match (&current, current_stack_only) {
+        (Some(branch), true) => {
+            let mut set: HashSet<String> = stack::ancestors(&metadata, branch)
+                .into_iter()
+                .chain(stack::descendants(&metadata, branch))
+                .collect();
+            set.insert(branch.clone());
+            Some(set)
+        }
+        _ => None,
+    };
+
+    let mut order = Vec::new();
+    for root in stack::roots(&metadata) {
+        collect_order(&metadata, &root, &visible, &mut order);
+    }
+
+    let mut states = common::fetch_review_states(&mut metadata, &order, fetch_states);
+    let mut entries = HashMap::new();
+    for name in &order {
+        let entry = build_entry(&metadata, name, current.as_deref(), &mut states)?;
+        entries.insert(name.clone(), entry);
+    }
+
+    if json {
+        let output = LogOutput {
+            base_branch: &metadata.base_branch,
+            current_branch: current.as_deref(),
+            branches: order.iter().map(|name| &entries[name]).collect(),
+        };
+        output::emit(&output)?;
+        return Ok(());
+    }
+
+    match format {
+        LogFormat::Long => {
+            for name in &order {
+                print_long(&entries[name]);
+            }
+        }
+        LogFormat::Graph | LogFormat::Short => {
+            for root in stack::roots(&metadata) {
+                let children = visible_children(&metadata, &root, &entries);
+                if children.is_empty() && root != metadata.base_branch {
+                    continue;
+                }
+                progress!("{}", root);
+                for (index, child) in children.iter().enumerate() {
+                    let is_last = index + 1 == children.len();
+                    print_tree(&metadata, &entries, child, "", is_last, format);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect visible branches below `branch` in depth-first order
+fn collect_order(
+    metadata: &Metadata,
+    branch: &str,
+    visible: &Option<HashSet<String>>,
+    order: &mut Vec<String>,
+) {
+    for child in stack::children(metadata, branch) {
+        if visible.as_ref().is_some_and(|set| !set.contains(&child)) {
+            continue;
+        }
+        order.push(child.clone());
+        collect_order(metadata, &child, visible, order);
+    }
+}
+
+/// Get the children of `branch` that are part of the output
+fn visible_children(
+    metadata: &Metadata,
+    branch: &str,
+    entries: &HashMap<String, BranchEntry>,
+) -> Vec<String> {
+    stack::children(metadata, branch)
+        .into_iter()
+        .filter(|child| entries.contains_key(child))
+        .collect()
+}
+
+/// Gather the log information for one branch
+fn build_entry(
+    metadata: &Metadata,
+    name: &str,
+    current: Option<&str>,
+    states: &mut HashMap<String, ReviewState>,
+) -> Result<BranchEntry> {
+    let meta = metadata
+        .get_branch(name)
+        .expect("log entries are built from tracked branches");
+
+    let exists = git::local_branch_exists(name)? && git::local_branch_exists(&meta.parent)?;
+    let commits = if exists {
+        let base = git::merge_base(&meta.parent, name)?;
+        git::list_commits(&base, name)?
+    } else {
+        Vec::new()
+    };
+
+    Ok(BranchEntry {
+        name: name.to_string(),
+        parent: meta.parent.clone(),
+        current: current == Some(name),
+        needs_restack: exists && stack::needs_restack(metadata, name)?,
+        review: meta.review_id.as_ref().map(|id| ReviewEntry {
+            id: id.clone(),
+            url: meta.review_url.clone(),
+            state: states.remove(name),
+        }),
+        commits,
+    })
+}
+
+/// Format the one-line summary of a branch
+fn summary(entry: &BranchEntry) -> String {
+    let marker = if entry.current { "◉" } else { "◯" };
+    let mut line = format!("{} {}", marker, entry.name);
+
+    if let Some(review) = &entry.review {
+        match review.state {
+            Some(state) => line.push_str(&format!("  {} ({})", review.id, state)),
+            None => line.push_str(&format!("  {}", review.id)),
+        }
+    }
+    if entry.needs_restack {
+        line.push_str("  (needs restack)");
+    }
+
+    line
+}
+
+/// Print a branch and its children as a tree
+fn print_tree(
+    metadata: &Metadata,
+    entries: &HashMap<String, BranchEntry>,
+    name: &str,
+    prefix: &str,
+    is_last: bool,
+    format: LogFormat,
+) {
+    let entry = &entries[name];
+    let connector = if is_last { "└── " } else { "├── " };
+    progress!("{}{}{}", prefix, connector, summary(entry));
+
+    let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+    let children = visible_children(metadata, name, entries);
+
+    if format == LogFormat::Graph {
+        let rail = if children.is_empty() { "  " } else { "│ " };
+        for commit in entry.commits.iter().rev() {
+            progress!(
+                "{}{}{} {}",
+                child_prefix,
+                rail,
+                short_sha(&commit.sha),
+                commit.subject
+            );
+        }
+    }
+
+    for (index, child) in children.iter().enumerate() {
+        let is_last = index + 1 == children.len();
+        print_tree(metadata, entries, child, &child_prefix, is_last, format);
+    }
+}
+
+/// Print the detailed block for a branch
+fn print_long(entry: &BranchEntry) {
+    progress!("{}", summary(entry));
+    progress!("  parent:  {}", entry.parent);
+    if let Some(review) = &entry.review {
+        let state = review
+            .state
+            .map(|state| format!(" ({})", state))
+            .unwrap_or_default();
+        let url = review
+            .url
+            .as_ref()
+            .map(|url| format!(" {}", url))
+            .unwrap_or_default();
+        progress!("  review:  {}{}{}", review.id, state, url);
+    }
+    if entry.needs_restack {
+        progress!("  status:  needs restack");
+    }
+    progress!("  commits:");
+    for commit in entry.commits.iter().rev() {
+        progress!("    {} {}", short_sha(&commit.sha), commit.subject);
+    }
+    progress!();
+}
+
+/// Abbreviate a commit SHA for display
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
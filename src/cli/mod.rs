@@ -9,10 +9,25 @@
 //! - Command implementations live here in individual modules
 //! - Commands delegate to core logic in `crate::core`
 //! - Commands use providers through the provider abstraction
+//! - Mutating commands build a [`plan::Plan`] that is either printed
+//!   (`--dry-run`) or applied
 
+pub mod comments;
+pub mod common;
+pub mod delete;
+pub mod doctor;
+pub mod fold;
 pub mod init;
-
-// Future command modules:
-// pub mod submit;
-// pub mod restack;
-// pub mod status;
+pub mod land;
+pub mod log;
+pub mod move_branch;
+pub mod oplog;
+pub mod output;
+pub mod plan;
+pub mod rename;
+pub mod restack;
+pub mod split;
+pub mod squash;
+pub mod status;
+pub mod submit;
+pub mod undo;
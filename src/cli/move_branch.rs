@@ -0,0 +1,124 @@
+//! Implementation of the `bt move` command
+//!
+//! Moves a branch, together with everything stacked on top of it, onto a
+//! new parent branch. This:
+//! - Updates the branch's parent in metadata
+//! - Rebases the branch onto the new parent
+//! - Rebases all descendants onto their (rewritten) parents
+//! - Retargets the branch's review to the new parent
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::move_branch::run_move;
+//!
+//! // Move the current branch onto main
+//! run_move(None, "main".to_string(), None)?;
+//! ```
+
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, Rebase};
+use crate::core::{environment, git, metadata, stack};
+use crate::error::{Error, Result};
+use crate::providers::UpdateReviewParams;
+
+/// Run the move command
+///
+/// # Arguments
+///
+/// * `branch` - Branch to move (defaults to the current branch)
+/// * `onto` - New parent branch
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The environment isn't ready for stack operations
+/// - Either branch doesn't exist
+/// - The move would create a cycle (onto is the branch or one of its descendants)
+/// - A rebase stops on conflicts
+/// - Retargeting the review fails
+pub fn run_move(branch: Option<String>, onto: String, dry_run: Option<PlanFormat>) -> Result<()> {
+    environment::check_stack_operation_environment()?;
+
+    let original_branch = git::get_current_branch()?;
+    let branch = branch.unwrap_or_else(|| original_branch.clone());
+    let mut metadata = metadata::load_metadata()?;
+
+    if branch == metadata.base_branch {
+        return Err(Error::invalid_stack(format!(
+            "Cannot move the base branch '{}'",
+            branch
+        )));
+    }
+    for name in [&branch, &onto] {
+        if !git::local_branch_exists(name)? {
+            return Err(Error::BranchNotFound {
+                branch: name.clone(),
+            });
+        }
+    }
+    if onto == branch || stack::is_descendant(&metadata, &onto, &branch) {
+        return Err(Error::invalid_stack(format!(
+            "Cannot move '{}' onto '{}': '{}' is part of its upstack",
+            branch, onto, onto
+        )));
+    }
+
+    let old_parent = metadata
+        .get_branch(&branch)
+        .map(|meta| meta.parent.clone())
+        .unwrap_or_else(|| metadata.base_branch.clone());
+
+    let mut plan = Plan::new("move");
+    plan.push(Action::SetParent {
+        branch: branch.clone(),
+        parent: onto.clone(),
+    });
+
+    // The upstack keeps its parents and is replayed onto the moved branch
+    let mut rebases = vec![Rebase {
+        branch: branch.clone(),
+        onto: onto.clone(),
+        upstream: git::merge_base(&old_parent, &branch)?,
+    }];
+    for name in stack::descendants(&metadata, &branch) {
+        if let Some(meta) = metadata.get_branch(&name) {
+            rebases.push(Rebase {
+                branch: name.clone(),
+                onto: meta.parent.clone(),
+                upstream: meta.parent.clone(),
+            });
+        }
+    }
+    plan.push(Action::Restack { rebases });
+    plan.push(Action::Checkout {
+        branch: original_branch,
+    });
+
+    if let Some(review_id) = metadata
+        .get_branch(&branch)
+        .and_then(|meta| meta.review_id.clone())
+    {
+        plan.push(Action::UpdateReview(UpdateReviewParams {
+            review_id,
+            target_branch: Some(onto.clone()),
+            ..Default::default()
+        }));
+    }
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+
+    progress!(
+        "🚚 Moving '{}' from '{}' onto '{}'...",
+        branch,
+        old_parent,
+        onto
+    );
+    plan::execute(&plan, &mut metadata, None)?;
+
+    progress!("\n✨ Moved '{}' onto '{}'", branch, onto);
+    Ok(())
+}
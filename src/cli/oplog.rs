@@ -0,0 +1,96 @@
+//! Implementation of the `bt oplog` command
+//!
+//! Lists the operations recorded in the operation log, newest first, with
+//! the branches each one changed. The newest entry is the one `bt undo`
+//! reverts. With `--output json`, the entries are emitted as a JSON array.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::oplog::run_oplog;
+//!
+//! run_oplog()?;
+//! ```
+
+use crate::cli::output::{self, progress};
+use crate::core::environment;
+use crate::core::oplog::{self, RefChange};
+use crate::error::Result;
+
+/// Run the oplog command
+///
+/// # Errors
+///
+/// Returns an error if the repository isn't initialized or the log can't
+/// be read
+pub fn run_oplog() -> Result<()> {
+    environment::check_basic_environment()?;
+
+    let entries = oplog::list_entries()?;
+    if output::is_json() {
+        let newest_first: Vec<_> = entries.iter().rev().collect();
+        return output::emit(&newest_first);
+    }
+    if entries.is_empty() {
+        progress!("No operations recorded yet");
+        return Ok(());
+    }
+
+    for (index, entry) in entries.iter().rev().enumerate() {
+        if index > 0 {
+            progress!();
+        }
+        progress!(
+            "📜 #{}  {}  bt {}",
+            entry.id,
+            entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+            entry.command
+        );
+        for change in &entry.refs {
+            progress!("   {}", describe_change(change));
+        }
+        for change in &entry.remote_changes {
+            progress!("   {} (remote)", change);
+        }
+    }
+
+    Ok(())
+}
+
+/// Describe how an operation changed a branch
+fn describe_change(change: &RefChange) -> String {
+    let short = |sha: &String| sha[..7.min(sha.len())].to_string();
+    let description = match (&change.before, &change.after) {
+        (Some(before), Some(after)) => format!("{} → {}", short(before), short(after)),
+        (Some(before), None) => format!("deleted (was {})", short(before)),
+        (None, Some(after)) => format!("created at {}", short(after)),
+        (None, None) => "unchanged".to_string(),
+    };
+    let pushed = if change.pushed { "  (pushed)" } else { "" };
+
+    format!("{}  {}{}", change.branch, description, pushed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_change() {
+        let change = RefChange {
+            branch: "a".to_string(),
+            before: Some("1234567890".to_string()),
+            after: Some("abcdef0123".to_string()),
+            pushed: true,
+        };
+        assert_eq!(describe_change(&change), "a  1234567 → abcdef0  (pushed)");
+
+        let created = RefChange {
+            branch: "b".to_string(),
+            before: None,
+            after: Some("abcdef0123".to_string()),
+            pushed: false,
+        };
+        assert_eq!(describe_change(&created), "b  created at abcdef0");
+    }
+}
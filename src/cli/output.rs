@@ -0,0 +1,155 @@
+//! Output format shared by all commands
+//!
+//! By default commands print human-readable progress to stdout. With the
+//! global `--output json`, stdout carries exactly one JSON document per
+//! command instead:
+//!
+//! - read-only commands (`status`, `log`, `comments`, `oplog`, `doctor`)
+//!   emit their usual `--json` output
+//! - `--dry-run` emits the plan
+//! - mutating commands emit `{"command": ..., "actions": [...]}`, listing
+//!   the actions they applied
+//!
+//! Progress messages printed with [`progress!`] go to stderr in JSON mode,
+//! and errors are written to stderr as `{"kind", "message", "exit_code"}`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::output::{self, progress};
+//!
+//! progress!("✓ Rebased {}", branch);
+//! if output::is_json() {
+//!     output::emit(&entries)?;
+//! }
+//! ```
+
+use crate::error::Result;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// How command results are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// A single JSON document on stdout
+    Json,
+}
+
+/// Format selected on the command line
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Whether the command already wrote its JSON document
+static EMITTED: AtomicBool = AtomicBool::new(false);
+
+/// Actions applied so far, reported when the command finishes
+static APPLIED: Mutex<Vec<serde_json::Value>> = Mutex::new(Vec::new());
+
+/// Set the output format for the rest of the process
+///
+/// Only the first call has an effect.
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+/// Check whether JSON output was requested
+pub fn is_json() -> bool {
+    FORMAT.get().copied().unwrap_or_default() == OutputFormat::Json
+}
+
+/// Print a progress message
+///
+/// Goes to stdout for text output and to stderr for JSON output, so that
+/// stdout only carries the JSON document.
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if $crate::cli::output::is_json() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use progress;
+
+/// Write the command's JSON document to stdout
+///
+/// # Errors
+///
+/// Returns an error if the value can't be serialized
+pub fn emit<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    EMITTED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Check whether the command already wrote its JSON document
+pub fn emitted() -> bool {
+    EMITTED.load(Ordering::SeqCst)
+}
+
+/// Remember an applied action for the command's result
+///
+/// # Errors
+///
+/// Returns an error if the action can't be serialized
+pub fn record_action<T: Serialize>(action: &T) -> Result<()> {
+    let value = serde_json::to_value(action)?;
+    APPLIED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(value);
+    Ok(())
+}
+
+/// Result of a command that didn't emit its own JSON document
+#[derive(Debug, Serialize)]
+struct CommandResult<'a> {
+    command: &'a str,
+    actions: Vec<serde_json::Value>,
+}
+
+/// Emit the actions a command applied, unless it emitted a document
+///
+/// # Errors
+///
+/// Returns an error if the result can't be serialized
+pub fn finish(command: &str) -> Result<()> {
+    if emitted() {
+        return Ok(());
+    }
+    let actions = std::mem::take(
+        &mut *APPLIED
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+    emit(&CommandResult { command, actions })
+}
+
+/// An error as written to stderr with `--output json`
+#[derive(Debug, Serialize)]
+struct ErrorOutput<'a> {
+    kind: &'a str,
+    message: String,
+    exit_code: i32,
+}
+
+/// Write an error to stderr in the selected format
+pub fn print_error(kind: &str, message: String, exit_code: i32) {
+    if !is_json() {
+        eprintln!("Error: {}", message);
+        return;
+    }
+    let error = ErrorOutput {
+        kind,
+        message,
+        exit_code,
+    };
+    match serde_json::to_string_pretty(&error) {
+        Ok(json) => eprintln!("{}", json),
+        Err(_) => eprintln!("Error: {}", error.message),
+    }
+}
@@ -0,0 +1,1103 @@
+//! Plans for mutating commands
+//!
+//! Commands that change branches, metadata or reviews don't change anything
+//! while they inspect the repository. Instead they build a [`Plan`]: the
+//! ordered list of [`Action`]s they intend to take. The plan is then either
+//! printed (`--dry-run`) or applied by [`execute`].
+//!
+//! Building a plan may read from git and from the provider, and may prompt
+//! the user, but never writes anything.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::plan::{self, Action, Plan};
+//!
+//! let mut plan = Plan::new("delete");
+//! plan.push(Action::DeleteBranch { branch: "feature".to_string() });
+//! plan.push(Action::UntrackBranch { branch: "feature".to_string() });
+//!
+//! match dry_run {
+//!     Some(format) => plan.print(format)?,
+//!     None => plan::execute(&plan, &mut metadata, None)?,
+//! }
+//! ```
+
+use crate::cli::common;
+use crate::cli::output::{self, progress};
+use crate::core::git::{self, PushRef};
+use crate::core::metadata::{self, BranchMetadata, Metadata};
+use crate::core::navigation;
+use crate::core::oplog::{self, Snapshot};
+use crate::core::rebase;
+use crate::error::{Error, Result};
+use crate::providers::{
+    CreateReviewParams, MergeMethod, MergeReviewParams, MergeStatus, Provider, ReviewChecks,
+    ReviewState, UpdateReviewParams,
+};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Remote that branches are pushed to and fetched from
+pub const REMOTE: &str = "origin";
+
+/// Delay between polls while waiting for a review's checks or merge
+#[cfg(not(test))]
+const MERGE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+#[cfg(test)]
+const MERGE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// How long to wait for a review's checks or merge before giving up
+const MERGE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// How a dry-run plan is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PlanFormat {
+    /// Numbered list of actions
+    Text,
+    /// JSON document
+    Json,
+}
+
+/// One rebase within an [`Action::Restack`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rebase {
+    /// Branch to rebase
+    pub branch: String,
+    /// Branch to replay its commits onto
+    pub onto: String,
+    /// Revision the branch is currently based on (branch name or SHA)
+    pub upstream: String,
+}
+
+/// A single step of a plan
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Create a local branch at a commit
+    CreateBranch { branch: String, commit: String },
+    /// Point a local branch at another commit
+    SetBranchCommit { branch: String, commit: String },
+    /// Delete a local branch
+    DeleteBranch { branch: String },
+    /// Rename a local branch
+    RenameBranch { from: String, to: String },
+    /// Check out a local branch
+    Checkout { branch: String },
+    /// Detach HEAD at the current commit, so any branch can be moved
+    DetachHead,
+    /// Rebase branches, parents first
+    ///
+    /// Every upstream is resolved before the first rebase runs, so a branch
+    /// name refers to where that branch was before the restack.
+    Restack { rebases: Vec<Rebase> },
+    /// Squash the commits of the checked-out branch after `base` into one
+    Squash {
+        branch: String,
+        base: String,
+        message: String,
+        edit: bool,
+    },
+    /// Fetch from a remote
+    Fetch { remote: String },
+    /// Fast-forward a local branch to a remote-tracking branch
+    FastForward { branch: String, target: String },
+    /// Push branches in one go, leasing each on its expected remote commit
+    ///
+    /// With `force`, remote changes are overwritten instead.
+    Push {
+        remote: String,
+        refs: Vec<PushRef>,
+        force: bool,
+    },
+    /// Delete a branch on a remote if it still points to `expected`
+    DeleteRemoteBranch {
+        remote: String,
+        branch: String,
+        expected: String,
+    },
+    /// Start tracking a new branch
+    TrackBranch { branch: String, parent: String },
+    /// Stop tracking a branch
+    UntrackBranch { branch: String },
+    /// Change the parent of a branch, tracking it if needed
+    SetParent { branch: String, parent: String },
+    /// Move a branch's metadata to a new name and reparent its children
+    RenameTracked { from: String, to: String },
+    /// Replace the metadata with the one recorded before an operation,
+    /// keeping the current auth token
+    RestoreMetadata {
+        command: String,
+        metadata: Box<Metadata>,
+    },
+    /// Create a review and record it in the branch's metadata
+    CreateReview(CreateReviewParams),
+    /// Update a review
+    UpdateReview(UpdateReviewParams),
+    /// Close a review without merging it
+    CloseReview { review_id: String },
+    /// Refresh the stack navigation in the open reviews of a stack
+    ///
+    /// `stack` lists the stack's branches parents first. Review IDs are
+    /// read from the metadata when the action is applied, so reviews
+    /// created earlier in the plan are included.
+    UpdateNavigation { stack: Vec<String> },
+    /// Merge a review and wait until the provider reports it as merged
+    MergeReview {
+        branch: String,
+        review_id: String,
+        method: MergeMethod,
+    },
+}
+
+impl Action {
+    /// Describe the action for a dry run, one line per step
+    pub fn describe(&self) -> Vec<String> {
+        let line = match self {
+            Action::CreateBranch { branch, commit } => {
+                format!("Create branch '{}' at {}", branch, short_sha(commit))
+            }
+            Action::SetBranchCommit { branch, commit } => {
+                format!("Move branch '{}' to {}", branch, short_sha(commit))
+            }
+            Action::DeleteBranch { branch } => format!("Delete branch '{}'", branch),
+            Action::RenameBranch { from, to } => format!("Rename branch '{}' to '{}'", from, to),
+            Action::Checkout { branch } => format!("Check out '{}'", branch),
+            Action::DetachHead => "Detach HEAD".to_string(),
+            Action::Restack { rebases } => {
+                return rebases
+                    .iter()
+                    .map(|rebase| {
+                        format!(
+                            "Rebase '{}' onto '{}' (from {})",
+                            rebase.branch,
+                            rebase.onto,
+                            short_sha(&rebase.upstream)
+                        )
+                    })
+                    .collect();
+            }
+            Action::Squash { branch, .. } => {
+                format!("Squash the commits of '{}' into one", branch)
+            }
+            Action::Fetch { remote } => format!("Fetch '{}'", remote),
+            Action::FastForward { branch, target } => {
+                format!("Fast-forward '{}' to '{}'", branch, target)
+            }
+            Action::Push {
+                remote,
+                refs,
+                force,
+            } => {
+                let branches: Vec<String> = refs
+                    .iter()
+                    .map(|push_ref| format!("'{}'", push_ref.branch))
+                    .collect();
+                let forced = if *force {
+                    ", overwriting remote changes"
+                } else {
+                    ""
+                };
+                format!("Push {} to '{}'{}", branches.join(", "), remote, forced)
+            }
+            Action::DeleteRemoteBranch { remote, branch, .. } => {
+                format!("Delete '{}' from '{}'", branch, remote)
+            }
+            Action::TrackBranch { branch, parent } => {
+                format!("Track '{}' on top of '{}'", branch, parent)
+            }
+            Action::UntrackBranch { branch } => format!("Stop tracking '{}'", branch),
+            Action::SetParent { branch, parent } => {
+                format!("Set the parent of '{}' to '{}'", branch, parent)
+            }
+            Action::RenameTracked { from, to } => {
+                format!("Move the metadata of '{}' to '{}'", from, to)
+            }
+            Action::RestoreMetadata { command, .. } => {
+                format!("Restore the metadata from before 'bt {}'", command)
+            }
+            Action::CreateReview(params) => format!(
+                "Create {}review for '{}' targeting '{}': {}",
+                if params.draft { "draft " } else { "" },
+                params.source_branch,
+                params.target_branch,
+                params.title
+            ),
+            Action::UpdateReview(params) => describe_update(params),
+            Action::CloseReview { review_id } => format!("Close review {}", review_id),
+            Action::UpdateNavigation { stack } => {
+                let branches: Vec<String> =
+                    stack.iter().map(|branch| format!("'{}'", branch)).collect();
+                format!(
+                    "Update the stack navigation in the reviews of {}",
+                    branches.join(", ")
+                )
+            }
+            Action::MergeReview {
+                branch,
+                review_id,
+                method,
+            } => format!("Merge review {} of '{}' ({})", review_id, branch, method),
+        };
+        vec![line]
+    }
+
+    /// Whether applying the action needs a provider connection
+    fn needs_provider(&self) -> bool {
+        matches!(
+            self,
+            Action::CreateReview(_)
+                | Action::UpdateReview(_)
+                | Action::CloseReview { .. }
+                | Action::UpdateNavigation { .. }
+                | Action::MergeReview { .. }
+        )
+    }
+
+    /// Apply the action's effect on metadata, if it has one
+    ///
+    /// Returns whether the metadata changed. Planners use this to simulate
+    /// the metadata a later step will see.
+    pub fn apply_to_metadata(&self, metadata: &mut Metadata) -> bool {
+        match self {
+            Action::TrackBranch { branch, parent } => {
+                metadata.set_branch(branch.clone(), BranchMetadata::new(parent.clone()));
+            }
+            Action::UntrackBranch { branch } => {
+                metadata.remove_branch(branch);
+            }
+            Action::SetParent { branch, parent } => {
+                let mut meta = metadata
+                    .get_branch(branch)
+                    .cloned()
+                    .unwrap_or_else(|| BranchMetadata::new(parent.clone()));
+                meta.parent = parent.clone();
+                meta.touch();
+                metadata.set_branch(branch.clone(), meta);
+            }
+            Action::RenameTracked { from, to } => {
+                if let Some(meta) = metadata.remove_branch(from) {
+                    metadata.set_branch(to.clone(), meta);
+                }
+                metadata.reparent_children(from, to);
+            }
+            Action::RestoreMetadata {
+                metadata: recorded, ..
+            } => {
+                let auth_token = metadata.auth_token.take();
+                *metadata = Metadata {
+                    auth_token,
+                    ..(**recorded).clone()
+                };
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Describe a review update by the fields it changes
+fn describe_update(params: &UpdateReviewParams) -> String {
+    let mut changes = Vec::new();
+    if let Some(target) = &params.target_branch {
+        changes.push(format!("target '{}'", target));
+    }
+    let fields = [
+        ("title", params.title.is_some()),
+        ("description", params.description.is_some()),
+        ("draft state", params.draft.is_some()),
+        ("reviewers", params.reviewers.is_some()),
+        ("assignees", params.assignees.is_some()),
+        ("labels", params.labels.is_some()),
+        ("milestone", params.milestone.is_some()),
+    ];
+    changes.extend(
+        fields
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(field, _)| field.to_string()),
+    );
+
+    match retarget_only(params) {
+        Some(target) => format!("Retarget review {} to '{}'", params.review_id, target),
+        None => format!("Update review {}: {}", params.review_id, changes.join(", ")),
+    }
+}
+
+/// Get the new target of an update that changes nothing else
+fn retarget_only(params: &UpdateReviewParams) -> Option<&str> {
+    let unchanged = params.title.is_none()
+        && params.description.is_none()
+        && params.draft.is_none()
+        && params.reviewers.is_none()
+        && params.assignees.is_none()
+        && params.labels.is_none()
+        && params.milestone.is_none();
+    params.target_branch.as_deref().filter(|_| unchanged)
+}
+
+/// Shorten a SHA for display, leaving branch names alone
+fn short_sha(revision: &str) -> String {
+    if revision.len() == 40 && revision.chars().all(|c| c.is_ascii_hexdigit()) {
+        revision[..7].to_string()
+    } else {
+        format!("'{}'", revision)
+    }
+}
+
+/// The actions a command will take, in order
+#[derive(Debug, Clone, Serialize)]
+pub struct Plan {
+    /// Name of the command the plan is for
+    pub command: String,
+    /// Actions to apply, in order
+    pub actions: Vec<Action>,
+}
+
+impl Plan {
+    /// Create an empty plan for a command
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            actions: Vec::new(),
+        }
+    }
+
+    /// Append an action
+    pub fn push(&mut self, action: Action) {
+        self.actions.push(action);
+    }
+
+    /// Check whether the plan does nothing
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Get the metadata as it will be once the plan is applied
+    pub fn simulate(&self, metadata: &Metadata) -> Metadata {
+        let mut simulated = metadata.clone();
+        for action in &self.actions {
+            action.apply_to_metadata(&mut simulated);
+        }
+        simulated
+    }
+
+    /// Print the plan without applying it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the plan can't be serialized
+    pub fn print(&self, format: PlanFormat) -> Result<()> {
+        match format {
+            PlanFormat::Json => output::emit(self)?,
+            PlanFormat::Text => progress!("{}", self.render_text()),
+        }
+        Ok(())
+    }
+
+    /// Render the plan as a numbered list of steps
+    fn render_text(&self) -> String {
+        let steps: Vec<String> = self.actions.iter().flat_map(Action::describe).collect();
+        if steps.is_empty() {
+            return format!("📋 Dry run of 'bt {}': nothing to do", self.command);
+        }
+
+        let mut text = format!(
+            "📋 Dry run of 'bt {}', nothing was changed:\n",
+            self.command
+        );
+        for (index, step) in steps.iter().enumerate() {
+            text.push_str(&format!("\n  {}. {}", index + 1, step));
+        }
+        text
+    }
+}
+
+/// Apply a plan and record it in the operation log
+///
+/// Metadata is saved after every action that changes it, so stopping
+/// halfway (e.g. on a rebase conflict) leaves it consistent with the
+/// branches. The provider is connected on first use unless one is given.
+/// Consecutive review creations are independent, so they are sent to the
+/// provider as one batch.
+///
+/// # Errors
+///
+/// Returns the error of the first action that fails; later actions are
+/// not applied
+pub fn execute(
+    plan: &Plan,
+    metadata: &mut Metadata,
+    provider: Option<Box<dyn Provider>>,
+) -> Result<()> {
+    execute_from(plan, metadata, provider, Some(Snapshot::capture()?))
+}
+
+/// Apply a plan, recording it from an earlier snapshot
+///
+/// Commands that change the repository before applying their plan pass
+/// the snapshot they took first, so the operation log covers both. With
+/// `None`, nothing is recorded.
+///
+/// # Errors
+///
+/// Returns the error of the first action that fails; later actions are
+/// not applied
+pub fn execute_from(
+    plan: &Plan,
+    metadata: &mut Metadata,
+    mut provider: Option<Box<dyn Provider>>,
+    before: Option<Snapshot>,
+) -> Result<()> {
+    let mut done = Vec::new();
+    let result = apply_actions(&plan.actions, metadata, &mut provider, &mut done);
+
+    if let Some(before) = before {
+        // A rebase stopped by conflicts still changed what came before it
+        let pushed: Vec<String> = done
+            .iter()
+            .flat_map(|action| match action {
+                Action::Push { refs, .. } => refs.iter().map(|r| r.branch.clone()).collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+        let remote_changes: Vec<String> = done
+            .iter()
+            .copied()
+            .filter(|action| {
+                action.needs_provider() || matches!(action, Action::DeleteRemoteBranch { .. })
+            })
+            .flat_map(Action::describe)
+            .collect();
+
+        if let Err(e) = oplog::record(&plan.command, before, &pushed, remote_changes) {
+            eprintln!("⚠️  Couldn't record the operation for 'bt undo': {}", e);
+        }
+    }
+
+    result
+}
+
+/// Apply actions in order, collecting the ones that succeeded in `done`
+fn apply_actions<'a>(
+    actions: &'a [Action],
+    metadata: &mut Metadata,
+    provider: &mut Option<Box<dyn Provider>>,
+    done: &mut Vec<&'a Action>,
+) -> Result<()> {
+    let mut index = 0;
+    while index < actions.len() {
+        let action = &actions[index];
+        if action.needs_provider() && provider.is_none() {
+            *provider = Some(common::connect_provider(metadata)?);
+        }
+
+        if let Action::CreateReview(_) = action {
+            let batch = actions[index..]
+                .iter()
+                .take_while(|action| matches!(action, Action::CreateReview(_)))
+                .count();
+            let provider = provider.as_deref_mut().expect("provider connected above");
+            create_reviews(&actions[index..index + batch], provider, metadata, done)?;
+            index += batch;
+            continue;
+        }
+
+        if action.apply_to_metadata(metadata) {
+            metadata::save_metadata(metadata)?;
+        } else if let Action::UpdateNavigation { stack } = action {
+            let provider = provider.as_deref_mut().expect("provider connected above");
+            update_navigation(stack, provider, metadata)?;
+        } else if action.needs_provider() {
+            let provider = provider.as_deref_mut().expect("provider connected above");
+            apply_review_action(action, provider, metadata)?;
+        } else if let Action::Push {
+            remote,
+            refs,
+            force,
+        } = action
+        {
+            push(remote, refs, *force, metadata)?;
+        } else {
+            apply_git_action(action)?;
+        }
+        record_done(action, done)?;
+        index += 1;
+    }
+
+    Ok(())
+}
+
+/// Remember that an action was applied
+fn record_done<'a>(action: &'a Action, done: &mut Vec<&'a Action>) -> Result<()> {
+    done.push(action);
+    if output::is_json() {
+        output::record_action(action)?;
+    }
+    Ok(())
+}
+
+/// Create the reviews of consecutive [`Action::CreateReview`]s together
+///
+/// Every review that was created is recorded in the metadata, even if
+/// another one failed, so that the next submit doesn't create it again.
+///
+/// # Errors
+///
+/// Returns the error of the first review that couldn't be created
+fn create_reviews<'a>(
+    actions: &'a [Action],
+    provider: &mut dyn Provider,
+    metadata: &mut Metadata,
+    done: &mut Vec<&'a Action>,
+) -> Result<()> {
+    let params: Vec<CreateReviewParams> = actions
+        .iter()
+        .map(|action| match action {
+            Action::CreateReview(params) => params.clone(),
+            _ => unreachable!("not a review creation: {:?}", action),
+        })
+        .collect();
+    let results = provider.create_reviews(params.clone());
+
+    let mut first_error = None;
+    for ((action, params), result) in actions.iter().zip(&params).zip(results) {
+        match result {
+            Ok(review) => {
+                if let Some(meta) = metadata.branches.get_mut(&params.source_branch) {
+                    meta.set_review(review.id.clone(), review.url.clone());
+                }
+                progress!("✓ Created review {} ({})", review.id, review.url);
+                record_done(action, done)?;
+            }
+            Err(e) if first_error.is_none() => first_error = Some(e),
+            Err(e) => eprintln!(
+                "⚠️  Failed to create a review for '{}': {}",
+                params.source_branch, e
+            ),
+        }
+    }
+    metadata::save_metadata(metadata)?;
+
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Rewrite the navigation section of every open review in a stack
+///
+/// Reviews whose section is already up to date aren't updated.
+///
+/// # Errors
+///
+/// Returns an error if a review can't be fetched or updated
+fn update_navigation(
+    stack: &[String],
+    provider: &mut dyn Provider,
+    metadata: &Metadata,
+) -> Result<()> {
+    let (branches, review_ids): (Vec<&String>, Vec<String>) = stack
+        .iter()
+        .filter_map(|branch| Some((branch, metadata.get_branch(branch)?.review_id.clone()?)))
+        .unzip();
+
+    let mut updated = 0;
+    for (branch, review) in branches.into_iter().zip(provider.get_reviews(&review_ids)) {
+        let review = review?;
+        if review.state != ReviewState::Open {
+            continue;
+        }
+
+        let block = navigation::render(metadata, stack, branch);
+        let description = navigation::apply(review.description.as_deref(), &block);
+        if review.description.as_deref() == Some(description.as_str()) {
+            continue;
+        }
+        provider.update_review(UpdateReviewParams {
+            review_id: review.id,
+            description: Some(description),
+            ..Default::default()
+        })?;
+        updated += 1;
+    }
+
+    if updated > 0 {
+        progress!("✓ Updated the stack navigation in {} review(s)", updated);
+    }
+    Ok(())
+}
+
+/// Apply an action that changes branches or remotes
+fn apply_git_action(action: &Action) -> Result<()> {
+    match action {
+        Action::CreateBranch { branch, commit } => {
+            git::create_branch(branch, commit)?;
+            progress!("✓ Created branch '{}'", branch);
+        }
+        Action::SetBranchCommit { branch, commit } => {
+            git::set_branch_commit(branch, commit)?;
+            progress!("✓ Moved '{}' to {}", branch, short_sha(commit));
+        }
+        Action::DeleteBranch { branch } => {
+            git::delete_branch(branch)?;
+            progress!("✓ Deleted branch '{}'", branch);
+        }
+        Action::RenameBranch { from, to } => {
+            git::rename_branch(from, to)?;
+            progress!("✓ Renamed branch '{}' to '{}'", from, to);
+        }
+        Action::Checkout { branch } => {
+            // Restacks no longer leave HEAD elsewhere, so this is often a no-op
+            if git::get_current_branch().ok().as_ref() != Some(branch) {
+                git::checkout_branch(branch)?;
+            }
+        }
+        Action::DetachHead => git::checkout_detached("HEAD")?,
+        Action::Restack { rebases } => {
+            let steps: Vec<rebase::Step> = rebases
+                .iter()
+                .map(|r| rebase::Step {
+                    branch: r.branch.clone(),
+                    onto: r.onto.clone(),
+                    upstream: r.upstream.clone(),
+                })
+                .collect();
+            rebase::rebase_branches(&steps, |rebased| {
+                if rebased.skipped == 0 {
+                    progress!("✓ Rebased {}", rebased.branch);
+                } else if rebased.replayed == 0 {
+                    progress!(
+                        "✓ Rebased {}; all of its commits are already upstream",
+                        rebased.branch
+                    );
+                } else {
+                    progress!(
+                        "✓ Rebased {}, skipping {} commit(s) already upstream",
+                        rebased.branch,
+                        rebased.skipped
+                    );
+                }
+            })?;
+        }
+        Action::Squash {
+            branch,
+            base,
+            message,
+            edit,
+        } => {
+            let original_tip = git::get_branch_commit(branch)?;
+            git::reset_soft(base)?;
+            if let Err(e) = git::commit(message, *edit) {
+                // Restore the original commits rather than leaving them staged
+                git::reset_soft(&original_tip)?;
+                return Err(e);
+            }
+            progress!("✓ Squashed into a single commit");
+        }
+        Action::DeleteRemoteBranch {
+            remote,
+            branch,
+            expected,
+        } => {
+            git::delete_remote_branch(remote, branch, expected)?;
+            progress!("✓ Deleted '{}' from '{}'", branch, remote);
+        }
+        Action::Fetch { remote } => git::fetch(remote)?,
+        Action::FastForward { branch, target } => {
+            git::fast_forward_branch(branch, target)?;
+            progress!("✓ Updated '{}'", branch);
+        }
+        _ => unreachable!("not a git action: {:?}", action),
+    }
+
+    Ok(())
+}
+
+/// Push branches and remember what was pushed for the next lease
+fn push(remote: &str, refs: &[PushRef], force: bool, metadata: &mut Metadata) -> Result<()> {
+    git::push(remote, refs, force)?;
+
+    for push_ref in refs {
+        let commit = git::get_branch_commit(&push_ref.branch)?;
+        if let Some(meta) = metadata.branches.get_mut(&push_ref.branch) {
+            meta.pushed_sha = Some(commit);
+        }
+        progress!("✓ Pushed {}", push_ref.branch);
+    }
+    metadata::save_metadata(metadata)
+}
+
+/// Plan pushing branches
+///
+/// Each branch is leased on the commit basalt last pushed, falling back
+/// to the remote-tracking branch for branches pushed some other way.
+///
+/// # Errors
+///
+/// Returns an error if a remote-tracking branch can't be read
+pub fn push_action(metadata: &Metadata, branches: &[String], force: bool) -> Result<Action> {
+    let mut refs = Vec::new();
+    for branch in branches {
+        let pushed = metadata
+            .get_branch(branch)
+            .and_then(|meta| meta.pushed_sha.clone());
+        let expected = match pushed {
+            Some(commit) => Some(commit),
+            None => git::remote_branch_commit(REMOTE, branch)?,
+        };
+        refs.push(PushRef {
+            branch: branch.clone(),
+            expected,
+        });
+    }
+
+    Ok(Action::Push {
+        remote: REMOTE.to_string(),
+        refs,
+        force,
+    })
+}
+
+/// Apply an action that goes through the provider
+fn apply_review_action(
+    action: &Action,
+    provider: &mut dyn Provider,
+    metadata: &Metadata,
+) -> Result<()> {
+    match action {
+        Action::UpdateReview(params) => {
+            let review = provider.update_review(params.clone())?;
+            match retarget_only(params) {
+                Some(target) => progress!("✓ Retargeted review {} to '{}'", review.id, target),
+                None => progress!("✓ Updated review {} ({})", review.id, review.url),
+            }
+        }
+        Action::CloseReview { review_id } => {
+            provider.close_review(review_id)?;
+            progress!("✓ Closed review {}", review_id);
+        }
+        Action::MergeReview {
+            branch,
+            review_id,
+            method,
+        } => {
+            progress!("🛬 Landing '{}' ({}, {})...", branch, review_id, method);
+            // Earlier steps may have changed the review since it was planned,
+            // e.g. pushing a restacked branch starts a new pipeline
+            require_mergeable(provider, branch, review_id, *method, true)?;
+            let head = match metadata
+                .get_branch(branch)
+                .and_then(|meta| meta.pushed_sha.clone())
+            {
+                Some(pushed) => pushed,
+                None => git::get_branch_commit(branch)?,
+            };
+            let merged = provider.merge_review(MergeReviewParams {
+                review_id: review_id.clone(),
+                method: *method,
+                sha: Some(head),
+            })?;
+            if merged.state != ReviewState::Merged {
+                wait_for_merge(provider, review_id)?;
+            }
+            progress!("✓ Merged {}", review_id);
+        }
+        _ => unreachable!("not a review action: {:?}", action),
+    }
+
+    Ok(())
+}
+
+/// Check that nothing prevents merging a review
+///
+/// With `wait`, checks that are still pending (a running pipeline,
+/// mergeability being computed) are polled until they settle. Without it,
+/// they are assumed to settle in time, since merging waits for them; a
+/// merge status blocked only by the running pipeline is ignored too.
+///
+/// A review that needs a rebase is fine with the rebase method: the
+/// provider rebases it as part of the merge.
+///
+/// # Errors
+///
+/// Returns an error listing what blocks the merge, if the review's checks
+/// can't be fetched, or if they don't settle in time
+pub fn require_mergeable(
+    provider: &mut dyn Provider,
+    branch: &str,
+    review_id: &str,
+    method: MergeMethod,
+    wait: bool,
+) -> Result<()> {
+    let mut checks = if wait {
+        wait_for_checks(provider, review_id)?
+    } else {
+        provider.get_review_checks(review_id)?
+    };
+    if method == MergeMethod::Rebase && checks.merge_status == MergeStatus::NeedsRebase {
+        checks.merge_status = MergeStatus::Mergeable;
+    }
+    if checks.is_pending() && checks.merge_status == MergeStatus::Blocked {
+        checks.merge_status = MergeStatus::Checking;
+    }
+
+    let blockers = checks.blockers();
+    if !blockers.is_empty() {
+        return Err(Error::provider_op(format!(
+            "Cannot land '{}': review {} is blocked ({})",
+            branch,
+            review_id,
+            blockers.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Poll the provider until a review's checks are no longer pending
+fn wait_for_checks(provider: &mut dyn Provider, review_id: &str) -> Result<ReviewChecks> {
+    let started = Instant::now();
+    let mut announced = false;
+
+    loop {
+        let checks = provider.get_review_checks(review_id)?;
+        if !checks.is_pending() {
+            return Ok(checks);
+        }
+
+        if !announced {
+            progress!("⏳ Waiting for the checks of {}...", review_id);
+            announced = true;
+        }
+        if started.elapsed() > MERGE_TIMEOUT {
+            return Err(Error::provider_op(format!(
+                "Timed out waiting for the checks of review {}",
+                review_id
+            )));
+        }
+        std::thread::sleep(MERGE_POLL_INTERVAL);
+    }
+}
+
+/// Poll the provider until a review is merged
+fn wait_for_merge(provider: &mut dyn Provider, review_id: &str) -> Result<()> {
+    progress!("⏳ Waiting for {} to be merged...", review_id);
+    let started = Instant::now();
+
+    loop {
+        let review = provider.get_review(review_id)?;
+        match review.state {
+            ReviewState::Merged => return Ok(()),
+            ReviewState::Closed => {
+                return Err(Error::provider_op(format!(
+                    "Review {} was closed instead of merged",
+                    review_id
+                )));
+            }
+            ReviewState::Open => {}
+        }
+
+        if started.elapsed() > MERGE_TIMEOUT {
+            return Err(Error::provider_op(format!(
+                "Timed out waiting for review {} to be merged",
+                review_id
+            )));
+        }
+        std::thread::sleep(MERGE_POLL_INTERVAL);
+    }
+}
+
+/// Retarget the reviews of `branches` to their parents in `metadata`
+///
+/// `metadata` should be the metadata as it will be once the plan's earlier
+/// actions are applied. Branches without a review are skipped.
+pub fn retarget_reviews(plan: &mut Plan, metadata: &Metadata, branches: &[String]) {
+    for branch in branches {
+        let Some(meta) = metadata.get_branch(branch) else {
+            continue;
+        };
+        if let Some(review_id) = &meta.review_id {
+            plan.push(Action::UpdateReview(UpdateReviewParams {
+                review_id: review_id.clone(),
+                target_branch: Some(meta.parent.clone()),
+                ..Default::default()
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::providers::{PipelineStatus, ProviderType};
+
+    #[test]
+    fn test_apply_to_metadata() {
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        metadata.set_branch("a".to_string(), BranchMetadata::new("main".to_string()));
+        metadata.set_branch("b".to_string(), BranchMetadata::new("a".to_string()));
+
+        let rename = Action::RenameTracked {
+            from: "a".to_string(),
+            to: "x".to_string(),
+        };
+        assert!(rename.apply_to_metadata(&mut metadata));
+        assert!(!metadata.has_branch("a"));
+        assert_eq!(metadata.get_branch("b").unwrap().parent, "x");
+
+        let untrack = Action::UntrackBranch {
+            branch: "x".to_string(),
+        };
+        assert!(untrack.apply_to_metadata(&mut metadata));
+        assert!(!metadata.has_branch("x"));
+
+        let delete = Action::DeleteBranch {
+            branch: "b".to_string(),
+        };
+        assert!(!delete.apply_to_metadata(&mut metadata));
+        assert!(metadata.has_branch("b"));
+    }
+
+    #[test]
+    fn test_merge_rechecks_review() {
+        let mut provider = MockProvider::new_gitlab();
+        let review = provider
+            .create_review(CreateReviewParams {
+                source_branch: "a".to_string(),
+                target_branch: "main".to_string(),
+                title: "A".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let mut plan = Plan::new("land");
+        plan.push(Action::MergeReview {
+            branch: "a".to_string(),
+            review_id: review.id.clone(),
+            method: MergeMethod::Merge,
+        });
+
+        // The pipeline failed after the plan was made
+        provider.set_review_checks(
+            &review.id,
+            ReviewChecks {
+                pipeline: Some(PipelineStatus::Failed),
+                ..Default::default()
+            },
+        );
+
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        let err = execute_from(&plan, &mut metadata, Some(Box::new(provider.clone())), None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("pipeline failed"), "{}", err);
+        assert_eq!(
+            provider.get_review(&review.id).unwrap().state,
+            ReviewState::Open
+        );
+    }
+
+    #[test]
+    fn test_merge_waits_for_running_pipeline() {
+        let mut provider = MockProvider::new_gitlab();
+        let review = provider
+            .create_review(CreateReviewParams {
+                source_branch: "b".to_string(),
+                target_branch: "main".to_string(),
+                title: "B".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Pushing the restacked branch started a pipeline, which then passes
+        let running = ReviewChecks {
+            pipeline: Some(PipelineStatus::Running),
+            merge_status: MergeStatus::Checking,
+            ..Default::default()
+        };
+        provider.queue_review_checks(&review.id, running.clone());
+        provider.queue_review_checks(&review.id, running);
+        provider.set_review_checks(
+            &review.id,
+            ReviewChecks {
+                pipeline: Some(PipelineStatus::Success),
+                merge_status: MergeStatus::Mergeable,
+                ..Default::default()
+            },
+        );
+
+        let mut plan = Plan::new("land");
+        plan.push(Action::MergeReview {
+            branch: "b".to_string(),
+            review_id: review.id.clone(),
+            method: MergeMethod::Merge,
+        });
+
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        let mut meta = BranchMetadata::new("main".to_string());
+        meta.pushed_sha = Some("0".repeat(40));
+        metadata.set_branch("b".to_string(), meta);
+
+        execute_from(&plan, &mut metadata, Some(Box::new(provider.clone())), None).unwrap();
+        assert_eq!(
+            provider.get_review(&review.id).unwrap().state,
+            ReviewState::Merged
+        );
+    }
+
+    #[test]
+    fn test_describe_update() {
+        let retarget = UpdateReviewParams {
+            review_id: "12".to_string(),
+            target_branch: Some("main".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(describe_update(&retarget), "Retarget review 12 to 'main'");
+
+        let triage = UpdateReviewParams {
+            review_id: "12".to_string(),
+            target_branch: Some("main".to_string()),
+            labels: Some(vec!["stacked".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            describe_update(&triage),
+            "Update review 12: target 'main', labels"
+        );
+    }
+
+    #[test]
+    fn test_render_text_and_json() {
+        let mut plan = Plan::new("move");
+        assert!(plan.render_text().contains("nothing to do"));
+
+        plan.push(Action::Restack {
+            rebases: vec![Rebase {
+                branch: "b".to_string(),
+                onto: "main".to_string(),
+                upstream: "a".to_string(),
+            }],
+        });
+        plan.push(Action::Push {
+            remote: REMOTE.to_string(),
+            refs: vec![PushRef {
+                branch: "b".to_string(),
+                expected: None,
+            }],
+            force: false,
+        });
+
+        let text = plan.render_text();
+        assert!(
+            text.contains("1. Rebase 'b' onto 'main' (from 'a')"),
+            "{}",
+            text
+        );
+        assert!(text.contains("2. Push 'b' to 'origin'"), "{}", text);
+
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["command"], "move");
+        assert_eq!(json["actions"][0]["action"], "restack");
+        assert_eq!(json["actions"][1]["action"], "push");
+        assert_eq!(json["actions"][1]["refs"][0]["branch"], "b");
+        assert_eq!(json["actions"][1]["force"], false);
+    }
+}
@@ -0,0 +1,151 @@
+//! Implementation of the `bt rename` command
+//!
+//! Renames the current branch while keeping the stack consistent:
+//! - Moves the branch's metadata to the new name
+//! - Updates the `parent` of its children
+//! - Pushes the new branch name if the branch was already pushed
+//! - Moves the review to the new source branch
+//! - Deletes the old branch from the remote, unless someone pushed to it
+//!
+//! Providers don't allow changing the source branch of an existing review,
+//! so a branch with a review gets a new review from the new branch (same
+//! title, description, draft state, reviewers, assignees, labels and
+//! milestone) and the old review is closed. Its discussions and approvals
+//! stay on the closed review. The stack navigation of every review in the
+//! stack is then updated to link the new review.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::rename::run_rename;
+//!
+//! run_rename("feature-auth".to_string(), None)?;
+//! ```
+
+use crate::cli::common;
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, REMOTE};
+use crate::core::git::PushRef;
+use crate::core::{environment, git, metadata, stack};
+use crate::error::{Error, Result};
+use crate::providers::CreateReviewParams;
+
+/// Run the rename command
+///
+/// # Arguments
+///
+/// * `new_name` - New name for the current branch
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The repository isn't initialized
+/// - The current branch is the base branch
+/// - A branch named `new_name` already exists
+/// - Pushing or updating reviews fails
+pub fn run_rename(new_name: String, dry_run: Option<PlanFormat>) -> Result<()> {
+    environment::check_basic_environment()?;
+
+    let old_name = git::get_current_branch()?;
+    let mut metadata = metadata::load_metadata()?;
+
+    if old_name == metadata.base_branch {
+        return Err(Error::invalid_stack(format!(
+            "Cannot rename the base branch '{}'",
+            old_name
+        )));
+    }
+    if git::local_branch_exists(&new_name)? {
+        return Err(Error::other(format!(
+            "Branch '{}' already exists",
+            new_name
+        )));
+    }
+
+    let children = stack::children(&metadata, &old_name);
+    let old_review = metadata
+        .get_branch(&old_name)
+        .and_then(|meta| meta.review_id.clone());
+
+    let mut plan = Plan::new("rename");
+    plan.push(Action::RenameBranch {
+        from: old_name.clone(),
+        to: new_name.clone(),
+    });
+    plan.push(Action::RenameTracked {
+        from: old_name.clone(),
+        to: new_name.clone(),
+    });
+
+    // A review needs the new branch on the remote
+    let old_remote = git::remote_branch_commit(REMOTE, &old_name)?;
+    if git::has_upstream(&old_name)? || old_remote.is_some() || old_review.is_some() {
+        plan.push(Action::Push {
+            remote: REMOTE.to_string(),
+            refs: vec![PushRef {
+                branch: new_name.clone(),
+                expected: None,
+            }],
+            force: false,
+        });
+    }
+
+    let planned = plan.simulate(&metadata);
+    let mut provider = None;
+    if let Some(review_id) = old_review.clone() {
+        let connected = provider.insert(common::connect_provider(&mut metadata)?);
+        let old = connected.get_review(&review_id)?;
+        let parent = planned
+            .get_branch(&new_name)
+            .map(|meta| meta.parent.clone())
+            .unwrap_or_else(|| planned.base_branch.clone());
+
+        plan.push(Action::CreateReview(CreateReviewParams {
+            source_branch: new_name.clone(),
+            target_branch: parent,
+            title: old.title,
+            description: old.description,
+            draft: old.draft,
+            reviewers: old.reviewers,
+            assignees: old.assignees,
+            labels: old.labels,
+            milestone: old.milestone,
+        }));
+        plan.push(Action::CloseReview { review_id });
+    }
+    plan::retarget_reviews(&mut plan, &planned, &children);
+
+    if old_review.is_some() {
+        let mut stack: Vec<String> = stack::ancestors(&planned, &new_name)
+            .into_iter()
+            .rev()
+            .collect();
+        stack.push(new_name.clone());
+        stack.extend(stack::descendants(&planned, &new_name));
+        plan.push(Action::UpdateNavigation { stack });
+    }
+
+    // Last, once no review uses the old branch anymore
+    if let Some(expected) = old_remote {
+        plan.push(Action::DeleteRemoteBranch {
+            remote: REMOTE.to_string(),
+            branch: old_name.clone(),
+            expected,
+        });
+    }
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+    plan::execute(&plan, &mut metadata, provider)?;
+
+    if let Some(review_id) = old_review {
+        eprintln!(
+            "⚠️  Review {} was closed; its discussions and approvals stay there and weren't moved to the new review",
+            review_id
+        );
+    }
+    progress!("\n✨ Renamed '{}' to '{}'", old_name, new_name);
+    Ok(())
+}
@@ -0,0 +1,167 @@
+//! Implementation of the `bt restack` command
+//!
+//! Rebases the branches of the current stack that are no longer based on
+//! the tip of their parent, parents first, so that every branch contains
+//! its parent's latest commits. Branches stacked on a restacked branch
+//! are restacked too.
+//!
+//! Each branch's own commits are found from where it forked off its parent
+//! (`git merge-base --fork-point`), so commits that were amended or dropped
+//! on the parent aren't replayed again.
+//!
+//! Commits are replayed in memory (see [`crate::core::rebase`]), so
+//! branches are never checked out unless a rebase conflicts.
+//!
+//! Branches to rebase must be linear and have commits of their own; pass
+//! `--allow-empty` to restack branches without commits.
+//!
+//! When a rebase stops on conflicts, resolve them and run
+//! `bt restack --continue`, or give up with `bt restack --abort`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::restack::run_restack;
+//!
+//! // Restack the current stack
+//! run_restack(false, false, false, None)?;
+//! ```
+
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, Rebase};
+use crate::core::metadata::Metadata;
+use crate::core::oplog::Snapshot;
+use crate::core::{environment, git, metadata, stack, validation};
+use crate::error::{Error, Result};
+use std::collections::HashSet;
+
+/// Run the restack command
+///
+/// # Arguments
+///
+/// * `continue_rebase` - Continue an interrupted rebase, then restack the rest
+/// * `abort` - Abort an interrupted rebase
+/// * `allow_empty` - Restack branches that have no commits of their own
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The environment isn't ready for stack operations
+/// - `continue_rebase` or `abort` is set without a rebase in progress
+/// - `dry_run` is combined with `continue_rebase` or `abort`
+/// - A branch to rebase contains a merge commit, or has no commits and
+///   `allow_empty` isn't set
+/// - A rebase stops on conflicts
+pub fn run_restack(
+    continue_rebase: bool,
+    abort: bool,
+    allow_empty: bool,
+    dry_run: Option<PlanFormat>,
+) -> Result<()> {
+    environment::check_basic_environment()?;
+
+    if (continue_rebase || abort) && dry_run.is_some() {
+        return Err(Error::other(
+            "--dry-run can't be combined with --continue or --abort",
+        ));
+    }
+    if (continue_rebase || abort) && !git::is_rebase_in_progress()? {
+        return Err(Error::other("No rebase in progress"));
+    }
+
+    if abort {
+        let branch = git::rebasing_branch()?;
+        git::rebase_abort()?;
+        progress!("✓ Aborted rebasing '{}'", branch);
+        return Ok(());
+    }
+    // The continued rebase is part of the operation being recorded
+    let before = Snapshot::capture()?;
+    if continue_rebase {
+        let branch = git::rebasing_branch()?;
+        git::rebase_continue()?;
+        progress!("✓ Rebased {}", branch);
+    }
+
+    environment::check_stack_operation_environment()?;
+
+    let current = git::get_current_branch()?;
+    let mut metadata = metadata::load_metadata()?;
+    let branches = stack_branches(&metadata, &current)?;
+
+    let mut plan = Plan::new("restack");
+    let rebases = plan_rebases(&metadata, &branches)?;
+    let rebased: Vec<String> = rebases.iter().map(|r| r.branch.clone()).collect();
+    validation::validate_branches(&metadata, &rebased, allow_empty)?;
+    if !rebases.is_empty() {
+        plan.push(Action::Restack { rebases });
+        plan.push(Action::Checkout {
+            branch: current.clone(),
+        });
+    }
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+    if plan.is_empty() && !continue_rebase {
+        progress!("✓ The stack is up to date");
+        return Ok(());
+    }
+
+    progress!("🥞 Restacking '{}'...", current);
+    plan::execute_from(&plan, &mut metadata, None, Some(before))?;
+
+    progress!("\n✨ Restacked '{}'", current);
+    Ok(())
+}
+
+/// Get the branches of the stack containing `current`, parents first
+///
+/// On the base branch, this is every tracked branch.
+fn stack_branches(metadata: &Metadata, current: &str) -> Result<Vec<String>> {
+    if current == metadata.base_branch {
+        return Ok(stack::descendants(metadata, current));
+    }
+    if !metadata.has_branch(current) {
+        return Err(Error::invalid_stack(format!(
+            "Branch '{}' is not tracked by basalt",
+            current
+        )));
+    }
+
+    let mut branches: Vec<String> = stack::ancestors(metadata, current)
+        .into_iter()
+        .rev()
+        .collect();
+    branches.push(current.to_string());
+    branches.extend(stack::descendants(metadata, current));
+    Ok(branches)
+}
+
+/// Plan rebases for the branches that need them
+///
+/// A branch is rebased if it isn't based on its parent's tip, or if its
+/// parent is rebased. `branches` must be ordered parents first.
+fn plan_rebases(metadata: &Metadata, branches: &[String]) -> Result<Vec<Rebase>> {
+    let mut rebased = HashSet::new();
+    let mut rebases = Vec::new();
+
+    for branch in branches {
+        let Some(meta) = metadata.get_branch(branch) else {
+            continue;
+        };
+        if !rebased.contains(&meta.parent) && !stack::needs_restack(metadata, branch)? {
+            continue;
+        }
+
+        rebases.push(Rebase {
+            branch: branch.clone(),
+            onto: meta.parent.clone(),
+            upstream: git::fork_point(&meta.parent, branch)?,
+        });
+        rebased.insert(branch.clone());
+    }
+
+    Ok(rebases)
+}
@@ -0,0 +1,326 @@
+//! Implementation of the `bt split` command
+//!
+//! Splits the current branch into a chain of stacked branches:
+//!
+//! - **By commit**: choose the commits after which a new branch should end.
+//!   No history is rewritten; new branches are created at existing commits.
+//! - **By hunk**: stage hunks with `git add --patch` into successive
+//!   single-commit branches, similar to Charcoal's `split --by-hunk`.
+//!
+//! The topmost part of the split always keeps the original branch name, so
+//! its review and its children stay attached to it. Every new branch is
+//! recorded in metadata with the correct parent.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::split::run_split;
+//!
+//! // Split the current branch by commit
+//! run_split(false, None)?;
+//! ```
+
+use crate::cli::common;
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, Rebase};
+use crate::core::metadata::Metadata;
+use crate::core::oplog::Snapshot;
+use crate::core::{environment, git, metadata, stack};
+use crate::error::{Error, Result};
+use crate::providers::UpdateReviewParams;
+
+/// Run the split command
+///
+/// # Arguments
+///
+/// * `by_hunk` - Split by hunk instead of by commit
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The environment isn't ready for stack operations
+/// - The branch has no commits of its own
+/// - `dry_run` is set with `by_hunk`, whose result depends on the hunks
+///   staged along the way
+/// - The user aborts or gives an invalid answer
+/// - Git operations or retargeting the review fail
+pub fn run_split(by_hunk: bool, dry_run: Option<PlanFormat>) -> Result<()> {
+    environment::check_stack_operation_environment()?;
+
+    let current = git::get_current_branch()?;
+    let mut metadata = metadata::load_metadata()?;
+
+    if current == metadata.base_branch {
+        return Err(Error::invalid_stack(format!(
+            "Cannot split the base branch '{}'",
+            current
+        )));
+    }
+    if by_hunk && dry_run.is_some() {
+        return Err(Error::other(
+            "'bt split --by-hunk' can't be planned ahead; --dry-run is only supported by commit",
+        ));
+    }
+
+    let original_parent = metadata
+        .get_branch(&current)
+        .map(|meta| meta.parent.clone())
+        .unwrap_or_else(|| metadata.base_branch.clone());
+    let base = git::merge_base(&original_parent, &current)?;
+    let commits = git::list_commits(&base, &current)?;
+
+    if commits.is_empty() {
+        return Err(Error::EmptyStack {
+            current_branch: current,
+            base_branch: original_parent,
+        });
+    }
+
+    // Splitting by hunk commits before the plan is applied
+    let before = Snapshot::capture()?;
+    let mut plan = Plan::new("split");
+    let new_parent = if by_hunk {
+        split_by_hunk(&mut plan, &metadata, &current, &original_parent, &base)?
+    } else {
+        split_by_commit(&mut plan, &current, &original_parent, &commits)?
+    };
+
+    plan.push(Action::SetParent {
+        branch: current.clone(),
+        parent: new_parent.clone(),
+    });
+    if let Some(review_id) = metadata
+        .get_branch(&current)
+        .and_then(|meta| meta.review_id.clone())
+    {
+        plan.push(Action::UpdateReview(UpdateReviewParams {
+            review_id,
+            target_branch: Some(new_parent),
+            ..Default::default()
+        }));
+    }
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+    plan::execute_from(&plan, &mut metadata, None, Some(before))?;
+
+    progress!("\n✨ Split '{}'", current);
+    Ok(())
+}
+
+/// Plan new branches at commits chosen by the user
+///
+/// Returns the new parent of the original branch.
+fn split_by_commit(
+    plan: &mut Plan,
+    current: &str,
+    original_parent: &str,
+    commits: &[git::CommitInfo],
+) -> Result<String> {
+    if commits.len() < 2 {
+        return Err(Error::invalid_stack(format!(
+            "Branch '{}' has a single commit. Use 'bt split --by-hunk' instead.",
+            current
+        )));
+    }
+
+    eprintln!("✂️  Splitting the commits of '{}'\n", current);
+    for (index, commit) in commits.iter().enumerate() {
+        eprintln!("  {}. {} {}", index + 1, &commit.sha[..7], commit.subject);
+    }
+    eprintln!();
+
+    let answer = common::prompt(
+        "End a new branch after commits (comma-separated numbers)",
+        None,
+    )?;
+    let split_points = parse_split_points(&answer, commits.len())?;
+
+    let mut names = Vec::new();
+    let mut start = 0;
+    for &point in &split_points {
+        eprintln!("\nCommits for branch {}:", names.len() + 1);
+        for commit in &commits[start..point] {
+            eprintln!("  {} {}", &commit.sha[..7], commit.subject);
+        }
+        names.push(prompt_branch_name(&names)?);
+        start = point;
+    }
+
+    let mut parent = original_parent.to_string();
+    for (name, &point) in names.iter().zip(&split_points) {
+        plan.push(Action::CreateBranch {
+            branch: name.clone(),
+            commit: commits[point - 1].sha.clone(),
+        });
+        plan.push(Action::TrackBranch {
+            branch: name.clone(),
+            parent: parent.clone(),
+        });
+        parent = name.clone();
+    }
+
+    Ok(parent)
+}
+
+/// Split by staging hunks into successive single-commit branches
+///
+/// The commits and branches are created right away, since each one
+/// depends on the hunks picked for the previous ones. Tracking them and
+/// restacking the branches above are planned.
+///
+/// Returns the new parent of the original branch.
+fn split_by_hunk(
+    plan: &mut Plan,
+    metadata: &Metadata,
+    current: &str,
+    original_parent: &str,
+    base: &str,
+) -> Result<String> {
+    let original_tip = git::get_branch_commit(current)?;
+    let default_message = git::commit_messages(base, current)?.join("\n\n");
+
+    progress!("✂️  Splitting the changes of '{}' by hunk", current);
+    progress!("   The last part keeps the name '{}'\n", current);
+
+    git::checkout_detached(&original_tip)?;
+    git::reset_keep_unstaged(base)?;
+
+    let mut created: Vec<String> = Vec::new();
+    let mut parent = original_parent.to_string();
+
+    let result = (|| -> Result<()> {
+        loop {
+            progress!("Select the hunks for branch {}:", created.len() + 1);
+            git::add_patch()?;
+
+            if !git::has_staged_changes()? {
+                return Err(Error::other("No changes staged, aborting split"));
+            }
+
+            let is_last = !git::has_unstaged_changes()?;
+            let name = if is_last {
+                current.to_string()
+            } else {
+                prompt_branch_name(&created)?
+            };
+
+            git::commit(&default_message, true)?;
+            let head = git::get_head_commit()?;
+
+            if is_last {
+                git::set_branch_commit(current, &head)?;
+                return Ok(());
+            }
+
+            git::create_branch(&name, &head)?;
+            plan.push(Action::TrackBranch {
+                branch: name.clone(),
+                parent: parent.clone(),
+            });
+            progress!("✓ Created branch '{}' on '{}'\n", name, parent);
+            parent = name.clone();
+            created.push(name);
+        }
+    })();
+
+    if let Err(e) = result {
+        // Put everything back the way it was
+        git::reset_hard(&original_tip)?;
+        git::checkout_branch(current)?;
+        for name in &created {
+            git::delete_branch(name)?;
+        }
+        return Err(e);
+    }
+
+    git::checkout_branch(current)?;
+
+    // Children are based on the tip that the split replaced
+    let mut rebases = Vec::new();
+    for name in stack::descendants(metadata, current) {
+        if let Some(meta) = metadata.get_branch(&name) {
+            let upstream = if meta.parent == current {
+                original_tip.clone()
+            } else {
+                meta.parent.clone()
+            };
+            rebases.push(Rebase {
+                branch: name.clone(),
+                onto: meta.parent.clone(),
+                upstream,
+            });
+        }
+    }
+    if !rebases.is_empty() {
+        plan.push(Action::Restack { rebases });
+        plan.push(Action::Checkout {
+            branch: current.to_string(),
+        });
+    }
+
+    Ok(parent)
+}
+
+/// Parse a comma-separated list of 1-based commit numbers
+///
+/// Each number must leave at least one commit for the original branch.
+/// Returns the sorted, deduplicated split points.
+fn parse_split_points(answer: &str, commit_count: usize) -> Result<Vec<usize>> {
+    let mut points = Vec::new();
+
+    for part in answer.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let point: usize = part
+            .parse()
+            .map_err(|_| Error::other(format!("Invalid commit number: {}", part)))?;
+        if point == 0 || point >= commit_count {
+            return Err(Error::other(format!(
+                "Commit number must be between 1 and {}: {}",
+                commit_count - 1,
+                point
+            )));
+        }
+        points.push(point);
+    }
+
+    if points.is_empty() {
+        return Err(Error::other("No split points selected, aborting split"));
+    }
+
+    points.sort_unstable();
+    points.dedup();
+    Ok(points)
+}
+
+/// Prompt for the name of a new branch
+///
+/// The name must not be empty, already exist, or be used earlier in the split.
+fn prompt_branch_name(taken: &[String]) -> Result<String> {
+    let name = common::prompt("Branch name", None)?;
+
+    if name.is_empty() {
+        return Err(Error::other("Branch name cannot be empty"));
+    }
+    if taken.contains(&name) || git::local_branch_exists(&name)? {
+        return Err(Error::other(format!("Branch '{}' already exists", name)));
+    }
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_split_points() {
+        assert_eq!(parse_split_points("1", 3).unwrap(), vec![1]);
+        assert_eq!(parse_split_points("2, 1,2", 3).unwrap(), vec![1, 2]);
+        assert!(parse_split_points("", 3).is_err());
+        assert!(parse_split_points("0", 3).is_err());
+        assert!(parse_split_points("3", 3).is_err());
+        assert!(parse_split_points("x", 3).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Implementation of the `bt squash` command
+//!
+//! Collapses all commits of the current branch into a single commit and
+//! restacks every branch stacked on top of it.
+//!
+//! The default message combines the messages of the squashed commits and is
+//! opened in the user's editor, like `git rebase -i` squashing does.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::squash::run_squash;
+//!
+//! // Squash with an explicit message
+//! run_squash(Some("Add feature".to_string()), false, None)?;
+//! ```
+
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, Rebase};
+use crate::core::{environment, git, metadata, stack};
+use crate::error::{Error, Result};
+
+/// Run the squash command
+///
+/// # Arguments
+///
+/// * `message` - Message for the squashed commit (skips the editor)
+/// * `no_edit` - Use the combined commit messages without opening the editor
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The environment isn't ready for stack operations
+/// - The current branch isn't tracked
+/// - The branch has no commits of its own
+/// - Committing or restacking fails
+pub fn run_squash(
+    message: Option<String>,
+    no_edit: bool,
+    dry_run: Option<PlanFormat>,
+) -> Result<()> {
+    environment::check_stack_operation_environment()?;
+
+    let current = git::get_current_branch()?;
+    let mut metadata = metadata::load_metadata()?;
+
+    let parent = metadata
+        .get_branch(&current)
+        .map(|meta| meta.parent.clone())
+        .ok_or_else(|| {
+            Error::invalid_stack(format!("Branch '{}' is not tracked by basalt", current))
+        })?;
+
+    let base = git::merge_base(&parent, &current)?;
+    let messages = git::commit_messages(&base, &current)?;
+
+    if messages.is_empty() {
+        return Err(Error::EmptyStack {
+            current_branch: current,
+            base_branch: parent,
+        });
+    }
+
+    let mut plan = Plan::new("squash");
+    if messages.len() == 1 && message.is_none() {
+        if let Some(format) = dry_run {
+            return plan.print(format);
+        }
+        progress!("✓ '{}' already has a single commit", current);
+        return Ok(());
+    }
+
+    let edit = message.is_none() && !no_edit;
+    plan.push(Action::Squash {
+        branch: current.clone(),
+        base,
+        message: message.unwrap_or_else(|| messages.join("\n\n")),
+        edit,
+    });
+
+    // Children are based on the tip that the squash replaces
+    let original_tip = git::get_branch_commit(&current)?;
+    let mut rebases = Vec::new();
+    for name in stack::descendants(&metadata, &current) {
+        if let Some(meta) = metadata.get_branch(&name) {
+            let upstream = if meta.parent == current {
+                original_tip.clone()
+            } else {
+                meta.parent.clone()
+            };
+            rebases.push(Rebase {
+                branch: name.clone(),
+                onto: meta.parent.clone(),
+                upstream,
+            });
+        }
+    }
+    if !rebases.is_empty() {
+        plan.push(Action::Restack { rebases });
+        plan.push(Action::Checkout {
+            branch: current.clone(),
+        });
+    }
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+
+    progress!(
+        "🗜️  Squashing {} commits on '{}'...",
+        messages.len(),
+        current
+    );
+    plan::execute(&plan, &mut metadata, None)?;
+
+    progress!("\n✨ Squashed '{}'", current);
+    Ok(())
+}
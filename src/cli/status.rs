@@ -0,0 +1,196 @@
+//! Implementation of the `bt status` command
+//!
+//! Shows the current stack from the base branch up: the current branch,
+//! its ancestors and its descendants, with each branch's review, whether
+//! it needs a restack, and the worktree it's checked out in if that isn't
+//! this one. Uncommitted changes in this worktree are summarized below.
+//!
+//! Review states are fetched from the provider, all at once, with the token
+//! cached by an earlier command. Like `bt log`, status never prompts for
+//! credentials and leaves the states out if they can't be fetched.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::status::run_status;
+//!
+//! run_status(false)?;
+//! ```
+
+use crate::cli::common;
+use crate::cli::output::{self, progress};
+use crate::core::git::{self, Worktree, WorktreeStatus};
+use crate::core::metadata::{self, Metadata};
+use crate::core::{environment, stack};
+use crate::error::Result;
+use crate::providers::ReviewState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A branch as shown in the status
+#[derive(Debug, Serialize)]
+struct BranchStatus {
+    name: String,
+    parent: String,
+    current: bool,
+    needs_restack: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    review_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    review_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    review_state: Option<ReviewState>,
+    /// Worktree the branch is checked out in, if it's another one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    worktree: Option<PathBuf>,
+}
+
+/// JSON output of the status command
+#[derive(Debug, Serialize)]
+struct StatusOutput<'a> {
+    base_branch: &'a str,
+    current_branch: Option<&'a str>,
+    branches: Vec<BranchStatus>,
+    changes: WorktreeStatus,
+}
+
+/// Run the status command
+///
+/// # Arguments
+///
+/// * `json` - Output JSON instead of text
+///
+/// # Errors
+///
+/// Returns an error if the repository isn't initialized or git fails
+pub fn run_status(json: bool) -> Result<()> {
+    environment::check_basic_environment()?;
+
+    let mut metadata = metadata::load_metadata()?;
+    let current = git::get_current_branch().ok();
+    let mut worktrees = git::checked_out_branches()?;
+    worktrees.retain(|_, worktree| !worktree.is_current);
+
+    let branches = match &current {
+        Some(branch) if metadata.has_branch(branch) || *branch == metadata.base_branch => {
+            let names = stack_branches(&metadata, branch);
+            let mut states = common::fetch_review_states(&mut metadata, &names, false);
+            names
+                .iter()
+                .map(|name| {
+                    let mut status =
+                        branch_status(&metadata, name, current.as_deref(), &worktrees)?;
+                    status.review_state = states.remove(name);
+                    Ok(status)
+                })
+                .collect::<Result<Vec<_>>>()?
+        }
+        _ => Vec::new(),
+    };
+    let changes = git::worktree_status(true)?;
+
+    if json {
+        let output = StatusOutput {
+            base_branch: &metadata.base_branch,
+            current_branch: current.as_deref(),
+            branches,
+            changes,
+        };
+        output::emit(&output)?;
+        return Ok(());
+    }
+
+    match &current {
+        Some(branch) if branches.is_empty() && *branch != metadata.base_branch => {
+            progress!("◉ {} (not tracked by basalt)", branch);
+        }
+        Some(_) if branches.is_empty() => progress!("No stacks on '{}'", metadata.base_branch),
+        None => progress!("HEAD is detached"),
+        Some(_) => {
+            progress!("{}", metadata.base_branch);
+            let mut above = metadata.base_branch.as_str();
+            for branch in &branches {
+                progress!("{}", summary(branch, above));
+                above = &branch.name;
+            }
+        }
+    }
+
+    if !changes.is_clean() {
+        progress!(
+            "\n{} staged, {} unstaged, {} untracked change(s)",
+            changes.staged.len(),
+            changes.unstaged.len(),
+            changes.untracked.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Get the branches of the stack containing `current`, parents first
+///
+/// On the base branch, this is every tracked branch.
+fn stack_branches(metadata: &Metadata, current: &str) -> Vec<String> {
+    if current == metadata.base_branch {
+        return stack::descendants(metadata, current);
+    }
+
+    let mut branches: Vec<String> = stack::ancestors(metadata, current)
+        .into_iter()
+        .rev()
+        .collect();
+    branches.push(current.to_string());
+    branches.extend(stack::descendants(metadata, current));
+    branches
+}
+
+/// Gather the status of one branch
+fn branch_status(
+    metadata: &Metadata,
+    name: &str,
+    current: Option<&str>,
+    worktrees: &HashMap<String, Worktree>,
+) -> Result<BranchStatus> {
+    let meta = metadata
+        .get_branch(name)
+        .expect("status is built from tracked branches");
+    let exists = git::local_branch_exists(name)? && git::local_branch_exists(&meta.parent)?;
+
+    Ok(BranchStatus {
+        name: name.to_string(),
+        parent: meta.parent.clone(),
+        current: current == Some(name),
+        needs_restack: exists && stack::needs_restack(metadata, name)?,
+        review_id: meta.review_id.clone(),
+        review_url: meta.review_url.clone(),
+        review_state: None,
+        worktree: worktrees.get(name).map(|worktree| worktree.path.clone()),
+    })
+}
+
+/// Format the one-line summary of a branch
+///
+/// The parent is only named when it isn't the branch listed above.
+fn summary(branch: &BranchStatus, above: &str) -> String {
+    let marker = if branch.current { "◉" } else { "◯" };
+    let mut line = format!("{} {}", marker, branch.name);
+
+    if branch.parent != above {
+        line.push_str(&format!(" (on {})", branch.parent));
+    }
+    match (&branch.review_id, branch.review_state) {
+        (Some(review_id), Some(state)) => line.push_str(&format!("  {} ({})", review_id, state)),
+        (Some(review_id), None) => line.push_str(&format!("  {}", review_id)),
+        (None, _) => {}
+    }
+    if branch.needs_restack {
+        line.push_str("  (needs restack)");
+    }
+    if let Some(worktree) = &branch.worktree {
+        line.push_str(&format!("  [worktree: {}]", worktree.display()));
+    }
+
+    line
+}
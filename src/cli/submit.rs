@@ -0,0 +1,697 @@
+//! Implementation of the `bt submit` command
+//!
+//! Pushes every branch from the bottom of the current stack up to the
+//! current branch and makes sure each one has a review targeting its parent:
+//! - Branches without a review get a new one (draft unless `--ready`)
+//! - Existing reviews are retargeted to the branch's parent
+//!
+//! The scope flags pick other branches instead: `--branch` submits the
+//! current branch only, `--upstack` the current branch and the branches
+//! above it, and `--stack` the whole stack. Branches outside the scope
+//! aren't pushed, but their existing reviews are still retargeted, so every
+//! review matches the stack. A branch can't be submitted before its parent
+//! has been pushed.
+//!
+//! Every open review of the stack, in the scope or not, gets a navigation
+//! section listing the stack's reviews (see [`crate::core::navigation`]).
+//!
+//! New reviews are titled after the branch's first commit. Their description
+//! is made of the commit message bodies followed by the repository's review
+//! template, if it has one. With `--edit`, the draft is opened in the user's
+//! editor before the review is created.
+//!
+//! Submitted branches must be linear and have commits of their own
+//! (`--allow-empty` lifts the latter). Branches are pushed with a lease on
+//! the commit basalt last pushed, so changes someone else pushed are never
+//! overwritten unless `--force` is given.
+//!
+//! Reviewers, assignees, labels and a milestone can be set on both new and
+//! existing reviews. Values come from command-line flags, falling back to
+//! the `[submit]` section of `.basalt.toml`. On existing reviews, reviewers,
+//! assignees and labels are added to the ones already set.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::submit::{run_submit, SubmitOptions};
+//!
+//! run_submit(
+//!     SubmitOptions {
+//!         reviewers: vec!["alice".to_string()],
+//!         ..Default::default()
+//!     },
+//!     None,
+//! )?;
+//! ```
+
+use crate::cli::common;
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat, REMOTE};
+use crate::core::config::{self, SubmitConfig};
+use crate::core::metadata::{self, Metadata};
+use crate::core::{environment, git, stack, templates, validation};
+use crate::error::{Error, Result};
+use crate::providers::{CreateReviewParams, Provider, Review, ReviewState, UpdateReviewParams};
+use std::collections::HashMap;
+use std::fs;
+use std::io::IsTerminal;
+
+/// File the review draft is written to for `--edit`, inside `.git/basalt/`
+const DRAFT_FILENAME: &str = "REVIEW_DESCRIPTION.md";
+
+/// Which branches of the current stack are submitted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SubmitScope {
+    /// The current branch only
+    Branch,
+    /// The current branch and its ancestors
+    #[default]
+    Downstack,
+    /// The current branch and its descendants
+    Upstack,
+    /// The current branch, its ancestors and its descendants
+    Stack,
+}
+
+/// Options for the submit command
+#[derive(Debug, Clone, Default)]
+pub struct SubmitOptions {
+    /// Branches to push and review
+    pub scope: SubmitScope,
+    /// Create new reviews as ready instead of draft
+    pub ready: bool,
+    /// Edit the title and description of new reviews before creating them
+    pub edit: bool,
+    /// Usernames to request reviews from
+    pub reviewers: Vec<String>,
+    /// Usernames to assign
+    pub assignees: Vec<String>,
+    /// Labels to add
+    pub labels: Vec<String>,
+    /// Milestone title
+    pub milestone: Option<String>,
+    /// Overwrite remote branches changed since they were last pushed
+    pub force: bool,
+    /// Submit branches that have no commits of their own
+    pub allow_empty: bool,
+}
+
+impl SubmitOptions {
+    /// Fill in unset reviewers, assignees, labels and milestone from config
+    fn with_defaults(mut self, defaults: SubmitConfig) -> Self {
+        if self.reviewers.is_empty() {
+            self.reviewers = defaults.reviewers;
+        }
+        if self.assignees.is_empty() {
+            self.assignees = defaults.assignees;
+        }
+        if self.labels.is_empty() {
+            self.labels = defaults.labels;
+        }
+        if self.milestone.is_none() {
+            self.milestone = defaults.milestone;
+        }
+        self
+    }
+}
+
+/// Run the submit command
+///
+/// # Arguments
+///
+/// * `options` - Draft state and review triage
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The repository isn't initialized or a rebase is in progress
+/// - The current branch isn't tracked
+/// - A submitted branch's parent is outside the scope and was never pushed
+/// - A submitted branch contains a merge commit, or has no commits and
+///   `allow_empty` isn't set
+/// - Pushing a branch or creating/updating a review fails
+pub fn run_submit(options: SubmitOptions, dry_run: Option<PlanFormat>) -> Result<()> {
+    environment::check_basic_environment()?;
+    environment::require_no_rebase_in_progress()?;
+
+    let current = git::get_current_branch()?;
+    let mut metadata = metadata::load_metadata()?;
+
+    if !metadata.has_branch(&current) {
+        return Err(Error::invalid_stack(format!(
+            "Branch '{}' is not tracked by basalt",
+            current
+        )));
+    }
+
+    let options = options.with_defaults(config::load_config()?.submit);
+
+    let stack = stack_branches(&metadata, &current);
+    let branches = select_branches(&metadata, &current, options.scope);
+    validation::validate_branches(&metadata, &branches, options.allow_empty)?;
+    require_parents_pushed(&metadata, &branches)?;
+
+    // Only new reviews use the template
+    let creates_reviews = branches.iter().any(|branch| {
+        metadata
+            .get_branch(branch)
+            .is_some_and(|meta| meta.review_id.is_none())
+    });
+    let template = if creates_reviews {
+        choose_template()?
+    } else {
+        None
+    };
+
+    let mut provider = common::connect_provider(&mut metadata)?;
+    let (plan, drafts) = plan_submit(
+        &metadata,
+        provider.as_mut(),
+        &stack,
+        &branches,
+        template.as_deref(),
+        &options,
+    )?;
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+
+    progress!("🚀 Submitting {} branch(es)...", branches.len());
+    plan::execute(&plan, &mut metadata, Some(provider))?;
+
+    progress!("\n✨ Submitted {} branch(es)", drafts.len());
+    for (branch, draft) in &drafts {
+        let url = metadata
+            .get_branch(branch)
+            .and_then(|meta| meta.review_url.as_deref())
+            .unwrap_or_default();
+        let draft = if *draft { " (draft)" } else { "" };
+        progress!("   {} → {}{}", branch, url, draft);
+    }
+
+    Ok(())
+}
+
+/// Plan submitting `branches`, a part of `stack`
+///
+/// Returns the plan and, for each submitted branch, whether its review
+/// is a draft.
+fn plan_submit(
+    metadata: &Metadata,
+    provider: &mut dyn Provider,
+    stack: &[String],
+    branches: &[String],
+    template: Option<&str>,
+    options: &SubmitOptions,
+) -> Result<(Plan, Vec<(String, bool)>)> {
+    let mut plan = Plan::new("submit");
+    let mut drafts = Vec::new();
+
+    plan.push(plan::push_action(metadata, branches, options.force)?);
+
+    // Fetch every existing review of the stack at once
+    let (reviewed, review_ids): (Vec<String>, Vec<String>) = stack
+        .iter()
+        .filter_map(|branch| {
+            let review_id = metadata.get_branch(branch)?.review_id.clone()?;
+            Some((branch.clone(), review_id))
+        })
+        .unzip();
+    let mut existing: HashMap<String, Result<Review>> = reviewed
+        .into_iter()
+        .zip(provider.get_reviews(&review_ids))
+        .collect();
+
+    for branch in branches {
+        let meta = metadata
+            .get_branch(branch)
+            .cloned()
+            .expect("submitted branches are tracked");
+
+        let draft = match meta.review_id {
+            Some(_) => {
+                let review = existing
+                    .remove(branch)
+                    .expect("existing reviews were fetched")?;
+                plan_update(&mut plan, &review, &meta.parent, options)
+            }
+            None => {
+                plan_create(&mut plan, branch, &meta.parent, template, options)?;
+                !options.ready
+            }
+        };
+        drafts.push((branch.clone(), draft));
+    }
+
+    // Only the reviews of branches outside the scope are left
+    for branch in stack.iter().filter(|branch| !branches.contains(branch)) {
+        let Some(review) = existing.remove(branch) else {
+            continue;
+        };
+        match review {
+            Ok(review) => plan_retarget(&mut plan, metadata, branch, &review, branches)?,
+            Err(e) => eprintln!("⚠️  Could not fetch the review of '{}': {}", branch, e),
+        }
+    }
+
+    // Last, so that it lists the reviews created above
+    plan.push(Action::UpdateNavigation {
+        stack: stack.to_vec(),
+    });
+
+    Ok((plan, drafts))
+}
+
+/// Get the branches of the stack containing `current`, parents first
+fn stack_branches(metadata: &Metadata, current: &str) -> Vec<String> {
+    let mut branches: Vec<String> = stack::ancestors(metadata, current)
+        .into_iter()
+        .rev()
+        .collect();
+    branches.push(current.to_string());
+    branches.extend(stack::descendants(metadata, current));
+    branches
+}
+
+/// Get the branches to submit for a scope, parents first
+fn select_branches(metadata: &Metadata, current: &str, scope: SubmitScope) -> Vec<String> {
+    let mut branches = Vec::new();
+    if matches!(scope, SubmitScope::Downstack | SubmitScope::Stack) {
+        branches.extend(stack::ancestors(metadata, current).into_iter().rev());
+    }
+    branches.push(current.to_string());
+    if matches!(scope, SubmitScope::Upstack | SubmitScope::Stack) {
+        branches.extend(stack::descendants(metadata, current));
+    }
+    branches
+}
+
+/// Check whether a tracked branch is on the remote
+///
+/// Untracked branches, such as the base branch, are assumed to be.
+fn is_pushed(metadata: &Metadata, branch: &str) -> Result<bool> {
+    match metadata.get_branch(branch) {
+        None => Ok(true),
+        Some(meta) if meta.pushed_sha.is_some() => Ok(true),
+        Some(_) => Ok(git::remote_branch_commit(REMOTE, branch)?.is_some()),
+    }
+}
+
+/// Make sure the parents of submitted branches will be on the remote
+///
+/// Reviews target their branch's parent, so a parent outside the scope
+/// must have been pushed before.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidStack` naming the first parent that wasn't
+fn require_parents_pushed(metadata: &Metadata, branches: &[String]) -> Result<()> {
+    for branch in branches {
+        let Some(meta) = metadata.get_branch(branch) else {
+            continue;
+        };
+        if !branches.contains(&meta.parent) && !is_pushed(metadata, &meta.parent)? {
+            return Err(Error::invalid_stack(format!(
+                "Cannot submit '{}': its parent '{}' hasn't been pushed. Submit it first or use --downstack",
+                branch, meta.parent
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Plan retargeting the review of a branch outside the scope
+///
+/// Only the target branch is updated, and only if the new target is on
+/// the remote once the submitted `branches` are pushed.
+fn plan_retarget(
+    plan: &mut Plan,
+    metadata: &Metadata,
+    branch: &str,
+    existing: &Review,
+    branches: &[String],
+) -> Result<()> {
+    let Some(parent) = metadata.get_branch(branch).map(|meta| meta.parent.as_str()) else {
+        return Ok(());
+    };
+    if existing.state != ReviewState::Open || existing.target_branch == parent {
+        return Ok(());
+    }
+    if !branches.iter().any(|branch| branch == parent) && !is_pushed(metadata, parent)? {
+        return Ok(());
+    }
+
+    plan.push(Action::UpdateReview(UpdateReviewParams {
+        review_id: existing.id.clone(),
+        target_branch: Some(parent.to_string()),
+        ..Default::default()
+    }));
+    Ok(())
+}
+
+/// Plan a review for a branch that doesn't have one yet
+fn plan_create(
+    plan: &mut Plan,
+    branch: &str,
+    parent: &str,
+    template: Option<&str>,
+    options: &SubmitOptions,
+) -> Result<()> {
+    let base = git::merge_base(parent, branch)?;
+    let messages = git::commit_messages(&base, branch)?;
+
+    let mut title = draft_title(&messages, branch);
+    let mut description = draft_description(&messages, template);
+    if options.edit {
+        (title, description) = edit_draft(&title, description.as_deref())?;
+    }
+
+    plan.push(Action::CreateReview(CreateReviewParams {
+        source_branch: branch.to_string(),
+        target_branch: parent.to_string(),
+        title,
+        description,
+        draft: !options.ready,
+        reviewers: options.reviewers.clone(),
+        assignees: options.assignees.clone(),
+        labels: options.labels.clone(),
+        milestone: options.milestone.clone(),
+    }));
+
+    Ok(())
+}
+
+/// Pick the repository's review template
+///
+/// With several templates, the user chooses one when running in a
+/// terminal; otherwise the first one is used.
+fn choose_template() -> Result<Option<String>> {
+    let found = templates::find_templates(&git::get_repo_root()?);
+
+    let template = match found.len() {
+        0 => return Ok(None),
+        1 => &found[0],
+        _ if !std::io::stdin().is_terminal() => &found[0],
+        _ => {
+            eprintln!("Review templates:");
+            for (index, template) in found.iter().enumerate() {
+                eprintln!("  {}) {}", index + 1, template.name);
+            }
+            eprintln!("  0) None");
+
+            let answer = common::prompt("Template to use", Some("1"))?;
+            match answer.parse::<usize>() {
+                Ok(0) => return Ok(None),
+                Ok(index) if index <= found.len() => &found[index - 1],
+                _ => {
+                    return Err(Error::other(format!("Invalid template choice: {}", answer)));
+                }
+            }
+        }
+    };
+
+    Ok(Some(template.read()?))
+}
+
+/// Title a review after the branch's first commit
+///
+/// `messages` are the branch's commit messages, oldest first. Falls back
+/// to the branch name for branches without commits.
+fn draft_title(messages: &[String], branch: &str) -> String {
+    messages
+        .first()
+        .and_then(|message| message.lines().next())
+        .map(|subject| subject.trim().to_string())
+        .filter(|subject| !subject.is_empty())
+        .unwrap_or_else(|| branch.to_string())
+}
+
+/// Build a review description from commit messages and a template
+///
+/// A single commit contributes its body (the subject is already the
+/// title); several commits contribute their full messages. The template
+/// comes last.
+fn draft_description(messages: &[String], template: Option<&str>) -> Option<String> {
+    let commits = match messages {
+        [single] => single
+            .split_once('\n')
+            .map(|(_, body)| body.trim().to_string())
+            .unwrap_or_default(),
+        _ => messages.join("\n\n"),
+    };
+
+    let parts: Vec<&str> = [commits.as_str(), template.unwrap_or_default().trim()]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    (!parts.is_empty()).then(|| parts.join("\n\n"))
+}
+
+/// Let the user edit a review's title and description in their editor
+///
+/// The first line of the file is the title and the rest is the description,
+/// like a commit message.
+fn edit_draft(title: &str, description: Option<&str>) -> Result<(String, Option<String>)> {
+    let path = environment::get_basalt_dir()?.join(DRAFT_FILENAME);
+    fs::write(
+        &path,
+        format!("{}\n\n{}\n", title, description.unwrap_or_default()),
+    )?;
+
+    let edited = git::edit_file(&path).and_then(|_| Ok(fs::read_to_string(&path)?));
+    let _ = fs::remove_file(&path);
+
+    parse_draft(&edited?)
+}
+
+/// Split an edited draft into a title and description
+fn parse_draft(contents: &str) -> Result<(String, Option<String>)> {
+    let contents = contents.trim_start();
+    let (title, description) = contents.split_once('\n').unwrap_or((contents, ""));
+
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(Error::other("Aborting submit: the review title is empty"));
+    }
+
+    let description = description.trim();
+    Ok((
+        title.to_string(),
+        (!description.is_empty()).then(|| description.to_string()),
+    ))
+}
+
+/// Plan retargeting an existing review and adding the requested triage
+///
+/// Reviews that are no longer open are left untouched, and so are reviews
+/// that already match. Returns whether the review is a draft.
+fn plan_update(plan: &mut Plan, existing: &Review, parent: &str, options: &SubmitOptions) -> bool {
+    let review_id = existing.id.as_str();
+    if existing.state != ReviewState::Open {
+        eprintln!(
+            "⚠️  Review {} is {}, not updating it",
+            review_id, existing.state
+        );
+        return existing.draft;
+    }
+
+    let milestone = options
+        .milestone
+        .clone()
+        .filter(|milestone| existing.milestone.as_ref() != Some(milestone));
+
+    let params = UpdateReviewParams {
+        review_id: review_id.to_string(),
+        target_branch: Some(parent.to_string()).filter(|parent| *parent != existing.target_branch),
+        reviewers: merge_names(&existing.reviewers, &options.reviewers),
+        assignees: merge_names(&existing.assignees, &options.assignees),
+        labels: merge_names(&existing.labels, &options.labels),
+        milestone,
+        ..Default::default()
+    };
+    let changed = params.target_branch.is_some()
+        || params.reviewers.is_some()
+        || params.assignees.is_some()
+        || params.labels.is_some()
+        || params.milestone.is_some();
+    if changed {
+        plan.push(Action::UpdateReview(params));
+    }
+
+    existing.draft
+}
+
+/// Add `requested` names to `existing` ones
+///
+/// Returns `None` if every requested name is already present, so that
+/// the review isn't needlessly updated.
+fn merge_names(existing: &[String], requested: &[String]) -> Option<Vec<String>> {
+    let missing: Vec<&String> = requested
+        .iter()
+        .filter(|name| !existing.contains(name))
+        .collect();
+    if missing.is_empty() {
+        return None;
+    }
+
+    let mut merged = existing.to_vec();
+    for name in missing {
+        if !merged.contains(name) {
+            merged.push(name.clone());
+        }
+    }
+    Some(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::metadata::BranchMetadata;
+    use crate::core::navigation;
+    use crate::providers::ProviderType;
+    use crate::providers::mock::MockProvider;
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_merge_names() {
+        assert_eq!(merge_names(&names(&["alice"]), &names(&["alice"])), None);
+        assert_eq!(merge_names(&names(&["alice"]), &[]), None);
+        assert_eq!(
+            merge_names(&names(&["alice"]), &names(&["bob", "alice", "bob"])),
+            Some(names(&["alice", "bob"]))
+        );
+    }
+
+    #[test]
+    fn test_select_branches() {
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        metadata.set_branch("a".to_string(), BranchMetadata::new("main".to_string()));
+        metadata.set_branch("b".to_string(), BranchMetadata::new("a".to_string()));
+        metadata.set_branch("c".to_string(), BranchMetadata::new("b".to_string()));
+
+        let select = |scope| select_branches(&metadata, "b", scope);
+        assert_eq!(select(SubmitScope::Branch), names(&["b"]));
+        assert_eq!(select(SubmitScope::Downstack), names(&["a", "b"]));
+        assert_eq!(select(SubmitScope::Upstack), names(&["b", "c"]));
+        assert_eq!(select(SubmitScope::Stack), names(&["a", "b", "c"]));
+    }
+
+    #[test]
+    fn test_submit_updates_navigation_outside_scope() {
+        let mut provider = MockProvider::new_gitlab();
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        for (branch, parent) in [("a", "main"), ("b", "a"), ("c", "b")] {
+            let review = provider
+                .create_review(CreateReviewParams {
+                    source_branch: branch.to_string(),
+                    target_branch: parent.to_string(),
+                    title: branch.to_string(),
+                    description: Some(format!("About {}", branch)),
+                    draft: false,
+                    ..Default::default()
+                })
+                .unwrap();
+            let mut meta = BranchMetadata::new(parent.to_string());
+            meta.set_review(review.id, review.url);
+            meta.pushed_sha = Some("0".repeat(40));
+            metadata.set_branch(branch.to_string(), meta);
+        }
+
+        // Submitting the downstack of 'b' leaves 'c' out of the scope
+        let stack = stack_branches(&metadata, "b");
+        let branches = select_branches(&metadata, "b", SubmitScope::Downstack);
+        let (mut plan, _) = plan_submit(
+            &metadata,
+            &mut provider,
+            &stack,
+            &branches,
+            None,
+            &SubmitOptions::default(),
+        )
+        .unwrap();
+
+        // Apply the review actions only; pushing needs a repository
+        plan.actions
+            .retain(|action| !matches!(action, Action::Push { .. }));
+        plan::execute_from(&plan, &mut metadata, Some(Box::new(provider.clone())), None).unwrap();
+
+        for (branch, review_id) in [("a", "!1"), ("b", "!2"), ("c", "!3")] {
+            let description = provider.get_review(review_id).unwrap().description.unwrap();
+            assert_eq!(
+                description,
+                format!(
+                    "About {}\n\n{}",
+                    branch,
+                    navigation::render(&metadata, &stack, branch)
+                )
+            );
+        }
+        let c = provider.get_review("!3").unwrap().description.unwrap();
+        assert!(c.contains("- **[!3]"), "{}", c);
+        assert!(c.contains("- [!2]"), "{}", c);
+    }
+
+    #[test]
+    fn test_draft_title() {
+        assert_eq!(
+            draft_title(&names(&["Add login\n\nDetails", "Fix typo"]), "auth"),
+            "Add login"
+        );
+        assert_eq!(draft_title(&[], "auth"), "auth");
+    }
+
+    #[test]
+    fn test_draft_description() {
+        // A single commit contributes its body only
+        assert_eq!(
+            draft_description(&names(&["Add login\n\nUses OAuth."]), None),
+            Some("Uses OAuth.".to_string())
+        );
+        assert_eq!(draft_description(&names(&["Add login"]), None), None);
+
+        // Several commits contribute their full messages, then the template
+        assert_eq!(
+            draft_description(&names(&["Add login", "Fix typo"]), Some("## Checklist\n")),
+            Some("Add login\n\nFix typo\n\n## Checklist".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_draft() {
+        assert_eq!(
+            parse_draft("\nAdd login\n\nUses OAuth.\n").unwrap(),
+            ("Add login".to_string(), Some("Uses OAuth.".to_string()))
+        );
+        assert_eq!(
+            parse_draft("Add login\n").unwrap(),
+            ("Add login".to_string(), None)
+        );
+        assert!(parse_draft("  \n\n").is_err());
+    }
+
+    #[test]
+    fn test_options_with_defaults() {
+        let defaults = SubmitConfig {
+            reviewers: names(&["alice"]),
+            assignees: names(&["carol"]),
+            labels: names(&["stacked"]),
+            milestone: Some("v1.0".to_string()),
+        };
+        let options = SubmitOptions {
+            reviewers: names(&["bob"]),
+            ..Default::default()
+        }
+        .with_defaults(defaults);
+
+        assert_eq!(options.reviewers, names(&["bob"]));
+        assert_eq!(options.assignees, names(&["carol"]));
+        assert_eq!(options.labels, names(&["stacked"]));
+        assert_eq!(options.milestone.as_deref(), Some("v1.0"));
+    }
+}
@@ -0,0 +1,113 @@
+//! Implementation of the `bt undo` command
+//!
+//! Reverts the most recent operation in the operation log: branches it
+//! changed are moved back, branches it deleted are recreated, branches it
+//! created are deleted, and the metadata is restored. The entry is then
+//! removed, so running `bt undo` again reverts the operation before it.
+//!
+//! Undo refuses to run if a branch changed since the operation, so work
+//! committed afterwards is never lost. Pushes, deleted remote branches and
+//! provider changes (e.g. created or merged reviews) can't be undone; they
+//! are listed instead.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::cli::undo::run_undo;
+//!
+//! run_undo(None)?;
+//! ```
+
+use crate::cli::output::progress;
+use crate::cli::plan::{self, Action, Plan, PlanFormat};
+use crate::core::{environment, git, metadata, oplog};
+use crate::error::{Error, Result};
+
+/// Run the undo command
+///
+/// # Arguments
+///
+/// * `dry_run` - Print the plan in this format instead of applying it
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The environment isn't ready for stack operations
+/// - There is nothing to undo
+/// - A branch changed since the operation
+/// - Restoring a branch fails
+pub fn run_undo(dry_run: Option<PlanFormat>) -> Result<()> {
+    environment::check_stack_operation_environment()?;
+
+    let entry = oplog::latest_entry()?.ok_or_else(|| Error::other("Nothing to undo"))?;
+    let branches = git::list_branch_commits()?;
+    let mut metadata = metadata::load_metadata()?;
+
+    for change in &entry.refs {
+        if branches.get(&change.branch) != change.after.as_ref() {
+            return Err(Error::other(format!(
+                "Branch '{}' changed since 'bt {}' ran, not undoing it. Use 'git reflog' to restore it by hand.",
+                change.branch, entry.command
+            )));
+        }
+    }
+
+    let mut plan = Plan::new("undo");
+    plan.push(Action::DetachHead);
+    for change in &entry.refs {
+        let branch = change.branch.clone();
+        plan.push(match (&change.before, &change.after) {
+            (Some(before), Some(_)) => Action::SetBranchCommit {
+                branch,
+                commit: before.clone(),
+            },
+            (Some(before), None) => Action::CreateBranch {
+                branch,
+                commit: before.clone(),
+            },
+            (None, _) => Action::DeleteBranch { branch },
+        });
+    }
+    plan.push(Action::RestoreMetadata {
+        command: entry.command.clone(),
+        metadata: Box::new(entry.metadata.clone()),
+    });
+
+    // Go back to the branch checked out before the operation, if it exists
+    let exists_after_undo =
+        |name: &String| match entry.refs.iter().find(|change| &change.branch == name) {
+            Some(change) => change.before.is_some(),
+            None => branches.contains_key(name),
+        };
+    let return_to = entry
+        .head
+        .clone()
+        .filter(exists_after_undo)
+        .or_else(|| git::get_current_branch().ok().filter(exists_after_undo))
+        .unwrap_or_else(|| metadata.base_branch.clone());
+    plan.push(Action::Checkout { branch: return_to });
+
+    if let Some(format) = dry_run {
+        return plan.print(format);
+    }
+
+    progress!("⏪ Undoing 'bt {}'...", entry.command);
+    plan::execute_from(&plan, &mut metadata, None, None)?;
+    oplog::remove_entry(entry.id)?;
+
+    for change in entry.refs.iter().filter(|change| change.pushed) {
+        eprintln!(
+            "⚠️  '{}' was pushed by 'bt {}'; run 'bt submit --force' to update the remote",
+            change.branch, entry.command
+        );
+    }
+    if !entry.remote_changes.is_empty() {
+        eprintln!("⚠️  These remote changes were not undone:");
+        for change in &entry.remote_changes {
+            eprintln!("   {}", change);
+        }
+    }
+
+    progress!("\n✨ Undid 'bt {}'", entry.command);
+    Ok(())
+}
@@ -0,0 +1,236 @@
+//! Repository configuration
+//!
+//! Team-wide settings live in `.basalt.toml` at the repository root, so
+//! they can be committed and shared. Unlike the metadata in
+//! `.git/basalt/`, this file is optional and never written by basalt.
+//!
+//! # Configuration Format
+//!
+//! ```toml
+//! [submit]
+//! reviewers = ["alice", "bob"]
+//! assignees = ["carol"]
+//! labels = ["stacked"]
+//! milestone = "v1.0"
+//!
+//! [http]
+//! timeout = 30      # Seconds before a provider request times out
+//! max_retries = 3   # Retries of failed idempotent requests
+//! concurrency = 4   # Provider requests sent at the same time
+//! ```
+//!
+//! Proxies and certificates are machine-specific, and proxy URLs may hold
+//! credentials, so they are never read from `.basalt.toml`. They come from
+//! the same per-user settings git uses for the provider's URL:
+//!
+//! - proxy: `http.proxy`, then `HTTPS_PROXY`/`ALL_PROXY` (`NO_PROXY` is
+//!   always honored)
+//! - extra root certificates: `GIT_SSL_CAINFO`, then `http.sslCAInfo`
+//! - client certificate: `GIT_SSL_CERT`, then `http.sslCert`
+//! - client key (PKCS#8 PEM): `GIT_SSL_KEY`, then `http.sslKey`
+//!
+//! Git settings can be scoped to a host, e.g. `http.https://gitlab.corp.proxy`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::core::config;
+//!
+//! let config = config::load_config()?;
+//! println!("Default reviewers: {:?}", config.submit.reviewers);
+//! ```
+
+#![allow(dead_code)] // Allow during early development
+
+use crate::core::git;
+use crate::error::Result;
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// Configuration file name, relative to the repository root
+pub const CONFIG_FILENAME: &str = ".basalt.toml";
+
+/// Top-level repository configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Defaults for `bt submit`
+    pub submit: SubmitConfig,
+    /// Provider HTTP client settings
+    pub http: HttpConfig,
+}
+
+/// Defaults applied to reviews created or updated by `bt submit`
+///
+/// Command-line flags take precedence over these values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubmitConfig {
+    /// Usernames to request reviews from
+    pub reviewers: Vec<String>,
+    /// Usernames to assign
+    pub assignees: Vec<String>,
+    /// Labels to add
+    pub labels: Vec<String>,
+    /// Milestone title
+    pub milestone: Option<String>,
+}
+
+/// Settings of the HTTP client used to call providers
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// Seconds before a single request times out
+    pub timeout: u64,
+    /// How many times a failed idempotent request is retried
+    pub max_retries: u32,
+    /// Most provider requests in flight at once when fetching or creating
+    /// several reviews
+    pub concurrency: usize,
+    /// Proxy URL for all provider requests
+    ///
+    /// This and the certificates are per-user settings, filled in by
+    /// [`load_http_config`] and never read from the file.
+    #[serde(skip)]
+    pub proxy: Option<String>,
+    /// PEM file with root certificates to trust in addition to the system's
+    #[serde(skip)]
+    pub ca_cert: Option<PathBuf>,
+    /// PEM file with the client certificate for mutual TLS
+    #[serde(skip)]
+    pub client_cert: Option<PathBuf>,
+    /// PEM file with the PKCS#8 private key of `client_cert`
+    #[serde(skip)]
+    pub client_key: Option<PathBuf>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout: 30,
+            max_retries: 3,
+            concurrency: 4,
+            proxy: None,
+            ca_cert: None,
+            client_cert: None,
+            client_key: None,
+        }
+    }
+}
+
+/// Parse configuration from TOML
+///
+/// # Errors
+///
+/// Returns an error if the TOML is invalid or contains unknown keys
+pub fn parse_config(contents: &str) -> Result<Config> {
+    Ok(toml::from_str(contents)?)
+}
+
+/// Load the repository configuration
+///
+/// Returns the default configuration if `.basalt.toml` doesn't exist.
+///
+/// # Errors
+///
+/// Returns an error if not in a git repository, or the file can't be read
+/// or parsed
+pub fn load_config() -> Result<Config> {
+    let path = git::get_repo_root()?.join(CONFIG_FILENAME);
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    parse_config(&contents)
+}
+
+/// Load the HTTP settings for requests to a provider
+///
+/// Timeouts, retries and concurrency come from `.basalt.toml`. The proxy
+/// and certificates come from the environment and git's configuration for
+/// `base_url`, so the ones already set up for the remote apply to API calls
+/// too.
+///
+/// # Arguments
+///
+/// * `base_url` - Base URL of the provider (e.g., "https://gitlab.com")
+///
+/// # Errors
+///
+/// Returns an error if the configuration can't be loaded
+pub fn load_http_config(base_url: &str) -> Result<HttpConfig> {
+    let mut http = load_config()?.http;
+
+    http.proxy = git::get_url_config("http.proxy", base_url)?;
+    http.ca_cert = user_path("GIT_SSL_CAINFO", "http.sslCAInfo", base_url)?;
+    http.client_cert = user_path("GIT_SSL_CERT", "http.sslCert", base_url)?;
+    http.client_key = user_path("GIT_SSL_KEY", "http.sslKey", base_url)?;
+
+    Ok(http)
+}
+
+/// Read a path from an environment variable, falling back to git config
+///
+/// Like git, the environment variable wins.
+fn user_path(env: &str, key: &str, base_url: &str) -> Result<Option<PathBuf>> {
+    match std::env::var_os(env).filter(|value| !value.is_empty()) {
+        Some(value) => Ok(Some(PathBuf::from(value))),
+        None => Ok(git::get_url_config(key, base_url)?.map(PathBuf::from)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_empty_config() {
+        assert_eq!(parse_config("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_parse_submit_config() {
+        let config = parse_config(
+            r#"
+            [submit]
+            reviewers = ["alice", "bob"]
+            labels = ["stacked"]
+            milestone = "v1.0"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.submit.reviewers, vec!["alice", "bob"]);
+        assert!(config.submit.assignees.is_empty());
+        assert_eq!(config.submit.labels, vec!["stacked"]);
+        assert_eq!(config.submit.milestone.as_deref(), Some("v1.0"));
+    }
+
+    #[test]
+    fn test_parse_http_config() {
+        let config = parse_config("[http]\ntimeout = 90\n").unwrap();
+        assert_eq!(config.http.timeout, 90);
+        assert_eq!(config.http.max_retries, HttpConfig::default().max_retries);
+    }
+
+    #[test]
+    fn test_parse_rejects_per_user_http_settings() {
+        // Shared files must not hold proxies (and their credentials) or
+        // machine-specific certificate paths
+        for key in ["proxy", "ca_cert", "client_cert", "client_key"] {
+            let contents = format!("[http]\n{} = \"value\"\n", key);
+            assert!(
+                parse_config(&contents).is_err(),
+                "{} should be rejected",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_unknown_key_fails() {
+        assert!(parse_config("[submit]\nreviewer = \"alice\"\n").is_err());
+    }
+}
@@ -60,12 +60,15 @@ pub fn get_git_dir() -> Result<PathBuf> {
 /// - Clean workspace (doesn't clutter repository root)
 /// - Auto-cleanup (removed if `.git/` is deleted)
 ///
+/// It lives in the git directory shared by all worktrees, so every
+/// worktree sees the same stacks.
+///
 /// # Errors
 ///
 /// Returns an error if not in a git repository
 pub fn get_basalt_dir() -> Result<PathBuf> {
-    let git_dir = get_git_dir()?;
-    Ok(git_dir.join("basalt"))
+    let common_dir = git::get_common_dir()?;
+    Ok(common_dir.join("basalt"))
 }
 
 pub fn basalt_dir_exists() -> Result<bool> {
@@ -113,7 +116,7 @@ pub fn require_initialized() -> Result<()> {
 
 /// Check if there are uncommitted changes in the working directory
 ///
-/// This includes both staged and unstaged changes.
+/// This includes staged, unstaged and untracked changes.
 ///
 /// # Errors
 ///
@@ -128,14 +131,46 @@ pub fn has_uncommitted_changes() -> Result<bool> {
 ///
 /// # Errors
 ///
-/// Returns `Error::UncommittedChanges` if there are uncommitted changes
+/// Returns `Error::UncommittedChanges`, listing the changed paths, if there
+/// are uncommitted changes
 pub fn require_clean_working_directory() -> Result<()> {
-    if has_uncommitted_changes()? {
-        return Err(Error::UncommittedChanges);
+    let status = git::worktree_status(true)?;
+    if !status.is_clean() {
+        return Err(Error::UncommittedChanges {
+            changes: describe_changes(&status),
+        });
     }
     Ok(())
 }
 
+/// Most paths listed by `describe_changes`
+const MAX_LISTED_CHANGES: usize = 10;
+
+/// List changed paths with their kind, one per line
+fn describe_changes(status: &git::WorktreeStatus) -> String {
+    let changes: Vec<String> = [
+        ("staged", &status.staged),
+        ("unstaged", &status.unstaged),
+        ("untracked", &status.untracked),
+    ]
+    .iter()
+    .flat_map(|(kind, paths)| {
+        paths
+            .iter()
+            .map(move |path| format!("  {:<10} {}", kind, path))
+    })
+    .collect();
+
+    let mut lines: Vec<String> = changes.iter().take(MAX_LISTED_CHANGES).cloned().collect();
+    if changes.len() > MAX_LISTED_CHANGES {
+        lines.push(format!(
+            "  ... and {} more",
+            changes.len() - MAX_LISTED_CHANGES
+        ));
+    }
+    lines.join("\n")
+}
+
 pub fn is_rebase_in_progress() -> Result<bool> {
     git::is_rebase_in_progress()
 }
@@ -223,4 +258,20 @@ mod tests {
             assert!(result.is_ok(), "Should find git repository");
         }
     }
+
+    #[test]
+    fn test_describe_changes() {
+        let status = git::WorktreeStatus {
+            staged: vec!["a.txt".to_string()],
+            unstaged: vec![],
+            untracked: (0..12).map(|i| format!("new{}.txt", i)).collect(),
+        };
+
+        let description = describe_changes(&status);
+        let lines: Vec<&str> = description.lines().collect();
+        assert_eq!(lines[0], "  staged     a.txt");
+        assert_eq!(lines[1], "  untracked  new0.txt");
+        assert_eq!(lines.len(), MAX_LISTED_CHANGES + 1);
+        assert_eq!(lines[MAX_LISTED_CHANGES], "  ... and 3 more");
+    }
 }
@@ -10,6 +10,7 @@
 //! - Handle errors explicitly with proper context
 //! - Never assume git command success
 //! - Provide clear error messages with context
+//! - Trace every public operation in a `tracing` span (see `core::trace`)
 //!
 //! # Why gitoxide?
 //!
@@ -34,15 +35,18 @@
 
 #![allow(dead_code)] // Allow during early development
 
+use crate::core::trace;
 use crate::error::{Error, Result};
 use gix::bstr::ByteSlice;
+use serde::Serialize;
+use tracing::instrument;
 
 /// Open a git repository at the current directory or any parent directory
 ///
 /// # Errors
 ///
 /// Returns an error if not in a git repository
-fn open_repo() -> Result<gix::Repository> {
+pub(crate) fn open_repo() -> Result<gix::Repository> {
     gix::discover(".").map_err(|_| Error::NotInGitRepository)
 }
 
@@ -54,6 +58,7 @@ fn open_repo() -> Result<gix::Repository> {
 /// - Not in a git repository
 /// - HEAD is detached (not on a branch)
 /// - Git operation fails
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn get_current_branch() -> Result<String> {
     let repo = open_repo()?;
 
@@ -97,6 +102,7 @@ pub fn get_current_branch() -> Result<String> {
 /// - Remote doesn't exist
 /// - Remote has no URL configured
 /// - Git operation fails
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn get_remote_url(remote_name: &str) -> Result<String> {
     let repo = open_repo()?;
 
@@ -135,6 +141,7 @@ pub fn get_remote_url(remote_name: &str) -> Result<String> {
 ///
 /// Returns an error if git operations fail unexpectedly.
 /// Does not error if detection methods don't work - returns a sensible default.
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn detect_default_branch() -> Result<String> {
     let repo = open_repo()?;
 
@@ -203,6 +210,7 @@ fn branch_exists(repo: &gix::Repository, branch_name: &str) -> Result<bool> {
 /// # Errors
 ///
 /// Returns an error if git operation fails
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn list_remotes() -> Result<Vec<String>> {
     let repo = open_repo()?;
 
@@ -227,6 +235,7 @@ pub fn list_remotes() -> Result<Vec<String>> {
 /// # Errors
 ///
 /// Returns an error if git operation fails
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn has_upstream(branch_name: &str) -> Result<bool> {
     let repo = open_repo()?;
 
@@ -254,6 +263,7 @@ pub fn has_upstream(branch_name: &str) -> Result<bool> {
 /// - Branch doesn't exist
 /// - Branch has no upstream configured
 /// - Git operation fails
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn get_upstream(branch_name: &str) -> Result<String> {
     let repo = open_repo()?;
 
@@ -301,6 +311,7 @@ pub fn get_upstream(branch_name: &str) -> Result<String> {
 /// # Errors
 ///
 /// Returns an error if not in a git repository
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn get_repo_root() -> Result<std::path::PathBuf> {
     let repo = open_repo()?;
     let work_dir = repo
@@ -314,36 +325,195 @@ pub fn get_repo_root() -> Result<std::path::PathBuf> {
 /// # Errors
 ///
 /// Returns an error if not in a git repository
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn get_git_dir() -> Result<std::path::PathBuf> {
     let repo = open_repo()?;
     Ok(repo.git_dir().to_path_buf())
 }
 
-/// Check if there are uncommitted changes in the working directory
+/// Get the git directory shared by all worktrees
 ///
-/// This includes both staged and unstaged changes.
+/// In the main worktree this is the same as [`get_git_dir`]; in a linked
+/// worktree, `get_git_dir` is `.git/worktrees/<name>` inside it.
 ///
-/// Note: Currently uses git CLI for simplicity. This is one of the few operations
-/// where the CLI is actually cleaner than using gitoxide's lower-level status API.
+/// # Errors
+///
+/// Returns an error if not in a git repository
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn get_common_dir() -> Result<std::path::PathBuf> {
+    let repo = open_repo()?;
+    Ok(repo.common_dir().to_path_buf())
+}
+
+/// A working tree of the repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Worktree {
+    /// Root directory of the working tree
+    pub path: std::path::PathBuf,
+    /// Branch checked out there, `None` if HEAD is detached
+    pub branch: Option<String>,
+    /// Whether this is the working tree basalt runs in
+    pub is_current: bool,
+}
+
+/// List the main working tree and all linked ones
+///
+/// Linked worktrees whose directory was deleted without `git worktree
+/// remove` are skipped.
 ///
 /// # Errors
 ///
-/// Returns an error if git operation fails
-pub fn has_uncommitted_changes() -> Result<bool> {
-    use std::process::Command;
+/// Returns an error if the worktrees can't be read
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn list_worktrees() -> Result<Vec<Worktree>> {
+    let repo = open_repo()?;
+    let worktree_error =
+        |e: &dyn std::fmt::Display| Error::git(format!("Failed to list worktrees: {}", e));
+    let current = repo.workdir().and_then(|path| path.canonicalize().ok());
 
-    let output = Command::new("git")
-        .args(["status", "--porcelain"])
-        .output()
+    let mut repos = vec![repo.main_repo().map_err(|e| worktree_error(&e))?];
+    for proxy in repo.worktrees().map_err(|e| worktree_error(&e))? {
+        if !proxy.base().is_ok_and(|path| path.exists()) {
+            continue;
+        }
+        repos.push(
+            proxy
+                .into_repo_with_possibly_inaccessible_worktree()
+                .map_err(|e| worktree_error(&e))?,
+        );
+    }
+
+    let mut worktrees = Vec::new();
+    for worktree_repo in repos {
+        let Some(path) = worktree_repo.workdir() else {
+            continue;
+        };
+        let path = path.canonicalize()?;
+        let branch = worktree_repo
+            .head_name()
+            .map_err(|e| worktree_error(&e))?
+            .and_then(|name| {
+                name.as_bstr()
+                    .to_str()
+                    .ok()
+                    .and_then(|name| name.strip_prefix("refs/heads/"))
+                    .map(String::from)
+            });
+        worktrees.push(Worktree {
+            is_current: current.as_ref() == Some(&path),
+            path,
+            branch,
+        });
+    }
+
+    Ok(worktrees)
+}
+
+/// Map each branch checked out in a worktree to that worktree
+///
+/// # Errors
+///
+/// Returns an error if the worktrees can't be read
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn checked_out_branches() -> Result<std::collections::HashMap<String, Worktree>> {
+    Ok(list_worktrees()?
+        .into_iter()
+        .filter_map(|worktree| Some((worktree.branch.clone()?, worktree)))
+        .collect())
+}
+
+/// Uncommitted changes in the working tree, by kind
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WorktreeStatus {
+    /// Paths whose changes are staged in the index
+    pub staged: Vec<String>,
+    /// Tracked paths changed in the working tree but not staged
+    pub unstaged: Vec<String>,
+    /// Paths not tracked by git; untracked directories are listed as a whole
+    pub untracked: Vec<String>,
+}
+
+impl WorktreeStatus {
+    /// Check whether there are no changes at all
+    pub fn is_clean(&self) -> bool {
+        self.staged.is_empty() && self.unstaged.is_empty() && self.untracked.is_empty()
+    }
+}
+
+/// Get the uncommitted changes in the working tree
+///
+/// Compares HEAD with the index for staged changes, and the index with the
+/// working tree for unstaged and untracked ones. Paths are sorted, and a
+/// path both staged and changed again since appears in both lists.
+///
+/// # Arguments
+///
+/// * `include_untracked` - Whether to look for untracked files at all
+///
+/// # Errors
+///
+/// Returns an error if the repository has no working tree or the status
+/// can't be computed
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn worktree_status(include_untracked: bool) -> Result<WorktreeStatus> {
+    use gix::status::index_worktree::Item as WorktreeItem;
+    use gix::status::plumbing::index_as_worktree::EntryStatus;
+
+    let repo = open_repo()?;
+    let untracked_files = if include_untracked {
+        gix::status::UntrackedFiles::Collapsed
+    } else {
+        gix::status::UntrackedFiles::None
+    };
+    let items = repo
+        .status(gix::progress::Discard)
+        .map_err(|e| Error::git(format!("Failed to check git status: {}", e)))?
+        .untracked_files(untracked_files)
+        .index_worktree_rewrites(None)
+        .into_iter(Vec::new())
         .map_err(|e| Error::git(format!("Failed to check git status: {}", e)))?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(Error::git(format!("Git status failed: {}", stderr)));
+    let mut status = WorktreeStatus::default();
+    for item in items {
+        let item = item.map_err(|e| Error::git(format!("Failed to check git status: {}", e)))?;
+        let path = item.location().to_str_lossy().into_owned();
+        match item {
+            gix::status::Item::TreeIndex(_) => status.staged.push(path),
+            gix::status::Item::IndexWorktree(WorktreeItem::Modification {
+                status: EntryStatus::NeedsUpdate(_),
+                ..
+            }) => {}
+            gix::status::Item::IndexWorktree(WorktreeItem::DirectoryContents { entry, .. }) => {
+                if entry.status == gix::dir::entry::Status::Untracked {
+                    status.untracked.push(path);
+                }
+            }
+            gix::status::Item::IndexWorktree(_) => status.unstaged.push(path),
+        }
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(!stdout.trim().is_empty())
+    // Changes are reported in no particular order
+    for paths in [
+        &mut status.staged,
+        &mut status.unstaged,
+        &mut status.untracked,
+    ] {
+        paths.sort();
+        paths.dedup();
+    }
+    Ok(status)
+}
+
+/// Check if there are uncommitted changes in the working directory
+///
+/// This includes staged, unstaged and untracked changes.
+///
+/// # Errors
+///
+/// Returns an error if the status can't be computed
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn has_uncommitted_changes() -> Result<bool> {
+    Ok(!worktree_status(true)?.is_clean())
 }
 
 /// Check if a rebase is in progress
@@ -351,6 +521,7 @@ pub fn has_uncommitted_changes() -> Result<bool> {
 /// # Errors
 ///
 /// Returns an error if git operation fails
+#[instrument(level = "debug", err(level = "debug"))]
 pub fn is_rebase_in_progress() -> Result<bool> {
     let git_dir = get_git_dir()?;
 
@@ -361,6 +532,867 @@ pub fn is_rebase_in_progress() -> Result<bool> {
     Ok(rebase_merge.exists() || rebase_apply.exists())
 }
 
+/// Run a git CLI command and return its trimmed stdout
+///
+/// Used for the few operations (rebase, merge-base, checkout) where
+/// the git CLI is the pragmatic choice over gitoxide.
+///
+/// # Errors
+///
+/// Returns `Error::CommandFailed` if git exits with a non-zero status
+fn run_git(args: &[&str]) -> Result<String> {
+    use std::process::Command;
+
+    let mut command = Command::new("git");
+    command.args(args);
+    let _span = trace::subprocess_span(&command).entered();
+    let output = command
+        .output()
+        .map_err(|e| Error::git(format!("Failed to run git: {}", e)))?;
+    trace::record_exit(&output.status);
+
+    if !output.status.success() {
+        return Err(Error::CommandFailed {
+            command: format!("git {}", args.join(" ")),
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Get the commit SHA a local branch points to
+///
+/// # Arguments
+///
+/// * `branch_name` - Local branch name
+///
+/// # Errors
+///
+/// Returns `Error::BranchNotFound` if the branch doesn't exist
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn get_branch_commit(branch_name: &str) -> Result<String> {
+    let repo = open_repo()?;
+
+    let branch_ref = format!("refs/heads/{}", branch_name);
+    let mut reference = repo
+        .find_reference(&branch_ref)
+        .map_err(|_| Error::BranchNotFound {
+            branch: branch_name.to_string(),
+        })?;
+
+    let id = reference
+        .peel_to_id()
+        .map_err(|e| Error::git(format!("Failed to resolve '{}': {}", branch_name, e)))?;
+
+    Ok(id.to_string())
+}
+
+/// Get the commit every local branch points to, by branch name
+///
+/// # Errors
+///
+/// Returns an error if the branches can't be listed
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn list_branch_commits() -> Result<std::collections::BTreeMap<String, String>> {
+    let output = run_git(&[
+        "for-each-ref",
+        "--format=%(objectname) %(refname:short)",
+        "refs/heads",
+    ])?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(sha, name)| (name.to_string(), sha.to_string()))
+        .collect())
+}
+
+/// Check if a local branch exists
+///
+/// # Arguments
+///
+/// * `branch_name` - Local branch name
+///
+/// # Errors
+///
+/// Returns an error if not in a git repository
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn local_branch_exists(branch_name: &str) -> Result<bool> {
+    let repo = open_repo()?;
+    let branch_ref = format!("refs/heads/{}", branch_name);
+    Ok(repo.find_reference(&branch_ref).is_ok())
+}
+
+/// Find the best common ancestor of two commits
+///
+/// # Arguments
+///
+/// * `a` - First revision (branch name or SHA)
+/// * `b` - Second revision (branch name or SHA)
+///
+/// # Errors
+///
+/// Returns an error if the revisions have no common ancestor
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn merge_base(a: &str, b: &str) -> Result<String> {
+    run_git(&["merge-base", a, b])
+}
+
+/// Check out a local branch
+///
+/// # Arguments
+///
+/// * `branch_name` - Local branch name
+///
+/// # Errors
+///
+/// Returns an error if the checkout fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn checkout_branch(branch_name: &str) -> Result<()> {
+    run_git(&["checkout", "--quiet", branch_name])?;
+    Ok(())
+}
+
+/// Rebase the commits of `branch` after `upstream` onto `new_base`
+///
+/// Equivalent to `git rebase --onto <new_base> <upstream> <branch>`.
+/// The branch is left checked out afterwards.
+///
+/// # Arguments
+///
+/// * `new_base` - Commit or branch to replay the commits onto
+/// * `upstream` - Commit the branch was previously based on
+/// * `branch` - Branch to rebase
+///
+/// # Errors
+///
+/// Returns `Error::RebaseConflict` if the rebase stopped on conflicts,
+/// or `Error::CommandFailed` for any other failure
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn rebase_onto(new_base: &str, upstream: &str, branch: &str) -> Result<()> {
+    match run_git(&["rebase", "--quiet", "--onto", new_base, upstream, branch]) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            if is_rebase_in_progress()? {
+                Err(Error::RebaseConflict {
+                    branch: branch.to_string(),
+                })
+            } else {
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Get the branch being rebased by an interrupted rebase
+///
+/// # Errors
+///
+/// Returns an error if no rebase is in progress
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn rebasing_branch() -> Result<String> {
+    let git_dir = get_git_dir()?;
+
+    for state_dir in ["rebase-merge", "rebase-apply"] {
+        let head_name = git_dir.join(state_dir).join("head-name");
+        if let Ok(contents) = std::fs::read_to_string(&head_name) {
+            let name = contents.trim();
+            return Ok(name.strip_prefix("refs/heads/").unwrap_or(name).to_string());
+        }
+    }
+
+    Err(Error::git("No rebase in progress"))
+}
+
+/// Continue an interrupted rebase after conflicts were resolved
+///
+/// Commit messages are kept as they are, without opening an editor.
+///
+/// # Errors
+///
+/// Returns `Error::RebaseConflict` if the rebase stopped on conflicts
+/// again, or `Error::CommandFailed` for any other failure
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn rebase_continue() -> Result<()> {
+    let branch = rebasing_branch()?;
+
+    match run_git(&["-c", "core.editor=true", "rebase", "--continue"]) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            if is_rebase_in_progress()? {
+                Err(Error::RebaseConflict { branch })
+            } else {
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Abort an interrupted rebase, restoring the branch being rebased
+///
+/// # Errors
+///
+/// Returns an error if no rebase is in progress
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn rebase_abort() -> Result<()> {
+    run_git(&["rebase", "--abort"])?;
+    Ok(())
+}
+
+/// Point a local branch at a commit without touching the working tree
+///
+/// # Arguments
+///
+/// * `branch_name` - Local branch name
+/// * `commit` - Commit SHA (or any revision) the branch should point to
+///
+/// # Errors
+///
+/// Returns an error if the ref update fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn set_branch_commit(branch_name: &str, commit: &str) -> Result<()> {
+    let branch_ref = format!("refs/heads/{}", branch_name);
+    run_git(&["update-ref", &branch_ref, commit])?;
+    Ok(())
+}
+
+/// Delete a local branch, even if it isn't merged
+///
+/// # Arguments
+///
+/// * `branch_name` - Local branch name
+///
+/// # Errors
+///
+/// Returns an error if the branch doesn't exist or is checked out
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn delete_branch(branch_name: &str) -> Result<()> {
+    run_git(&["branch", "--quiet", "-D", branch_name])?;
+    Ok(())
+}
+
+/// Get the full messages of the commits in `base..branch`, oldest first
+///
+/// # Arguments
+///
+/// * `base` - Exclusive lower bound (branch name or SHA)
+/// * `branch` - Inclusive upper bound (branch name or SHA)
+///
+/// # Errors
+///
+/// Returns an error if either revision is invalid
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn commit_messages(base: &str, branch: &str) -> Result<Vec<String>> {
+    let range = format!("{}..{}", base, branch);
+    let output = run_git(&["log", "--reverse", "--format=%B%x00", &range])?;
+
+    Ok(output
+        .split('\0')
+        .map(|message| message.trim().to_string())
+        .filter(|message| !message.is_empty())
+        .collect())
+}
+
+/// Move the current branch to `commit`, keeping all changes staged
+///
+/// # Errors
+///
+/// Returns an error if the reset fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn reset_soft(commit: &str) -> Result<()> {
+    run_git(&["reset", "--soft", commit])?;
+    Ok(())
+}
+
+/// Move the branch checked out in a worktree to `commit`, updating that
+/// worktree
+///
+/// Like `git reset --keep`, this refuses to overwrite local changes to
+/// files that differ between the two commits.
+///
+/// # Errors
+///
+/// Returns an error if the reset fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn reset_keep_in(worktree: &std::path::Path, commit: &str) -> Result<()> {
+    let worktree = worktree.to_string_lossy();
+    run_git(&["-C", &worktree, "reset", "--quiet", "--keep", commit])?;
+    Ok(())
+}
+
+/// Commit the staged changes
+///
+/// # Arguments
+///
+/// * `message` - Commit message
+/// * `edit` - Open the user's editor to adjust the message before committing
+///
+/// # Errors
+///
+/// Returns an error if the commit fails or the editor exits with an error
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn commit(message: &str, edit: bool) -> Result<()> {
+    if edit {
+        return run_git_interactive(&["commit", "--quiet", "-m", message, "--edit"]);
+    }
+
+    run_git(&["commit", "--quiet", "-m", message])?;
+    Ok(())
+}
+
+/// Run a git CLI command attached to the user's terminal
+///
+/// Used for commands that need user interaction, like editors and
+/// `git add --patch`.
+///
+/// # Errors
+///
+/// Returns `Error::CommandFailed` if git exits with a non-zero status
+fn run_git_interactive(args: &[&str]) -> Result<()> {
+    use std::process::Command;
+
+    let mut command = Command::new("git");
+    command.args(args);
+    let _span = trace::subprocess_span(&command).entered();
+    let status = command
+        .status()
+        .map_err(|e| Error::git(format!("Failed to run git: {}", e)))?;
+    trace::record_exit(&status);
+
+    if !status.success() {
+        return Err(Error::CommandFailed {
+            command: format!("git {}", args.join(" ")),
+            exit_code: status.code().unwrap_or(-1),
+            stderr: String::new(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Open a file in the user's editor and wait for it to be closed
+///
+/// Uses the same editor as git: `$GIT_EDITOR`, `core.editor`, `$VISUAL`,
+/// then `$EDITOR`.
+///
+/// # Errors
+///
+/// Returns an error if no editor is configured or it exits with an error
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn edit_file(path: &std::path::Path) -> Result<()> {
+    use std::process::Command;
+
+    let editor = run_git(&["var", "GIT_EDITOR"])?;
+
+    // Run through the shell like git does, so editors with arguments work
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(path);
+    let _span = trace::subprocess_span(&command).entered();
+    let status = command
+        .status()
+        .map_err(|e| Error::git(format!("Failed to run editor '{}': {}", editor, e)))?;
+    trace::record_exit(&status);
+
+    if !status.success() {
+        return Err(Error::CommandFailed {
+            command: editor,
+            exit_code: status.code().unwrap_or(-1),
+            stderr: String::new(),
+        });
+    }
+
+    Ok(())
+}
+
+/// A commit on a branch
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CommitInfo {
+    /// Full commit SHA
+    pub sha: String,
+    /// First line of the commit message
+    pub subject: String,
+}
+
+/// List the commits in `base..branch`, oldest first
+///
+/// # Arguments
+///
+/// * `base` - Exclusive lower bound (branch name or SHA)
+/// * `branch` - Inclusive upper bound (branch name or SHA)
+///
+/// # Errors
+///
+/// Returns an error if either revision is invalid
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn list_commits(base: &str, branch: &str) -> Result<Vec<CommitInfo>> {
+    let range = format!("{}..{}", base, branch);
+    let output = run_git(&["log", "--reverse", "--format=%H %s", &range])?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (sha, subject) = line.split_once(' ').unwrap_or((line, ""));
+            (!sha.is_empty()).then(|| CommitInfo {
+                sha: sha.to_string(),
+                subject: subject.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Create a local branch pointing at a commit
+///
+/// # Arguments
+///
+/// * `branch_name` - Name of the new branch
+/// * `commit` - Commit SHA (or any revision) the branch should point to
+///
+/// # Errors
+///
+/// Returns an error if the branch already exists or the name is invalid
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn create_branch(branch_name: &str, commit: &str) -> Result<()> {
+    run_git(&["branch", branch_name, commit])?;
+    Ok(())
+}
+
+/// Detach HEAD at a commit
+///
+/// # Errors
+///
+/// Returns an error if the checkout fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn checkout_detached(commit: &str) -> Result<()> {
+    run_git(&["checkout", "--quiet", "--detach", commit])?;
+    Ok(())
+}
+
+/// Move HEAD to `commit`, keeping all changes unstaged in the working tree
+///
+/// New files are marked with intent-to-add so they show up in
+/// `git add --patch`.
+///
+/// # Errors
+///
+/// Returns an error if the reset fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn reset_keep_unstaged(commit: &str) -> Result<()> {
+    run_git(&["reset", "--quiet", commit])?;
+    run_git(&["add", "--all", "--intent-to-add"])?;
+    Ok(())
+}
+
+/// Interactively stage hunks with `git add --patch`
+///
+/// # Errors
+///
+/// Returns an error if git exits with an error
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn add_patch() -> Result<()> {
+    run_git_interactive(&["add", "--patch"])
+}
+
+/// Check whether the index contains staged changes
+///
+/// # Errors
+///
+/// Returns an error if git fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn has_staged_changes() -> Result<bool> {
+    Ok(!run_git(&["diff", "--cached", "--name-only"])?.is_empty())
+}
+
+/// Check whether the working tree has changes that aren't staged
+///
+/// # Errors
+///
+/// Returns an error if git fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn has_unstaged_changes() -> Result<bool> {
+    Ok(!run_git(&["diff", "--name-only"])?.is_empty())
+}
+
+/// Reset HEAD, the index and the working tree to `commit`
+///
+/// Discards all uncommitted changes.
+///
+/// # Errors
+///
+/// Returns an error if the reset fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn reset_hard(commit: &str) -> Result<()> {
+    run_git(&["reset", "--quiet", "--hard", commit])?;
+    Ok(())
+}
+
+/// Get the current HEAD commit SHA
+///
+/// # Errors
+///
+/// Returns an error if HEAD can't be resolved
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn get_head_commit() -> Result<String> {
+    run_git(&["rev-parse", "HEAD"])
+}
+
+/// Resolve a revision (branch name, SHA, ...) to a commit SHA
+///
+/// # Errors
+///
+/// Returns an error if the revision doesn't name a commit
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn rev_parse(revision: &str) -> Result<String> {
+    run_git(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("{}^{{commit}}", revision),
+    ])
+}
+
+/// Find the commit `branch` forked from `parent`
+///
+/// Uses the parent's reflog (`git merge-base --fork-point`) so that commits
+/// removed from the parent by a rewrite aren't replayed onto it again.
+/// Falls back to the plain merge base when the reflog doesn't help.
+///
+/// # Errors
+///
+/// Returns an error if the branches have no common ancestor
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn fork_point(parent: &str, branch: &str) -> Result<String> {
+    match run_git(&["merge-base", "--fork-point", parent, branch]) {
+        Ok(commit) if !commit.is_empty() => Ok(commit),
+        _ => merge_base(parent, branch),
+    }
+}
+
+/// A commit on a branch, as seen by a revision walk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchCommit {
+    /// Commit SHA
+    pub id: String,
+    /// Number of parents; more than one means a merge commit
+    pub parent_count: usize,
+}
+
+impl BranchCommit {
+    /// Check whether this is a merge commit
+    pub fn is_merge(&self) -> bool {
+        self.parent_count > 1
+    }
+}
+
+/// List the commits reachable from `tip` but not from any of `hidden`
+///
+/// Commits are returned newest first.
+///
+/// # Arguments
+///
+/// * `tip` - Commit SHA to start walking from
+/// * `hidden` - Commit SHAs whose history is excluded
+///
+/// # Errors
+///
+/// Returns an error if a SHA is invalid or the history can't be walked
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn commits_between(tip: &str, hidden: &[String]) -> Result<Vec<BranchCommit>> {
+    let repo = open_repo()?;
+    let parse = |sha: &str| {
+        gix::ObjectId::from_hex(sha.as_bytes())
+            .map_err(|e| Error::git(format!("Invalid commit SHA '{}': {}", sha, e)))
+    };
+
+    let hidden = hidden
+        .iter()
+        .map(|sha| parse(sha))
+        .collect::<Result<Vec<_>>>()?;
+    let walk = repo
+        .rev_walk([parse(tip)?])
+        .with_hidden(hidden)
+        .all()
+        .map_err(|e| Error::git(format!("Failed to walk history of {}: {}", tip, e)))?;
+
+    walk.map(|info| {
+        let info =
+            info.map_err(|e| Error::git(format!("Failed to walk history of {}: {}", tip, e)))?;
+        Ok(BranchCommit {
+            id: info.id.to_string(),
+            parent_count: info.parent_ids.len(),
+        })
+    })
+    .collect()
+}
+
+/// Check whether `ancestor` is reachable from `descendant`
+///
+/// # Errors
+///
+/// Returns an error if either revision is invalid
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn is_ancestor(ancestor: &str, descendant: &str) -> Result<bool> {
+    match run_git(&["merge-base", "--is-ancestor", ancestor, descendant]) {
+        Ok(_) => Ok(true),
+        Err(Error::CommandFailed { exit_code: 1, .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Rename a local branch
+///
+/// # Errors
+///
+/// Returns an error if the branch doesn't exist or the new name is taken
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn rename_branch(old_name: &str, new_name: &str) -> Result<()> {
+    run_git(&["branch", "-m", old_name, new_name])?;
+    Ok(())
+}
+
+/// A branch to push, and where the remote branch is expected to be
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PushRef {
+    /// Local branch, pushed to the remote branch of the same name
+    pub branch: String,
+    /// Commit the remote branch must still point to (normally the last
+    /// one pushed), or `None` if it must not exist yet
+    pub expected: Option<String>,
+}
+
+/// Push branches to a remote in a single `git push`, and set upstreams
+///
+/// Each branch is pushed with `--force-with-lease=<branch>:<expected>`, so
+/// rewritten branches replace their remote branch only if nobody else
+/// pushed to it in the meantime. With `force`, remote changes are
+/// overwritten.
+///
+/// # Errors
+///
+/// Returns `Error::RemoteBranchChanged` if a remote branch isn't where it
+/// was expected, or `Error::CommandFailed` for any other failure
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn push(remote: &str, refs: &[PushRef], force: bool) -> Result<()> {
+    if refs.is_empty() {
+        return Ok(());
+    }
+
+    let mut args = vec![
+        "push".to_string(),
+        "--porcelain".to_string(),
+        "--set-upstream".to_string(),
+    ];
+    if force {
+        args.push("--force".to_string());
+    } else {
+        args.extend(refs.iter().map(|push_ref| {
+            format!(
+                "--force-with-lease=refs/heads/{}:{}",
+                push_ref.branch,
+                push_ref.expected.as_deref().unwrap_or_default()
+            )
+        }));
+    }
+    args.push(remote.to_string());
+    args.extend(
+        refs.iter()
+            .map(|push_ref| format!("refs/heads/{0}:refs/heads/{0}", push_ref.branch)),
+    );
+
+    run_push(&args)
+}
+
+/// Delete a branch on a remote if it still points to `expected`
+///
+/// # Errors
+///
+/// Returns `Error::RemoteBranchChanged` if the remote branch moved since
+/// `expected` was read, or `Error::CommandFailed` for any other failure
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn delete_remote_branch(remote: &str, branch: &str, expected: &str) -> Result<()> {
+    let args = [
+        "push".to_string(),
+        "--porcelain".to_string(),
+        format!("--force-with-lease=refs/heads/{}:{}", branch, expected),
+        remote.to_string(),
+        format!(":refs/heads/{}", branch),
+    ];
+    run_push(&args)
+}
+
+/// Run `git push --porcelain`, reporting rejected leases as
+/// `Error::RemoteBranchChanged`
+fn run_push(args: &[String]) -> Result<()> {
+    use std::process::Command;
+
+    let mut command = Command::new("git");
+    command.args(args);
+    let _span = trace::subprocess_span(&command).entered();
+    let output = command
+        .output()
+        .map_err(|e| Error::git(format!("Failed to run git: {}", e)))?;
+    trace::record_exit(&output.status);
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let changed = stale_branches(&String::from_utf8_lossy(&output.stdout));
+    if !changed.is_empty() {
+        return Err(Error::RemoteBranchChanged {
+            branches: changed.join(", "),
+        });
+    }
+
+    Err(Error::CommandFailed {
+        command: format!("git {}", args.join(" ")),
+        exit_code: output.status.code().unwrap_or(-1),
+        stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    })
+}
+
+/// Get the branches `git push --porcelain` rejected because the remote
+/// branch changed
+fn stale_branches(porcelain: &str) -> Vec<String> {
+    porcelain
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let (flag, refs, summary) = (fields.next()?, fields.next()?, fields.next()?);
+            let stale = ["stale info", "fetch first", "non-fast-forward"]
+                .iter()
+                .any(|reason| summary.contains(reason));
+            if flag != "!" || !stale {
+                return None;
+            }
+
+            // Deletions have no local side
+            let (local, remote) = refs.split_once(':')?;
+            let name = if local.is_empty() { remote } else { local };
+            Some(name.strip_prefix("refs/heads/").unwrap_or(name).to_string())
+        })
+        .collect()
+}
+
+/// Get the commit a remote-tracking branch points to
+///
+/// Returns `None` if the branch was never fetched from or pushed to the
+/// remote.
+///
+/// # Errors
+///
+/// Returns an error if git can't be run
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn remote_branch_commit(remote: &str, branch_name: &str) -> Result<Option<String>> {
+    let revision = format!("refs/remotes/{}/{}", remote, branch_name);
+    match run_git(&["rev-parse", "--verify", "--quiet", &revision]) {
+        Ok(commit) => Ok(Some(commit)),
+        Err(Error::CommandFailed { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Fetch from a remote
+///
+/// # Errors
+///
+/// Returns an error if the fetch fails
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn fetch(remote: &str) -> Result<()> {
+    run_git(&["fetch", "--quiet", remote])?;
+    Ok(())
+}
+
+/// Check that a remote can be reached with the configured credentials
+///
+/// Lists the remote's HEAD without prompting for credentials, so it fails
+/// instead of hanging when they're missing.
+///
+/// # Errors
+///
+/// Returns `Error::CommandFailed` if the remote can't be reached
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn check_remote(remote: &str) -> Result<()> {
+    use std::process::Command;
+
+    let args = ["ls-remote", "--quiet", remote, "HEAD"];
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    let _span = trace::subprocess_span(&command).entered();
+    let output = command
+        .output()
+        .map_err(|e| Error::git(format!("Failed to run git: {}", e)))?;
+    trace::record_exit(&output.status);
+
+    if !output.status.success() {
+        return Err(Error::CommandFailed {
+            command: format!("git {}", args.join(" ")),
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Get a git config value that applies to a URL
+///
+/// Honors URL-specific sections such as `http.https://gitlab.com.proxy`,
+/// like git does when it talks to that URL. Paths starting with `~/` are
+/// expanded.
+///
+/// # Arguments
+///
+/// * `key` - Config key, e.g. `http.proxy`
+/// * `url` - URL the value should apply to
+///
+/// # Errors
+///
+/// Returns an error if git config can't be read
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn get_url_config(key: &str, url: &str) -> Result<Option<String>> {
+    match run_git(&["config", "--type=path", "--get-urlmatch", key, url]) {
+        Ok(value) if !value.is_empty() => Ok(Some(value)),
+        Ok(_) => Ok(None),
+        // git config exits with 1 when the key isn't set
+        Err(Error::CommandFailed { exit_code: 1, .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Fast-forward a local branch to `target`
+///
+/// Works whether or not the branch is checked out.
+///
+/// # Errors
+///
+/// Returns an error if the branch can't be fast-forwarded (it has
+/// commits that `target` doesn't contain)
+#[instrument(level = "debug", err(level = "debug"))]
+pub fn fast_forward_branch(branch_name: &str, target: &str) -> Result<()> {
+    if !is_ancestor(branch_name, target)? {
+        return Err(Error::git(format!(
+            "Cannot fast-forward '{}' to '{}': the branch has diverged",
+            branch_name, target
+        )));
+    }
+
+    if get_current_branch().ok().as_deref() == Some(branch_name) {
+        run_git(&["merge", "--quiet", "--ff-only", target])?;
+    } else {
+        let target_commit = run_git(&["rev-parse", target])?;
+        set_branch_commit(branch_name, &target_commit)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -390,6 +1422,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_stale_branches() {
+        let porcelain = "To /tmp/remote.git\n\
+            =\trefs/heads/a:refs/heads/a\t[up to date]\n\
+            !\trefs/heads/b:refs/heads/b\t[rejected] (stale info)\n\
+            !\trefs/heads/c:refs/heads/c\t[remote rejected] (pre-receive hook declined)\n\
+            !\t:refs/heads/d\t[rejected] (stale info)\n\
+            Done";
+        assert_eq!(stale_branches(porcelain), vec!["b", "d"]);
+    }
+
     #[test]
     fn test_open_repo_in_git_repo() {
         // This test assumes we're running from within the basalt git repo
@@ -25,6 +25,7 @@
 //!     review_url: "https://gitlab.com/..."
 //!     parent: feature-part-1
 //!     created_at: "2024-01-01T00:00:00Z"
+//!     pushed_sha: "9c41..."  # Last commit pushed, for --force-with-lease
 //! ```
 //!
 //! # Design Principles
@@ -125,6 +126,12 @@ pub struct BranchMetadata {
     /// When this branch metadata was last updated (ISO 8601 format)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<String>,
+
+    /// Commit last pushed to the remote branch
+    ///
+    /// Pushes are refused if the remote branch moved away from it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pushed_sha: Option<String>,
 }
 
 impl Metadata {
@@ -205,6 +212,26 @@ impl Metadata {
     pub fn has_branch(&self, branch_name: &str) -> bool {
         self.branches.contains_key(branch_name)
     }
+
+    /// Point every branch whose parent is `old_parent` at `new_parent`
+    ///
+    /// # Returns
+    ///
+    /// The names of the reparented branches, sorted
+    pub fn reparent_children(&mut self, old_parent: &str, new_parent: &str) -> Vec<String> {
+        let mut reparented = Vec::new();
+
+        for (name, branch) in self.branches.iter_mut() {
+            if branch.parent == old_parent && name != new_parent {
+                branch.parent = new_parent.to_string();
+                branch.touch();
+                reparented.push(name.clone());
+            }
+        }
+
+        reparented.sort();
+        reparented
+    }
 }
 
 impl BranchMetadata {
@@ -220,6 +247,7 @@ impl BranchMetadata {
             parent,
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: None,
+            pushed_sha: None,
         }
     }
 
@@ -443,6 +471,20 @@ mod tests {
         assert!(!metadata.has_branch("feature"));
     }
 
+    #[test]
+    fn test_reparent_children() {
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        metadata.set_branch("a".to_string(), BranchMetadata::new("main".to_string()));
+        metadata.set_branch("b".to_string(), BranchMetadata::new("a".to_string()));
+        metadata.set_branch("c".to_string(), BranchMetadata::new("a".to_string()));
+
+        let reparented = metadata.reparent_children("a", "main");
+        assert_eq!(reparented, vec!["b", "c"]);
+        assert_eq!(metadata.get_branch("b").unwrap().parent, "main");
+        assert!(metadata.get_branch("c").unwrap().updated_at.is_some());
+        assert_eq!(metadata.get_branch("a").unwrap().parent, "main");
+    }
+
     #[test]
     fn test_metadata_serialization() {
         let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
@@ -2,17 +2,29 @@
 //!
 //! This module contains provider-agnostic core functionality for basalt:
 //!
+//! - **Configuration** — Load team-wide settings from `.basalt.toml`
 //! - **Environment checking** — Verify git repository, dependencies, authentication
 //! - **Git operations** — Wrapper around git commands
 //! - **Metadata management** — Store and retrieve stack metadata
-//! - **Stack detection** — Identify and validate linear branch stacks (TODO)
+//! - **Navigation** — List the stack in review descriptions
+//! - **Operation log** — Record branch and metadata changes for undo
+//! - **Rebase engine** — Replay commits in memory and move branches at the end
+//! - **Stack graph** — Traverse and restack the tree of tracked branches
+//! - **Templates** — Find review description templates in the repository
+//! - **Tracing** — Trace git, subprocess and HTTP calls with tokens redacted
+//! - **Validation** — Reject merge commits and empty branches in stacks
 //!
 //! All code in this module MUST be provider-agnostic. Provider-specific
 //! logic belongs in the `providers` module.
 
+pub mod config;
 pub mod environment;
 pub mod git;
 pub mod metadata;
-
-// Future modules:
-// pub mod stack;
+pub mod navigation;
+pub mod oplog;
+pub mod rebase;
+pub mod stack;
+pub mod templates;
+pub mod trace;
+pub mod validation;
@@ -0,0 +1,159 @@
+//! Stack navigation in review descriptions
+//!
+//! Submitted reviews carry a section listing every review of their stack,
+//! so reviewers can move between them. The section sits between two HTML
+//! comments, which the provider doesn't render, and is replaced as a whole
+//! each time the stack is submitted. The rest of the description is left
+//! as the author wrote it.
+//!
+//! The stack is listed from the top down to the base branch, with the
+//! review the description belongs to marked:
+//!
+//! ```text
+//! <!-- basalt:stack -->
+//! **Stack**
+//!
+//! - [!3](https://gitlab.com/group/project/-/merge_requests/3) feature-c
+//! - **[!2](https://gitlab.com/group/project/-/merge_requests/2) feature-b** 👈
+//! - [!1](https://gitlab.com/group/project/-/merge_requests/1) feature-a
+//! - `main`
+//! <!-- /basalt:stack -->
+//! ```
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::core::navigation;
+//!
+//! let block = navigation::render(&metadata, &stack, "feature-b");
+//! let description = navigation::apply(review.description.as_deref(), &block);
+//! ```
+
+use crate::core::metadata::Metadata;
+
+/// Comment opening the managed section
+const START_MARKER: &str = "<!-- basalt:stack -->";
+
+/// Comment closing the managed section
+const END_MARKER: &str = "<!-- /basalt:stack -->";
+
+/// Render the navigation section for the review of `current`
+///
+/// `stack` lists the stack's branches parents first. Branches without a
+/// review are left out; the base branch of the bottom one closes the list.
+pub fn render(metadata: &Metadata, stack: &[String], current: &str) -> String {
+    let mut lines = vec![
+        START_MARKER.to_string(),
+        "**Stack**".to_string(),
+        String::new(),
+    ];
+
+    for branch in stack.iter().rev() {
+        let Some(meta) = metadata.get_branch(branch) else {
+            continue;
+        };
+        let Some(review_id) = &meta.review_id else {
+            continue;
+        };
+        let review = match &meta.review_url {
+            Some(url) => format!("[{}]({}) {}", review_id, url, branch),
+            None => format!("{} {}", review_id, branch),
+        };
+        if branch == current {
+            lines.push(format!("- **{}** 👈", review));
+        } else {
+            lines.push(format!("- {}", review));
+        }
+    }
+
+    let base = stack
+        .first()
+        .and_then(|bottom| metadata.get_branch(bottom))
+        .map_or(metadata.base_branch.as_str(), |meta| meta.parent.as_str());
+    lines.push(format!("- `{}`", base));
+    lines.push(END_MARKER.to_string());
+
+    lines.join("\n")
+}
+
+/// Put a navigation section into a description
+///
+/// An existing section is replaced where it is; otherwise the section is
+/// appended after the description.
+pub fn apply(description: Option<&str>, block: &str) -> String {
+    let description = description.unwrap_or_default();
+
+    if let Some(start) = description.find(START_MARKER) {
+        if let Some(length) = description[start..].find(END_MARKER) {
+            let end = start + length + END_MARKER.len();
+            return format!("{}{}{}", &description[..start], block, &description[end..]);
+        }
+    }
+
+    let description = description.trim_end();
+    if description.is_empty() {
+        block.to_string()
+    } else {
+        format!("{}\n\n{}", description, block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::metadata::BranchMetadata;
+    use crate::providers::ProviderType;
+
+    fn stack_metadata() -> (Metadata, Vec<String>) {
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        let mut a = BranchMetadata::new("main".to_string());
+        a.set_review(
+            "!1".to_string(),
+            "https://gitlab.com/p/-/merge_requests/1".to_string(),
+        );
+        let mut c = BranchMetadata::new("b".to_string());
+        c.set_review(
+            "!3".to_string(),
+            "https://gitlab.com/p/-/merge_requests/3".to_string(),
+        );
+        metadata.set_branch("a".to_string(), a);
+        metadata.set_branch("b".to_string(), BranchMetadata::new("a".to_string()));
+        metadata.set_branch("c".to_string(), c);
+
+        let stack = ["a", "b", "c"].map(String::from).to_vec();
+        (metadata, stack)
+    }
+
+    #[test]
+    fn test_render() {
+        let (metadata, stack) = stack_metadata();
+
+        // 'b' has no review yet and is left out
+        assert_eq!(
+            render(&metadata, &stack, "a"),
+            "<!-- basalt:stack -->\n**Stack**\n\n\
+             - [!3](https://gitlab.com/p/-/merge_requests/3) c\n\
+             - **[!1](https://gitlab.com/p/-/merge_requests/1) a** 👈\n\
+             - `main`\n\
+             <!-- /basalt:stack -->"
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let block = format!("{}\nnew\n{}", START_MARKER, END_MARKER);
+
+        assert_eq!(apply(None, &block), block);
+        assert_eq!(
+            apply(Some("Adds login.\n"), &block),
+            format!("Adds login.\n\n{}", block)
+        );
+
+        // An existing section is replaced in place, keeping what surrounds it
+        let existing = format!("Intro\n\n{}\nold\n{}\n\nOutro", START_MARKER, END_MARKER);
+        assert_eq!(
+            apply(Some(&existing), &block),
+            format!("Intro\n\n{}\n\nOutro", block)
+        );
+    }
+}
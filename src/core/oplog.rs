@@ -0,0 +1,312 @@
+//! Operation log
+//!
+//! Every plan basalt applies is recorded as one YAML file in
+//! `.git/basalt/oplog/`. An entry holds the SHAs of the branches the
+//! operation changed, before and after, plus the metadata as it was before.
+//! `bt undo` uses the latest entry to put branches and metadata back.
+//!
+//! Changes made on the provider (reviews created, closed, merged, ...) and
+//! pushes can't be undone locally; entries list them so the user knows what
+//! is left to fix by hand.
+//!
+//! # Entry Format
+//!
+//! ```yaml
+//! id: 12
+//! command: restack
+//! created_at: "2024-01-01T00:00:00Z"
+//! head: feature-part-2
+//! refs:
+//!   - branch: feature-part-2
+//!     before: 3f2a...
+//!     after: 9c41...
+//!     pushed: false
+//! metadata: { ... }
+//! ```
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::core::oplog::{self, Snapshot};
+//!
+//! let before = Snapshot::capture()?;
+//! // ... rewrite branches ...
+//! oplog::record("restack", before, &[], Vec::new())?;
+//! ```
+
+#![allow(dead_code)] // Allow during early development
+
+use crate::core::metadata::{self, Metadata};
+use crate::core::{environment, git};
+use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Operation log directory name, inside `.git/basalt/`
+pub const OPLOG_DIRNAME: &str = "oplog";
+
+/// Number of entries kept; older ones are pruned when recording
+const MAX_ENTRIES: usize = 100;
+
+/// State of the repository before an operation
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Branch checked out, if any
+    pub head: Option<String>,
+    /// Commit of every local branch
+    pub branches: BTreeMap<String, String>,
+    /// Metadata, without the auth token
+    pub metadata: Metadata,
+}
+
+impl Snapshot {
+    /// Capture the current branches and metadata
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the branches or the metadata can't be read
+    pub fn capture() -> Result<Self> {
+        Ok(Self {
+            head: git::get_current_branch().ok(),
+            branches: git::list_branch_commits()?,
+            metadata: without_token(metadata::load_metadata()?),
+        })
+    }
+}
+
+/// How an operation changed one branch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefChange {
+    /// Branch name
+    pub branch: String,
+    /// Commit before the operation (`None` if the branch was created)
+    pub before: Option<String>,
+    /// Commit after the operation (`None` if the branch was deleted)
+    pub after: Option<String>,
+    /// Whether the operation pushed the new commit to the remote
+    #[serde(default)]
+    pub pushed: bool,
+}
+
+/// One recorded operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Sequence number, increasing
+    pub id: u64,
+    /// Command that ran (e.g. "restack")
+    pub command: String,
+    /// When the operation finished
+    pub created_at: DateTime<Utc>,
+    /// Branch checked out before the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+    /// Branches the operation changed
+    #[serde(default)]
+    pub refs: Vec<RefChange>,
+    /// Metadata before the operation, without the auth token
+    pub metadata: Metadata,
+    /// Provider and remote changes the operation made, which undo can't
+    /// revert
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remote_changes: Vec<String>,
+}
+
+/// Compare branch commits before and after an operation
+///
+/// Returns the changed branches sorted by name. Branches in `pushed` are
+/// flagged as pushed.
+pub fn ref_changes(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+    pushed: &[String],
+) -> Vec<RefChange> {
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .filter(|name| before.get(*name) != after.get(*name))
+        .map(|name| RefChange {
+            branch: name.clone(),
+            before: before.get(name).cloned(),
+            after: after.get(name).cloned(),
+            pushed: pushed.contains(name),
+        })
+        .collect()
+}
+
+/// Record an operation that started at `before`
+///
+/// Nothing is recorded if the operation didn't change any branch, the
+/// metadata, or anything on the provider.
+///
+/// # Arguments
+///
+/// * `command` - Name of the command
+/// * `before` - Snapshot taken before the operation
+/// * `pushed` - Branches the operation pushed
+/// * `remote_changes` - Descriptions of the provider and remote changes it
+///   made
+///
+/// # Errors
+///
+/// Returns an error if the current state can't be read or the entry can't
+/// be written
+pub fn record(
+    command: &str,
+    before: Snapshot,
+    pushed: &[String],
+    remote_changes: Vec<String>,
+) -> Result<Option<Entry>> {
+    let refs = ref_changes(&before.branches, &git::list_branch_commits()?, pushed);
+    let metadata_changed = serde_json::to_value(&before.metadata)?
+        != serde_json::to_value(without_token(metadata::load_metadata()?))?;
+
+    if refs.is_empty() && !metadata_changed && remote_changes.is_empty() {
+        return Ok(None);
+    }
+
+    let dir = oplog_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    let entries = list_entries()?;
+    let entry = Entry {
+        id: entries.last().map_or(1, |last| last.id + 1),
+        command: command.to_string(),
+        created_at: Utc::now(),
+        head: before.head,
+        refs,
+        metadata: before.metadata,
+        remote_changes,
+    };
+    fs::write(entry_path(entry.id)?, serde_yaml::to_string(&entry)?)?;
+
+    let excess = (entries.len() + 1).saturating_sub(MAX_ENTRIES);
+    for old in &entries[..excess] {
+        remove_entry(old.id)?;
+    }
+
+    Ok(Some(entry))
+}
+
+/// List recorded operations, oldest first
+///
+/// # Errors
+///
+/// Returns an error if an entry can't be read or parsed
+pub fn list_entries() -> Result<Vec<Entry>> {
+    let dir = oplog_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for dir_entry in fs::read_dir(&dir)? {
+        let path = dir_entry?.path();
+        if path.extension().is_some_and(|ext| ext == "yml") {
+            let contents = fs::read_to_string(&path)?;
+            let entry: Entry = serde_yaml::from_str(&contents).map_err(|e| {
+                Error::metadata(format!(
+                    "Invalid operation log entry {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            entries.push(entry);
+        }
+    }
+
+    entries.sort_by_key(|entry| entry.id);
+    Ok(entries)
+}
+
+/// Get the most recent operation
+///
+/// # Errors
+///
+/// Returns an error if the entries can't be read
+pub fn latest_entry() -> Result<Option<Entry>> {
+    Ok(list_entries()?.pop())
+}
+
+/// Remove an entry from the log
+///
+/// # Errors
+///
+/// Returns an error if the entry exists but can't be deleted
+pub fn remove_entry(id: u64) -> Result<()> {
+    let path = entry_path(id)?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Get the operation log directory
+fn oplog_dir() -> Result<PathBuf> {
+    Ok(environment::get_basalt_dir()?.join(OPLOG_DIRNAME))
+}
+
+/// Get the file of an entry
+fn entry_path(id: u64) -> Result<PathBuf> {
+    Ok(oplog_dir()?.join(format!("{:06}.yml", id)))
+}
+
+/// Drop the auth token so it isn't copied into every entry
+fn without_token(mut metadata: Metadata) -> Metadata {
+    metadata.auth_token = None;
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commits(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, sha)| (name.to_string(), sha.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_ref_changes() {
+        let before = commits(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let after = commits(&[("a", "1"), ("b", "4"), ("d", "5")]);
+
+        let changes = ref_changes(&before, &after, &["b".to_string()]);
+        assert_eq!(
+            changes,
+            vec![
+                RefChange {
+                    branch: "b".to_string(),
+                    before: Some("2".to_string()),
+                    after: Some("4".to_string()),
+                    pushed: true,
+                },
+                RefChange {
+                    branch: "c".to_string(),
+                    before: Some("3".to_string()),
+                    after: None,
+                    pushed: false,
+                },
+                RefChange {
+                    branch: "d".to_string(),
+                    before: None,
+                    after: Some("5".to_string()),
+                    pushed: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_changes() {
+        let same = commits(&[("a", "1")]);
+        assert!(ref_changes(&same, &same, &[]).is_empty());
+    }
+}
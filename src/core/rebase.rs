@@ -0,0 +1,659 @@
+//! In-memory rebase engine
+//!
+//! Rebases branches without touching the working tree: each commit is
+//! replayed onto its new base with a three-way tree merge, the new commits
+//! are written straight to the object database, and branches are moved to
+//! their new tips in a single ref transaction at the end.
+//!
+//! Like `git rebase`, merge commits are dropped, commits that become empty
+//! are dropped, and commits already based on the new base are kept as-is.
+//! Authors and messages are preserved; the committer is the current user.
+//!
+//! Commits whose changes are already upstream are skipped too. They're
+//! recognized by their patch-id, a fingerprint of the lines a commit adds
+//! and removes, so cherry-picked and rebased copies match. When a whole run
+//! of commits was squash-merged, the combined changes of those commits
+//! match the squashed commit, and the run is skipped as well.
+//!
+//! Only when a commit can't be replayed without conflicts does the engine
+//! fall back to `git rebase` for that branch, so the user can resolve the
+//! conflicts in the working tree and `bt restack --continue`. A branch
+//! checked out in another worktree is updated in that worktree, and
+//! refused if it conflicts, since it can't be rebased here.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::core::rebase::{self, Step};
+//!
+//! let steps = vec![Step {
+//!     branch: "feature".to_string(),
+//!     onto: "main".to_string(),
+//!     upstream: "a1b2c3d".to_string(),
+//! }];
+//! rebase::rebase_branches(&steps, |rebased| println!("✓ Rebased {}", rebased.branch))?;
+//! ```
+
+#![allow(dead_code)] // Allow during early development
+
+use crate::core::git;
+use crate::error::{Error, Result};
+use gix::ObjectId;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use tracing::instrument;
+
+/// Commit headers dropped from replayed commits, as their signature no
+/// longer matches
+const SIGNATURE_HEADERS: [&str; 2] = ["gpgsig", "gpgsig-sha256"];
+
+/// One branch to rebase, like `git rebase --onto <onto> <upstream> <branch>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// Branch whose commits are replayed
+    pub branch: String,
+    /// Revision to replay them onto; a branch rebased by an earlier step
+    /// resolves to its new tip
+    pub onto: String,
+    /// Revision the branch's own commits start after
+    pub upstream: String,
+}
+
+/// A branch replayed in memory, not yet moved to its new tip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rebased {
+    /// Branch name
+    pub branch: String,
+    /// Commit the branch pointed to before the rebase
+    pub old_tip: String,
+    /// Commit the branch points to after the rebase
+    pub new_tip: String,
+    /// Number of commits kept on the branch
+    pub replayed: usize,
+    /// Number of commits skipped because their changes are already upstream
+    pub skipped: usize,
+}
+
+/// The result of replaying steps in memory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Replay {
+    /// Branches replayed without conflicts, in step order
+    rebased: Vec<Rebased>,
+    /// The step that stopped on conflicts, if any
+    conflict: Option<Conflict>,
+}
+
+/// A step that stopped on conflicts
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Conflict {
+    /// Index of the step
+    index: usize,
+    /// Upstream for `git rebase`, past any commits found to be upstream
+    upstream: ObjectId,
+}
+
+/// The result of replaying one branch
+enum BranchReplay {
+    /// Every commit was replayed or skipped
+    Done {
+        tip: ObjectId,
+        replayed: usize,
+        skipped: usize,
+    },
+    /// A commit conflicts; `upstream` is where its branch's remaining
+    /// commits start
+    Conflict { upstream: ObjectId },
+}
+
+/// Fingerprint of the changes a commit makes, like `git patch-id`
+///
+/// Only meaningful within one run; it isn't compatible with git's.
+type PatchId = u64;
+
+/// Patch-ids of commits computed so far, `None` for commits without changes
+type PatchIdCache = HashMap<ObjectId, Option<PatchId>>;
+
+/// Rebase branches, replaying them in memory where possible
+///
+/// Steps run in order, so a branch must come after the branch it's rebased
+/// onto. Every upstream is resolved before anything is rebased, so steps
+/// may refer to commits of branches rewritten by earlier steps.
+///
+/// When a step conflicts, the branches replayed so far are updated, and
+/// that step runs through `git rebase`. If `git rebase` manages, the
+/// remaining steps continue in memory.
+///
+/// # Arguments
+///
+/// * `steps` - Branches to rebase, parents first
+/// * `on_rebased` - Called for each branch once it's been rebased
+///
+/// # Errors
+///
+/// Returns `Error::RebaseConflict` if `git rebase` stops on conflicts, with
+/// the remaining branches left untouched, or an error if a revision can't
+/// be resolved or a commit can't be written
+#[instrument(level = "debug", skip(on_rebased), err(level = "debug"))]
+pub fn rebase_branches(steps: &[Step], mut on_rebased: impl FnMut(&Rebased)) -> Result<()> {
+    let repo = git::open_repo()?;
+    let upstreams = steps
+        .iter()
+        .map(|step| resolve(&repo, &step.upstream))
+        .collect::<Result<Vec<_>>>()?;
+    let mut patch_ids = PatchIdCache::new();
+
+    let mut start = 0;
+    while start < steps.len() {
+        let replay = replay(&repo, &steps[start..], &upstreams[start..], &mut patch_ids)?;
+        update_branches(&repo, &replay.rebased)?;
+        for rebased in &replay.rebased {
+            on_rebased(rebased);
+        }
+
+        let Some(conflict) = replay.conflict else {
+            break;
+        };
+        let step = &steps[start + conflict.index];
+        if let Some(worktree) = git::checked_out_branches()?
+            .remove(&step.branch)
+            .filter(|worktree| !worktree.is_current)
+        {
+            return Err(Error::BranchCheckedOutElsewhere {
+                branch: step.branch.clone(),
+                path: worktree.path,
+            });
+        }
+        let old_tip = resolve(&repo, &step.branch)?;
+        let onto = resolve(&repo, &step.onto)?;
+        git::rebase_onto(
+            &onto.to_string(),
+            &conflict.upstream.to_string(),
+            &step.branch,
+        )?;
+        let new_tip = git::get_branch_commit(&step.branch)?;
+        on_rebased(&Rebased {
+            branch: step.branch.clone(),
+            old_tip: old_tip.to_string(),
+            replayed: git::commits_between(&new_tip, &[onto.to_string()])?.len(),
+            new_tip,
+            skipped: 0,
+        });
+        start += conflict.index + 1;
+    }
+
+    Ok(())
+}
+
+/// Replay steps in memory, stopping at the first conflict
+///
+/// Nothing but new objects is written; see [`update_branches`].
+///
+/// # Errors
+///
+/// Returns an error if a revision can't be resolved or an object can't be
+/// read or written
+fn replay(
+    repo: &gix::Repository,
+    steps: &[Step],
+    upstreams: &[ObjectId],
+    patch_ids: &mut PatchIdCache,
+) -> Result<Replay> {
+    let committer = committer(repo)?;
+    let mut new_tips: HashMap<&str, ObjectId> = HashMap::new();
+    let mut result = Replay::default();
+
+    for (index, (step, upstream)) in steps.iter().zip(upstreams).enumerate() {
+        let _span =
+            tracing::debug_span!("replay", branch = %step.branch, onto = %step.onto).entered();
+        let onto = match new_tips.get(step.onto.as_str()) {
+            Some(tip) => *tip,
+            None => resolve(repo, &step.onto)?,
+        };
+        let old_tip = resolve(repo, &step.branch)?;
+
+        match replay_commits(repo, &committer, patch_ids, onto, *upstream, old_tip)? {
+            BranchReplay::Done {
+                tip,
+                replayed,
+                skipped,
+            } => {
+                tracing::debug!(%tip, replayed, skipped, "replayed in memory");
+                new_tips.insert(&step.branch, tip);
+                result.rebased.push(Rebased {
+                    branch: step.branch.clone(),
+                    old_tip: old_tip.to_string(),
+                    new_tip: tip.to_string(),
+                    replayed,
+                    skipped,
+                });
+            }
+            BranchReplay::Conflict { upstream } => {
+                tracing::debug!("conflict, falling back to git rebase");
+                result.conflict = Some(Conflict { index, upstream });
+                return Ok(result);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Replay the commits in `upstream..tip` onto `onto`
+///
+/// Commits whose changes are already in `upstream..onto` are skipped.
+fn replay_commits(
+    repo: &gix::Repository,
+    committer: &gix::actor::Signature,
+    patch_ids: &mut PatchIdCache,
+    onto: ObjectId,
+    upstream: ObjectId,
+    tip: ObjectId,
+) -> Result<BranchReplay> {
+    let mut commits = linear_commits(repo, tip, upstream)?;
+    let total = commits.len();
+    let mut upstream = upstream;
+
+    let upstream_ids = if commits.is_empty() {
+        HashSet::new()
+    } else {
+        upstream_patch_ids(repo, patch_ids, onto, upstream)?
+    };
+    if let Some(squashed) = squashed_prefix(repo, &commits, &upstream_ids)? {
+        upstream = commits[squashed];
+        commits.drain(..=squashed);
+    }
+
+    let options = repo
+        .tree_merge_options()
+        .map_err(|e| Error::git(format!("Failed to read merge options: {}", e)))?
+        .with_fail_on_conflict(Some(gix::merge::tree::TreatAsUnresolved::git()));
+
+    let mut base = onto;
+    let mut replayed = 0;
+    for id in commits {
+        let commit = find_commit(repo, id)?;
+        let parent = commit.parent_ids().next().map(|parent| parent.detach());
+
+        // Already on top of the new base, so there's nothing to rewrite
+        if parent == Some(base) {
+            base = id;
+            replayed += 1;
+            continue;
+        }
+        if commit_patch_id(repo, patch_ids, id)?.is_some_and(|pid| upstream_ids.contains(&pid)) {
+            continue;
+        }
+
+        let tree = commit_tree(repo, id)?;
+        let parent_tree = parent_tree(repo, parent)?;
+        let base_tree = commit_tree(repo, base)?;
+
+        let labels = gix::merge::blob::builtin_driver::text::Labels {
+            ancestor: None,
+            current: None,
+            other: None,
+        };
+        let mut outcome = repo
+            .merge_trees(parent_tree, base_tree, tree, labels, options.clone())
+            .map_err(|e| Error::git(format!("Failed to replay {}: {}", id, e)))?;
+        if outcome.has_unresolved_conflicts(gix::merge::tree::TreatAsUnresolved::git()) {
+            return Ok(BranchReplay::Conflict { upstream });
+        }
+        let new_tree = outcome
+            .tree
+            .write()
+            .map_err(|e| Error::git(format!("Failed to write tree for {}: {}", id, e)))?
+            .detach();
+
+        // Drop commits whose changes are already in the new base
+        if new_tree == base_tree && tree != parent_tree {
+            continue;
+        }
+
+        let mut rewritten: gix::objs::Commit = commit
+            .decode()
+            .map_err(|e| Error::git(format!("Failed to read commit {}: {}", id, e)))?
+            .try_into()
+            .map_err(|e| Error::git(format!("Failed to read commit {}: {}", id, e)))?;
+        rewritten.tree = new_tree;
+        rewritten.parents = [base].into_iter().collect();
+        rewritten.committer = committer.clone();
+        rewritten
+            .extra_headers
+            .retain(|(name, _)| !SIGNATURE_HEADERS.iter().any(|header| name == header));
+
+        base = repo
+            .write_object(&rewritten)
+            .map_err(|e| Error::git(format!("Failed to write commit: {}", e)))?
+            .detach();
+        replayed += 1;
+    }
+
+    Ok(BranchReplay::Done {
+        tip: base,
+        replayed,
+        skipped: total - replayed,
+    })
+}
+
+/// List the non-merge commits in `hidden..tip`, oldest first
+fn linear_commits(
+    repo: &gix::Repository,
+    tip: ObjectId,
+    hidden: ObjectId,
+) -> Result<Vec<ObjectId>> {
+    let walk = repo
+        .rev_walk([tip])
+        .with_hidden([hidden])
+        .all()
+        .map_err(|e| walk_error(tip, e))?;
+
+    let mut commits = Vec::new();
+    for info in walk {
+        let info = info.map_err(|e| walk_error(tip, e))?;
+        if info.parent_ids.len() <= 1 {
+            commits.push(info.id);
+        }
+    }
+    commits.reverse();
+    Ok(commits)
+}
+
+/// Get the patch-ids of the commits in `upstream..onto`
+fn upstream_patch_ids(
+    repo: &gix::Repository,
+    patch_ids: &mut PatchIdCache,
+    onto: ObjectId,
+    upstream: ObjectId,
+) -> Result<HashSet<PatchId>> {
+    let mut ids = HashSet::new();
+    for id in linear_commits(repo, onto, upstream)? {
+        ids.extend(commit_patch_id(repo, patch_ids, id)?);
+    }
+    Ok(ids)
+}
+
+/// Find the longest run of two or more commits, starting with the first,
+/// whose combined changes match a commit upstream
+///
+/// Returns the index of the run's last commit.
+fn squashed_prefix(
+    repo: &gix::Repository,
+    commits: &[ObjectId],
+    upstream_ids: &HashSet<PatchId>,
+) -> Result<Option<usize>> {
+    let Some(&first) = commits.first() else {
+        return Ok(None);
+    };
+    if upstream_ids.is_empty() {
+        return Ok(None);
+    }
+
+    let first_parent = find_commit(repo, first)?
+        .parent_ids()
+        .next()
+        .map(|parent| parent.detach());
+    let base_tree = parent_tree(repo, first_parent)?;
+
+    for index in (1..commits.len()).rev() {
+        let tree = commit_tree(repo, commits[index])?;
+        if patch_id(repo, base_tree, tree)?.is_some_and(|pid| upstream_ids.contains(&pid)) {
+            return Ok(Some(index));
+        }
+    }
+    Ok(None)
+}
+
+/// Get the patch-id of a commit, compared with its first parent
+fn commit_patch_id(
+    repo: &gix::Repository,
+    patch_ids: &mut PatchIdCache,
+    id: ObjectId,
+) -> Result<Option<PatchId>> {
+    if let Some(patch_id) = patch_ids.get(&id) {
+        return Ok(*patch_id);
+    }
+
+    let parent = find_commit(repo, id)?
+        .parent_ids()
+        .next()
+        .map(|parent| parent.detach());
+    let patch_id = patch_id(repo, parent_tree(repo, parent)?, commit_tree(repo, id)?)?;
+    patch_ids.insert(id, patch_id);
+    Ok(patch_id)
+}
+
+/// Fingerprint the changes between two trees
+///
+/// Like `git patch-id`, this hashes the changed paths and the lines added
+/// and removed with whitespace ignored, but not line numbers, so the same
+/// change applied elsewhere in a file matches. Unlike it, context lines are
+/// left out. Binary files are compared by content. Returns `None` if the
+/// trees are the same.
+fn patch_id(
+    repo: &gix::Repository,
+    old_tree: ObjectId,
+    new_tree: ObjectId,
+) -> Result<Option<PatchId>> {
+    use gix::object::tree::diff::ChangeDetached;
+
+    if old_tree == new_tree {
+        return Ok(None);
+    }
+    let diff_error = |e: &dyn std::fmt::Display| {
+        Error::git(format!(
+            "Failed to diff {} and {}: {}",
+            old_tree, new_tree, e
+        ))
+    };
+    let old = repo.find_tree(old_tree).map_err(|e| diff_error(&e))?;
+    let new = repo.find_tree(new_tree).map_err(|e| diff_error(&e))?;
+    let mut changes = repo
+        .diff_tree_to_tree(&old, &new, gix::diff::Options::default())
+        .map_err(|e| diff_error(&e))?;
+    changes.sort_by(|a, b| a.location().cmp(b.location()));
+
+    let mut hasher = DefaultHasher::new();
+    for change in &changes {
+        let (before, after) = match change {
+            ChangeDetached::Addition { entry_mode, id, .. } => (None, Some((*entry_mode, *id))),
+            ChangeDetached::Deletion { entry_mode, id, .. } => (Some((*entry_mode, *id)), None),
+            ChangeDetached::Modification {
+                previous_entry_mode,
+                previous_id,
+                entry_mode,
+                id,
+                ..
+            } => (
+                Some((*previous_entry_mode, *previous_id)),
+                Some((*entry_mode, *id)),
+            ),
+            // Not produced without rename tracking, but harmless to support
+            ChangeDetached::Rewrite {
+                source_entry_mode,
+                source_id,
+                entry_mode,
+                id,
+                ..
+            } => (
+                Some((*source_entry_mode, *source_id)),
+                Some((*entry_mode, *id)),
+            ),
+        };
+        if [before, after]
+            .iter()
+            .flatten()
+            .any(|(mode, _)| mode.is_tree())
+        {
+            continue;
+        }
+
+        change.location().hash(&mut hasher);
+        before.map(|(mode, _)| mode.value()).hash(&mut hasher);
+        after.map(|(mode, _)| mode.value()).hash(&mut hasher);
+
+        let before = blob_data(repo, before.map(|(_, id)| id))?;
+        let after = blob_data(repo, after.map(|(_, id)| id))?;
+        if is_binary(&before) || is_binary(&after) {
+            before.hash(&mut hasher);
+            after.hash(&mut hasher);
+        } else {
+            hash_line_changes(&before, &after, &mut hasher);
+        }
+    }
+
+    Ok(Some(hasher.finish()))
+}
+
+/// Hash the lines removed and added between two texts, without whitespace
+fn hash_line_changes(before: &[u8], after: &[u8], hasher: &mut DefaultHasher) {
+    use gix::diff::blob::{Algorithm, diff, intern::InternedInput};
+
+    let input = InternedInput::new(before, after);
+    diff(
+        Algorithm::Histogram,
+        &input,
+        |removed: std::ops::Range<u32>, added: std::ops::Range<u32>| {
+            for (sign, tokens) in [
+                (
+                    b'-',
+                    &input.before[removed.start as usize..removed.end as usize],
+                ),
+                (b'+', &input.after[added.start as usize..added.end as usize]),
+            ] {
+                for token in tokens {
+                    sign.hash(hasher);
+                    input.interner[*token]
+                        .iter()
+                        .filter(|byte| !byte.is_ascii_whitespace())
+                        .for_each(|byte| byte.hash(hasher));
+                }
+            }
+        },
+    );
+}
+
+/// Read a blob, or nothing for a missing side of a change
+fn blob_data(repo: &gix::Repository, id: Option<ObjectId>) -> Result<Vec<u8>> {
+    let Some(id) = id else {
+        return Ok(Vec::new());
+    };
+    let blob = repo
+        .find_blob(id)
+        .map_err(|e| Error::git(format!("Failed to read blob {}: {}", id, e)))?;
+    Ok(blob.detach().data)
+}
+
+/// Check whether data looks binary, the way git does
+fn is_binary(data: &[u8]) -> bool {
+    data.iter().take(8000).any(|byte| *byte == 0)
+}
+
+/// Move rebased branches to their new tips
+///
+/// Branches are updated in a single transaction that fails if any of them
+/// moved in the meantime. Branches checked out in a worktree, this one or
+/// another, are updated with `git reset --keep` there instead, so their
+/// working tree follows.
+///
+/// # Errors
+///
+/// Returns an error if a branch moved since it was replayed, or the refs
+/// or a worktree can't be updated
+#[instrument(level = "debug", skip_all, err(level = "debug"))]
+fn update_branches(repo: &gix::Repository, rebased: &[Rebased]) -> Result<()> {
+    use gix::refs::Target;
+    use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit};
+
+    let worktrees = git::checked_out_branches()?;
+    let mut checked_out = Vec::new();
+    let mut edits = Vec::new();
+    for rebased in rebased {
+        if rebased.old_tip == rebased.new_tip {
+            continue;
+        }
+        if let Some(worktree) = worktrees.get(&rebased.branch) {
+            checked_out.push((worktree, rebased));
+            continue;
+        }
+
+        let name = format!("refs/heads/{}", rebased.branch);
+        edits.push(RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    message: format!("bt: rebase onto {}", rebased.new_tip).into(),
+                    ..Default::default()
+                },
+                expected: PreviousValue::MustExistAndMatch(Target::Object(parse_id(
+                    &rebased.old_tip,
+                )?)),
+                new: Target::Object(parse_id(&rebased.new_tip)?),
+            },
+            name: name
+                .as_str()
+                .try_into()
+                .map_err(|e| Error::git(format!("Invalid branch name '{}': {}", name, e)))?,
+            deref: false,
+        });
+    }
+
+    repo.edit_references(edits)
+        .map_err(|e| Error::git(format!("Failed to update branches: {}", e)))?;
+    for (worktree, rebased) in checked_out {
+        git::reset_keep_in(&worktree.path, &rebased.new_tip)?;
+    }
+
+    Ok(())
+}
+
+/// Resolve a revision to a commit
+fn resolve(repo: &gix::Repository, revision: &str) -> Result<ObjectId> {
+    let spec = format!("{}^{{commit}}", revision);
+    repo.rev_parse_single(spec.as_str())
+        .map(|id| id.detach())
+        .map_err(|e| Error::git(format!("Failed to resolve '{}': {}", revision, e)))
+}
+
+/// Parse a full commit SHA
+fn parse_id(sha: &str) -> Result<ObjectId> {
+    ObjectId::from_hex(sha.as_bytes())
+        .map_err(|e| Error::git(format!("Invalid commit SHA '{}': {}", sha, e)))
+}
+
+/// Look up a commit
+fn find_commit(repo: &gix::Repository, id: ObjectId) -> Result<gix::Commit<'_>> {
+    repo.find_commit(id)
+        .map_err(|e| Error::git(format!("Failed to find commit {}: {}", id, e)))
+}
+
+/// Get the tree of a commit's parent, or the empty tree for a root commit
+fn parent_tree(repo: &gix::Repository, parent: Option<ObjectId>) -> Result<ObjectId> {
+    match parent {
+        Some(parent) => commit_tree(repo, parent),
+        None => Ok(ObjectId::empty_tree(repo.object_hash())),
+    }
+}
+
+/// Get the tree of a commit
+fn commit_tree(repo: &gix::Repository, id: ObjectId) -> Result<ObjectId> {
+    find_commit(repo, id)?
+        .tree_id()
+        .map(|tree| tree.detach())
+        .map_err(|e| Error::git(format!("Failed to read commit {}: {}", id, e)))
+}
+
+/// Get the signature for new commits, as `git commit` would
+fn committer(repo: &gix::Repository) -> Result<gix::actor::Signature> {
+    let signature = repo
+        .committer()
+        .ok_or_else(|| Error::git("Committer identity unknown. Set user.name and user.email"))?
+        .map_err(|e| Error::git(format!("Invalid committer identity: {}", e)))?;
+    signature
+        .to_owned()
+        .map_err(|e| Error::git(format!("Invalid committer identity: {}", e)))
+}
+
+/// Describe a failed revision walk
+fn walk_error(tip: ObjectId, e: impl std::fmt::Display) -> Error {
+    Error::git(format!("Failed to walk history of {}: {}", tip, e))
+}
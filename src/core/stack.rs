@@ -0,0 +1,174 @@
+//! Stack graph traversal and restacking
+//!
+//! This module interprets the branch metadata as a tree of stacked branches.
+//! Every tracked branch records its parent; the base branch (e.g. `main`) is
+//! the implicit root of every stack.
+//!
+//! # Terminology
+//!
+//! - **Children**: tracked branches whose parent is the given branch
+//! - **Descendants** (upstack): children, their children, and so on
+//! - **Ancestors** (downstack): the parent chain down to the base branch
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::core::{metadata, stack};
+//!
+//! let metadata = metadata::load_metadata()?;
+//! for branch in stack::descendants(&metadata, "feature-part-1") {
+//!     println!("upstack: {}", branch);
+//! }
+//! ```
+
+#![allow(dead_code)] // Allow during early development
+
+use crate::core::git;
+use crate::core::metadata::Metadata;
+use crate::error::Result;
+
+/// Get the tracked branches whose parent is `branch`
+///
+/// Results are sorted by name so traversal order is deterministic.
+pub fn children(metadata: &Metadata, branch: &str) -> Vec<String> {
+    let mut children: Vec<String> = metadata
+        .branches
+        .iter()
+        .filter(|(_, meta)| meta.parent == branch)
+        .map(|(name, _)| name.clone())
+        .collect();
+    children.sort();
+    children
+}
+
+/// Get every tracked branch stacked on top of `branch`
+///
+/// Branches are returned parents first, so rebasing them in order
+/// always rebases a parent before its children.
+pub fn descendants(metadata: &Metadata, branch: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut queue = children(metadata, branch);
+
+    while !queue.is_empty() {
+        let next = queue.remove(0);
+        queue.extend(children(metadata, &next));
+        result.push(next);
+    }
+
+    result
+}
+
+/// Get the parent chain of `branch`, nearest first
+///
+/// The chain stops at the first branch that isn't tracked, which is
+/// normally the base branch. The base branch itself is not included.
+pub fn ancestors(metadata: &Metadata, branch: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = branch;
+
+    while let Some(meta) = metadata.get_branch(current) {
+        let parent = meta.parent.as_str();
+        if !metadata.has_branch(parent) || result.iter().any(|b| b == parent) {
+            break;
+        }
+        result.push(parent.to_string());
+        current = parent;
+    }
+
+    result
+}
+
+/// Check whether `candidate` is stacked (directly or not) on top of `branch`
+pub fn is_descendant(metadata: &Metadata, candidate: &str, branch: &str) -> bool {
+    ancestors(metadata, candidate).iter().any(|b| b == branch)
+        || metadata
+            .get_branch(candidate)
+            .is_some_and(|meta| meta.parent == branch)
+}
+
+/// Get the roots of every stack in the metadata
+///
+/// A root is a parent that isn't itself a tracked branch. This is
+/// normally only the base branch, but branches whose parent was deleted
+/// outside basalt show up as extra roots. The base branch comes first.
+pub fn roots(metadata: &Metadata) -> Vec<String> {
+    let mut roots: Vec<String> = metadata
+        .branches
+        .values()
+        .map(|meta| meta.parent.clone())
+        .filter(|parent| !metadata.has_branch(parent) && parent != &metadata.base_branch)
+        .collect();
+    roots.sort();
+    roots.dedup();
+    roots.insert(0, metadata.base_branch.clone());
+    roots
+}
+
+/// Check whether a branch is no longer based on the tip of its parent
+///
+/// # Errors
+///
+/// Returns an error if the branch or its parent doesn't exist
+pub fn needs_restack(metadata: &Metadata, branch: &str) -> Result<bool> {
+    match metadata.get_branch(branch) {
+        Some(meta) => Ok(!git::is_ancestor(&meta.parent, branch)?),
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::metadata::BranchMetadata;
+    use crate::providers::ProviderType;
+
+    /// main <- a <- b <- c, and a <- d
+    fn sample_metadata() -> Metadata {
+        let mut metadata = Metadata::new(ProviderType::GitLab, "main".to_string());
+        metadata.set_branch("a".to_string(), BranchMetadata::new("main".to_string()));
+        metadata.set_branch("b".to_string(), BranchMetadata::new("a".to_string()));
+        metadata.set_branch("c".to_string(), BranchMetadata::new("b".to_string()));
+        metadata.set_branch("d".to_string(), BranchMetadata::new("a".to_string()));
+        metadata
+    }
+
+    #[test]
+    fn test_children() {
+        let metadata = sample_metadata();
+        assert_eq!(children(&metadata, "main"), vec!["a"]);
+        assert_eq!(children(&metadata, "a"), vec!["b", "d"]);
+        assert!(children(&metadata, "c").is_empty());
+    }
+
+    #[test]
+    fn test_descendants_parents_first() {
+        let metadata = sample_metadata();
+        assert_eq!(descendants(&metadata, "a"), vec!["b", "d", "c"]);
+        assert_eq!(descendants(&metadata, "main"), vec!["a", "b", "d", "c"]);
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let metadata = sample_metadata();
+        assert_eq!(ancestors(&metadata, "c"), vec!["b", "a"]);
+        assert!(ancestors(&metadata, "a").is_empty());
+    }
+
+    #[test]
+    fn test_roots() {
+        let mut metadata = sample_metadata();
+        assert_eq!(roots(&metadata), vec!["main"]);
+
+        metadata.set_branch("e".to_string(), BranchMetadata::new("gone".to_string()));
+        assert_eq!(roots(&metadata), vec!["main", "gone"]);
+    }
+
+    #[test]
+    fn test_is_descendant() {
+        let metadata = sample_metadata();
+        assert!(is_descendant(&metadata, "c", "a"));
+        assert!(is_descendant(&metadata, "a", "main"));
+        assert!(!is_descendant(&metadata, "a", "c"));
+        assert!(!is_descendant(&metadata, "d", "b"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Review description templates
+//!
+//! Finds the merge/pull request templates committed to the repository so
+//! they can pre-fill review descriptions, like the provider's web UI does.
+//!
+//! Templates are searched in these locations, in order:
+//! 1. `.gitlab/merge_request_templates/*.md` (GitLab, `Default.md` first)
+//! 2. `pull_request_template.*` in the repository root, `.github/` and `docs/`
+//! 3. Files in a `PULL_REQUEST_TEMPLATE/` directory in those same locations
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::core::{git, templates};
+//!
+//! let root = git::get_repo_root()?;
+//! for template in templates::find_templates(&root) {
+//!     println!("{}", template.name);
+//! }
+//! ```
+
+#![allow(dead_code)] // Allow during early development
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A review description template found in the repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    /// Path relative to the repository root, for display
+    pub name: String,
+    /// Absolute path of the template file
+    pub path: PathBuf,
+}
+
+impl Template {
+    /// Read the template's contents
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read
+    pub fn read(&self) -> crate::error::Result<String> {
+        Ok(fs::read_to_string(&self.path)?)
+    }
+}
+
+/// Find all review templates in the repository
+///
+/// Unreadable directories are skipped.
+pub fn find_templates(repo_root: &Path) -> Vec<Template> {
+    let mut paths = gitlab_templates(repo_root);
+
+    let locations: Vec<PathBuf> = ["", ".github", "docs"]
+        .iter()
+        .map(|dir| repo_root.join(dir))
+        .filter(|dir| dir.is_dir())
+        .collect();
+
+    for location in &locations {
+        paths.extend(
+            read_dir_sorted(location)
+                .into_iter()
+                .filter(|path| path.is_file() && has_stem(path, "pull_request_template")),
+        );
+    }
+    for location in &locations {
+        for dir in read_dir_sorted(location) {
+            if dir.is_dir() && has_stem(&dir, "pull_request_template") {
+                paths.extend(read_dir_sorted(&dir).into_iter().filter(|p| p.is_file()));
+            }
+        }
+    }
+
+    paths
+        .into_iter()
+        .map(|path| Template {
+            name: path
+                .strip_prefix(repo_root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned(),
+            path,
+        })
+        .collect()
+}
+
+/// Find GitLab templates, with `Default.md` first
+fn gitlab_templates(repo_root: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> =
+        read_dir_sorted(&repo_root.join(".gitlab/merge_request_templates"))
+            .into_iter()
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+            })
+            .collect();
+
+    if let Some(index) = paths.iter().position(|path| has_stem(path, "default")) {
+        let default = paths.remove(index);
+        paths.insert(0, default);
+    }
+
+    paths
+}
+
+/// List a directory's entries sorted by name, or nothing if it can't be read
+fn read_dir_sorted(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    paths.sort();
+    paths
+}
+
+/// Check whether a path's name without extension matches, ignoring case
+fn has_stem(path: &Path, stem: &str) -> bool {
+    path.file_stem()
+        .is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(stem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch(root: &Path, path: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "template").unwrap();
+    }
+
+    fn names(root: &Path) -> Vec<String> {
+        find_templates(root).into_iter().map(|t| t.name).collect()
+    }
+
+    #[test]
+    fn test_no_templates() {
+        let dir = TempDir::new().unwrap();
+        assert!(find_templates(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_gitlab_templates_default_first() {
+        let dir = TempDir::new().unwrap();
+        touch(dir.path(), ".gitlab/merge_request_templates/Bug.md");
+        touch(dir.path(), ".gitlab/merge_request_templates/Default.md");
+        touch(dir.path(), ".gitlab/merge_request_templates/notes.txt");
+
+        assert_eq!(
+            names(dir.path()),
+            vec![
+                ".gitlab/merge_request_templates/Default.md",
+                ".gitlab/merge_request_templates/Bug.md",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_github_templates_in_precedence_order() {
+        let dir = TempDir::new().unwrap();
+        touch(dir.path(), ".github/PULL_REQUEST_TEMPLATE/feature.md");
+        touch(dir.path(), "docs/pull_request_template.md");
+        touch(dir.path(), ".github/pull_request_template.md");
+
+        assert_eq!(
+            names(dir.path()),
+            vec![
+                ".github/pull_request_template.md",
+                "docs/pull_request_template.md",
+                ".github/PULL_REQUEST_TEMPLATE/feature.md",
+            ]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Stack validation
+//!
+//! Stacked branches must be linear: each branch is a sequence of its own
+//! commits on top of its parent. Merge commits can't be restacked or
+//! reviewed one branch at a time, and a branch without commits has nothing
+//! to review, so both are rejected before submitting or restacking.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::core::{metadata, validation};
+//!
+//! let metadata = metadata::load_metadata()?;
+//! validation::validate_branches(&metadata, &["feature-part-1".to_string()], false)?;
+//! ```
+
+#![allow(dead_code)] // Allow during early development
+
+use crate::core::git::{self, BranchCommit};
+use crate::core::metadata::Metadata;
+use crate::error::{Error, Result};
+
+/// Get the commits a branch adds on top of its parent, newest first
+///
+/// Commits reachable from the parent's tip or from the branch's fork point
+/// are excluded, so a parent rewritten since the branch was created doesn't
+/// make its old commits look like the branch's own.
+///
+/// # Errors
+///
+/// Returns an error if either branch doesn't exist
+pub fn own_commits(branch: &str, parent: &str) -> Result<Vec<BranchCommit>> {
+    let tip = git::get_branch_commit(branch)?;
+    let hidden = vec![
+        git::get_branch_commit(parent)?,
+        git::fork_point(parent, branch)?,
+    ];
+    git::commits_between(&tip, &hidden)
+}
+
+/// Check that a tracked branch is a linear series of its own commits
+///
+/// # Errors
+///
+/// Returns:
+/// - `Error::MergeCommitInStack` if the branch contains a merge commit
+/// - `Error::EmptyStack` if it has no commits and `allow_empty` is false
+pub fn validate_branch(metadata: &Metadata, branch: &str, allow_empty: bool) -> Result<()> {
+    let parent = metadata
+        .get_branch(branch)
+        .map(|meta| meta.parent.clone())
+        .ok_or_else(|| Error::invalid_stack(format!("Branch '{}' is not tracked", branch)))?;
+
+    let commits = own_commits(branch, &parent)?;
+    check_commits(branch, &parent, &commits, allow_empty)
+}
+
+/// Validate each of the given tracked branches
+///
+/// # Errors
+///
+/// Returns the first validation error, see [`validate_branch`]
+pub fn validate_branches(
+    metadata: &Metadata,
+    branches: &[String],
+    allow_empty: bool,
+) -> Result<()> {
+    for branch in branches {
+        validate_branch(metadata, branch, allow_empty)?;
+    }
+    Ok(())
+}
+
+/// Check a branch's own commits
+fn check_commits(
+    branch: &str,
+    parent: &str,
+    commits: &[BranchCommit],
+    allow_empty: bool,
+) -> Result<()> {
+    if commits.iter().any(BranchCommit::is_merge) {
+        return Err(Error::MergeCommitInStack {
+            branch: branch.to_string(),
+            parent: parent.to_string(),
+        });
+    }
+    if commits.is_empty() && !allow_empty {
+        return Err(Error::EmptyStack {
+            current_branch: branch.to_string(),
+            base_branch: parent.to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(parent_count: usize) -> BranchCommit {
+        BranchCommit {
+            id: "0".repeat(40),
+            parent_count,
+        }
+    }
+
+    #[test]
+    fn test_check_commits() {
+        assert!(check_commits("b", "a", &[commit(1), commit(1)], false).is_ok());
+        assert!(matches!(
+            check_commits("b", "a", &[commit(1), commit(2)], false),
+            Err(Error::MergeCommitInStack { branch, .. }) if branch == "b"
+        ));
+        assert!(matches!(
+            check_commits("b", "a", &[], false),
+            Err(Error::EmptyStack { .. })
+        ));
+        assert!(check_commits("b", "a", &[], true).is_ok());
+    }
+}
@@ -2,6 +2,29 @@
 //!
 //! This module defines all error types used throughout the application.
 //! We use thiserror for ergonomic error handling with proper context.
+//!
+//! # Exit codes
+//!
+//! `bt` exits with a code that depends on the kind of error, so scripts
+//! can react without parsing messages. With `--output json`, the error is
+//! also written to stderr as `{"kind": ..., "message": ..., "exit_code": ...}`.
+//!
+//! | Code | Kinds |
+//! |------|-------|
+//! | 0    | success |
+//! | 1    | `other`, `io`, `git`, `command_failed`, `config` and parse errors |
+//! | 2    | invalid command-line usage (reported by clap) |
+//! | 3    | `not_in_git_repository` |
+//! | 4    | `not_initialized`, `metadata_not_found` |
+//! | 5    | `already_initialized` |
+//! | 6    | `metadata`, `unsupported_metadata_version` |
+//! | 7    | `uncommitted_changes` |
+//! | 8    | `rebase_in_progress` |
+//! | 9    | `rebase_conflict`, `branch_checked_out_elsewhere` |
+//! | 10   | `invalid_stack`, `merge_commit_in_stack`, `empty_stack`, `branch_not_found` |
+//! | 11   | `provider_auth_required`, `provider_cli_not_found` |
+//! | 12   | `provider_operation_failed`, `provider_detection_failed`, `unknown_provider`, `review_not_found` |
+//! | 13   | `remote_branch_changed` |
 
 #![allow(dead_code)] // Allow during early development
 
@@ -56,9 +79,9 @@ pub enum Error {
 
     /// Merge commits in stack
     #[error(
-        "Stack contains merge commits. Stacks must be linear.\n\nBranch '{branch}' has a merge commit.\nUse 'git log --graph --oneline' to visualize the branch history."
+        "Stack contains merge commits. Stacks must be linear.\n\nBranch '{branch}' has a merge commit.\nUse 'git log --graph --oneline' to visualize the branch history, then linearize it with:\n  git checkout {branch} && git rebase {parent}"
     )]
-    MergeCommitInStack { branch: String },
+    MergeCommitInStack { branch: String, parent: String },
 
     /// No commits in stack
     #[error(
@@ -69,6 +92,12 @@ pub enum Error {
         base_branch: String,
     },
 
+    /// Branch checked out in another worktree can't be rebased there
+    #[error(
+        "Branch '{branch}' is checked out in another worktree at {path}, and rebasing it conflicts.\n\nRun 'bt restack' from that worktree to resolve the conflicts there."
+    )]
+    BranchCheckedOutElsewhere { branch: String, path: PathBuf },
+
     /// Branch not found
     #[error("Branch not found: {branch}")]
     BranchNotFound { branch: String },
@@ -103,8 +132,8 @@ pub enum Error {
     AlreadyInitialized { path: PathBuf },
 
     /// Uncommitted changes
-    #[error("You have uncommitted changes. Commit or stash them before proceeding.")]
-    UncommittedChanges,
+    #[error("You have uncommitted changes. Commit or stash them before proceeding.\n\n{changes}")]
+    UncommittedChanges { changes: String },
 
     /// Rebase in progress
     #[error(
@@ -112,6 +141,18 @@ pub enum Error {
     )]
     RebaseInProgress,
 
+    /// Rebase stopped on conflicts
+    #[error(
+        "Conflicts while rebasing '{branch}'.\n\nResolve the conflicts, stage them, then run 'bt restack --continue' to update the rest of the stack."
+    )]
+    RebaseConflict { branch: String },
+
+    /// Remote branch changed since basalt last pushed it
+    #[error(
+        "Remote branch changed since it was last pushed: {branches}\n\nSomeone else may have pushed to it. Fetch and review their commits, or run 'bt submit --force' to overwrite them."
+    )]
+    RemoteBranchChanged { branches: String },
+
     /// Review not found
     #[error("Review not found for branch: {branch}")]
     ReviewNotFound { branch: String },
@@ -188,6 +229,76 @@ impl Error {
     pub fn other<S: Into<String>>(message: S) -> Self {
         Error::Other(message.into())
     }
+
+    /// Get the stable, snake_case name of the error's kind
+    ///
+    /// This is the `kind` field of errors printed with `--output json`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Git { .. } => "git",
+            Error::NotInGitRepository => "not_in_git_repository",
+            Error::ProviderCliNotFound { .. } => "provider_cli_not_found",
+            Error::ProviderAuthRequired { .. } => "provider_auth_required",
+            Error::ProviderDetectionFailed { .. } => "provider_detection_failed",
+            Error::UnknownProvider { .. } => "unknown_provider",
+            Error::ProviderOperationFailed { .. } => "provider_operation_failed",
+            Error::InvalidStack { .. } => "invalid_stack",
+            Error::MergeCommitInStack { .. } => "merge_commit_in_stack",
+            Error::EmptyStack { .. } => "empty_stack",
+            Error::BranchCheckedOutElsewhere { .. } => "branch_checked_out_elsewhere",
+            Error::BranchNotFound { .. } => "branch_not_found",
+            Error::Metadata { .. } => "metadata",
+            Error::MetadataNotFound => "metadata_not_found",
+            Error::UnsupportedMetadataVersion { .. } => "unsupported_metadata_version",
+            Error::Config { .. } => "config",
+            Error::NotInitialized => "not_initialized",
+            Error::AlreadyInitialized { .. } => "already_initialized",
+            Error::UncommittedChanges { .. } => "uncommitted_changes",
+            Error::RebaseInProgress => "rebase_in_progress",
+            Error::RebaseConflict { .. } => "rebase_conflict",
+            Error::RemoteBranchChanged { .. } => "remote_branch_changed",
+            Error::ReviewNotFound { .. } => "review_not_found",
+            Error::JsonParse { .. } => "json_parse",
+            Error::YamlParse { .. } => "yaml_parse",
+            Error::TomlParse { .. } => "toml_parse",
+            Error::Io(_) => "io",
+            Error::CommandFailed { .. } => "command_failed",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// Get the process exit code for the error
+    ///
+    /// See the table in the module documentation.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::NotInGitRepository => 3,
+            Error::NotInitialized | Error::MetadataNotFound => 4,
+            Error::AlreadyInitialized { .. } => 5,
+            Error::Metadata { .. } | Error::UnsupportedMetadataVersion { .. } => 6,
+            Error::UncommittedChanges { .. } => 7,
+            Error::RebaseInProgress => 8,
+            Error::RebaseConflict { .. } | Error::BranchCheckedOutElsewhere { .. } => 9,
+            Error::InvalidStack { .. }
+            | Error::MergeCommitInStack { .. }
+            | Error::EmptyStack { .. }
+            | Error::BranchNotFound { .. } => 10,
+            Error::ProviderAuthRequired { .. } | Error::ProviderCliNotFound { .. } => 11,
+            Error::ProviderOperationFailed { .. }
+            | Error::ProviderDetectionFailed { .. }
+            | Error::UnknownProvider { .. }
+            | Error::ReviewNotFound { .. } => 12,
+            Error::RemoteBranchChanged { .. } => 13,
+            Error::Git { .. }
+            | Error::Config { .. }
+            | Error::JsonParse { .. }
+            | Error::YamlParse { .. }
+            | Error::TomlParse { .. }
+            | Error::Io(_)
+            | Error::CommandFailed { .. }
+            | Error::Other(_) => 1,
+        }
+    }
 }
 
 /// Convert serde_json errors to our error type
@@ -3,7 +3,8 @@ mod core;
 mod error;
 mod providers;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use cli::output::OutputFormat;
 use std::process;
 
 #[derive(Parser)]
@@ -17,9 +18,34 @@ struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// Enable verbose output
-    #[arg(short, long, global = true)]
-    verbose: bool,
+    /// Trace git and provider calls on stderr (-v for debug, -vv for trace)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Write every trace event to this file as JSON lines
+    #[arg(long, global = true, value_name = "PATH")]
+    trace_file: Option<std::path::PathBuf>,
+
+    /// Print what a command would do instead of doing it (text or json)
+    #[arg(
+        long,
+        global = true,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    dry_run: Option<cli::plan::PlanFormat>,
+
+    /// Output format: human-readable text, or a single JSON document
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t
+    )]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -41,21 +67,72 @@ enum Commands {
     },
 
     /// Submit the current stack as reviews (MRs/PRs)
+    ///
+    /// By default the current branch and the branches below it are
+    /// submitted.
     Submit {
+        /// Only submit the current branch
+        #[arg(long, conflicts_with_all = ["downstack", "upstack", "stack"])]
+        branch: bool,
+
+        /// Submit the current branch and the branches below it (default)
+        #[arg(long, conflicts_with_all = ["upstack", "stack"])]
+        downstack: bool,
+
+        /// Submit the current branch and the branches above it
+        #[arg(long, conflicts_with = "stack")]
+        upstack: bool,
+
+        /// Submit the whole stack: branches below and above the current one
+        #[arg(long)]
+        stack: bool,
+
         /// Submit as ready instead of draft
         #[arg(short, long)]
         ready: bool,
+
+        /// Edit the title and description of new reviews in $EDITOR
+        #[arg(short, long)]
+        edit: bool,
+
+        /// Request a review from this user (repeatable or comma-separated)
+        #[arg(long = "reviewer", value_name = "USER", value_delimiter = ',')]
+        reviewers: Vec<String>,
+
+        /// Assign this user (repeatable or comma-separated)
+        #[arg(long = "assignee", value_name = "USER", value_delimiter = ',')]
+        assignees: Vec<String>,
+
+        /// Add this label (repeatable or comma-separated)
+        #[arg(long = "label", value_name = "LABEL", value_delimiter = ',')]
+        labels: Vec<String>,
+
+        /// Set the milestone
+        #[arg(long, value_name = "TITLE")]
+        milestone: Option<String>,
+
+        /// Overwrite remote branches that changed since they were last pushed
+        #[arg(short, long)]
+        force: bool,
+
+        /// Submit branches that have no commits of their own
+        #[arg(long)]
+        allow_empty: bool,
     },
 
     /// Restack (rebase) all branches in the current stack
     Restack {
         /// Continue after resolving conflicts
-        #[arg(long)]
+        #[arg(long, conflicts_with = "abort")]
         r#continue: bool,
 
         /// Abort the restack operation
         #[arg(long)]
         abort: bool,
+
+        /// Restack branches that have no commits of their own
+        #[arg(long, conflicts_with = "abort")]
+        allow_empty: bool,
     },
 
     /// Show the status of the current stack
@@ -64,12 +141,149 @@ enum Commands {
         #[arg(long)]
         json: bool,
     },
+
+    /// Move a branch and its upstack onto a new parent branch
+    Move {
+        /// Branch to move (defaults to the current branch)
+        branch: Option<String>,
+
+        /// New parent branch
+        #[arg(long)]
+        onto: String,
+    },
+
+    /// Fold the current branch into its parent
+    Fold {
+        /// Keep the current branch's name instead of the parent's
+        #[arg(short, long)]
+        keep: bool,
+    },
+
+    /// Squash all commits of the current branch into one
+    Squash {
+        /// Message for the squashed commit
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// Use the combined commit messages without opening an editor
+        #[arg(long)]
+        no_edit: bool,
+    },
+
+    /// Split the current branch into several stacked branches
+    Split {
+        /// Split at chosen commits (default)
+        #[arg(long, conflicts_with = "by_hunk")]
+        by_commit: bool,
+
+        /// Stage hunks into successive single-commit branches
+        #[arg(long)]
+        by_hunk: bool,
+    },
+
+    /// Delete a branch, reparenting its children onto its parent
+    Delete {
+        /// Branch to delete (defaults to the current branch)
+        branch: Option<String>,
+
+        /// Delete even if the branch isn't merged into the base branch
+        #[arg(short, long)]
+        force: bool,
+
+        /// Close the branch's review
+        #[arg(long)]
+        close: bool,
+    },
+
+    /// Rename the current branch
+    Rename {
+        /// New branch name
+        new_name: String,
+    },
+
+    /// Show all tracked stacks
+    Log {
+        /// One line per branch, without commits
+        #[arg(long, conflicts_with = "long")]
+        short: bool,
+
+        /// Detailed information for every branch
+        #[arg(long)]
+        long: bool,
+
+        /// Only show the current stack
+        #[arg(long)]
+        stack: bool,
+
+        /// Fetch review states, authenticating if needed
+        #[arg(long)]
+        states: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Merge the stack's reviews bottom-up
+    Land {
+        /// Land every branch up to the current one, not just the bottom one
+        #[arg(short, long)]
+        all: bool,
+
+        /// Squash each review's commits when merging
+        #[arg(long, conflicts_with = "rebase")]
+        squash: bool,
+
+        /// Rebase each review onto its target before merging
+        #[arg(long)]
+        rebase: bool,
+    },
+
+    /// Show the review discussions of the current stack
+    Comments {
+        /// Only show unresolved threads
+        #[arg(long)]
+        unresolved: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Revert the most recent operation
+    Undo,
+
+    /// List recorded operations, newest first
+    Oplog,
+
+    /// Check the repository setup and diagnose problems
+    Doctor {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let command = matches.subcommand_name().unwrap_or_default().to_string();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    cli::output::set_format(cli.output);
+    if let Err(e) = core::trace::init(cli.verbose, cli.trace_file.as_deref()) {
+        cli::output::print_error(e.kind(), e.to_string(), e.exit_code());
+        process::exit(e.exit_code());
+    }
+    let json_output = cli.output == OutputFormat::Json;
+    let dry_run = match cli.dry_run {
+        Some(_) if json_output => Some(cli::plan::PlanFormat::Json),
+        dry_run => dry_run,
+    };
 
     let result = match cli.command {
+        Some(Commands::Init { .. }) if dry_run.is_some() => {
+            Err(anyhow::anyhow!("--dry-run is not supported by 'bt init'"))
+        }
         Some(Commands::Init {
             provider,
             base_branch,
@@ -83,18 +297,94 @@ fn main() {
             #[cfg(not(debug_assertions))]
             false,
         ),
-        Some(Commands::Submit { ready }) => run_submit(ready),
-        Some(Commands::Restack { r#continue, abort }) => run_restack(r#continue, abort),
-        Some(Commands::Status { json }) => run_status(json),
+        Some(Commands::Submit {
+            branch,
+            downstack: _,
+            upstack,
+            stack,
+            ready,
+            edit,
+            reviewers,
+            assignees,
+            labels,
+            milestone,
+            force,
+            allow_empty,
+        }) => run_submit(
+            cli::submit::SubmitOptions {
+                scope: if branch {
+                    cli::submit::SubmitScope::Branch
+                } else if upstack {
+                    cli::submit::SubmitScope::Upstack
+                } else if stack {
+                    cli::submit::SubmitScope::Stack
+                } else {
+                    cli::submit::SubmitScope::Downstack
+                },
+                ready,
+                edit,
+                reviewers,
+                assignees,
+                labels,
+                milestone,
+                force,
+                allow_empty,
+            },
+            dry_run,
+        ),
+        Some(Commands::Restack {
+            r#continue,
+            abort,
+            allow_empty,
+        }) => run_restack(r#continue, abort, allow_empty, dry_run),
+        Some(Commands::Status { json }) => run_status(json || json_output),
+        Some(Commands::Move { branch, onto }) => run_move(branch, onto, dry_run),
+        Some(Commands::Fold { keep }) => run_fold(keep, dry_run),
+        Some(Commands::Squash { message, no_edit }) => run_squash(message, no_edit, dry_run),
+        Some(Commands::Split { by_hunk, .. }) => run_split(by_hunk, dry_run),
+        Some(Commands::Delete {
+            branch,
+            force,
+            close,
+        }) => run_delete(branch, force, close, dry_run),
+        Some(Commands::Rename { new_name }) => run_rename(new_name, dry_run),
+        Some(Commands::Log {
+            short,
+            long,
+            stack,
+            states,
+            json,
+        }) => run_log(short, long, stack, states, json || json_output),
+        Some(Commands::Land {
+            all,
+            squash,
+            rebase,
+        }) => run_land(all, squash, rebase, dry_run),
+        Some(Commands::Comments { unresolved, json }) => {
+            run_comments(unresolved, json || json_output)
+        }
+        Some(Commands::Undo) => run_undo(dry_run),
+        Some(Commands::Oplog) => run_oplog(),
+        Some(Commands::Doctor { json }) => run_doctor(json || json_output),
         None => {
             eprintln!("No command provided. Use --help for usage information.");
             process::exit(1);
         }
     };
 
+    let result = result.and_then(|()| {
+        if json_output {
+            cli::output::finish(&command)?;
+        }
+        Ok(())
+    });
     if let Err(e) = result {
-        eprintln!("Error: {}", e);
-        process::exit(1);
+        let (kind, exit_code) = match e.downcast_ref::<error::Error>() {
+            Some(error) => (error.kind(), error.exit_code()),
+            None => ("other", 1),
+        };
+        cli::output::print_error(kind, e.to_string(), exit_code);
+        process::exit(exit_code);
     }
 }
 
@@ -107,34 +397,117 @@ fn run_init(
     Ok(())
 }
 
-fn run_submit(ready: bool) -> anyhow::Result<()> {
-    println!("🚧 Submitting stack...");
-    if ready {
-        println!("   Mode: Ready for review");
-    } else {
-        println!("   Mode: Draft");
-    }
-    println!("\n⚠️  Not yet implemented - this is a placeholder");
+fn run_submit(
+    options: cli::submit::SubmitOptions,
+    dry_run: Option<cli::plan::PlanFormat>,
+) -> anyhow::Result<()> {
+    cli::submit::run_submit(options, dry_run)?;
     Ok(())
 }
 
-fn run_restack(r#continue: bool, abort: bool) -> anyhow::Result<()> {
-    println!("🚧 Restacking branches...");
-    if r#continue {
-        println!("   Continuing after conflict resolution");
-    }
-    if abort {
-        println!("   Aborting restack operation");
-    }
-    println!("\n⚠️  Not yet implemented - this is a placeholder");
+fn run_restack(
+    r#continue: bool,
+    abort: bool,
+    allow_empty: bool,
+    dry_run: Option<cli::plan::PlanFormat>,
+) -> anyhow::Result<()> {
+    cli::restack::run_restack(r#continue, abort, allow_empty, dry_run)?;
     Ok(())
 }
 
 fn run_status(json: bool) -> anyhow::Result<()> {
-    println!("🚧 Checking stack status...");
-    if json {
-        println!("   Output format: JSON");
-    }
-    println!("\n⚠️  Not yet implemented - this is a placeholder");
+    cli::status::run_status(json)?;
+    Ok(())
+}
+
+fn run_move(
+    branch: Option<String>,
+    onto: String,
+    dry_run: Option<cli::plan::PlanFormat>,
+) -> anyhow::Result<()> {
+    cli::move_branch::run_move(branch, onto, dry_run)?;
+    Ok(())
+}
+
+fn run_fold(keep: bool, dry_run: Option<cli::plan::PlanFormat>) -> anyhow::Result<()> {
+    cli::fold::run_fold(keep, dry_run)?;
+    Ok(())
+}
+
+fn run_squash(
+    message: Option<String>,
+    no_edit: bool,
+    dry_run: Option<cli::plan::PlanFormat>,
+) -> anyhow::Result<()> {
+    cli::squash::run_squash(message, no_edit, dry_run)?;
+    Ok(())
+}
+
+fn run_split(by_hunk: bool, dry_run: Option<cli::plan::PlanFormat>) -> anyhow::Result<()> {
+    cli::split::run_split(by_hunk, dry_run)?;
+    Ok(())
+}
+
+fn run_delete(
+    branch: Option<String>,
+    force: bool,
+    close: bool,
+    dry_run: Option<cli::plan::PlanFormat>,
+) -> anyhow::Result<()> {
+    cli::delete::run_delete(branch, force, close, dry_run)?;
+    Ok(())
+}
+
+fn run_rename(new_name: String, dry_run: Option<cli::plan::PlanFormat>) -> anyhow::Result<()> {
+    cli::rename::run_rename(new_name, dry_run)?;
+    Ok(())
+}
+
+fn run_log(short: bool, long: bool, stack: bool, states: bool, json: bool) -> anyhow::Result<()> {
+    let format = if short {
+        cli::log::LogFormat::Short
+    } else if long {
+        cli::log::LogFormat::Long
+    } else {
+        cli::log::LogFormat::Graph
+    };
+    cli::log::run_log(format, stack, states, json)?;
+    Ok(())
+}
+
+fn run_land(
+    all: bool,
+    squash: bool,
+    rebase: bool,
+    dry_run: Option<cli::plan::PlanFormat>,
+) -> anyhow::Result<()> {
+    let method = if squash {
+        providers::MergeMethod::Squash
+    } else if rebase {
+        providers::MergeMethod::Rebase
+    } else {
+        providers::MergeMethod::Merge
+    };
+    cli::land::run_land(all, method, dry_run)?;
+    Ok(())
+}
+
+fn run_comments(unresolved: bool, json: bool) -> anyhow::Result<()> {
+    cli::comments::run_comments(unresolved, json)?;
+    Ok(())
+}
+
+fn run_undo(dry_run: Option<cli::plan::PlanFormat>) -> anyhow::Result<()> {
+    cli::undo::run_undo(dry_run)?;
+    Ok(())
+}
+
+fn run_oplog() -> anyhow::Result<()> {
+    cli::oplog::run_oplog()?;
+    Ok(())
+}
+
+fn run_doctor(json: bool) -> anyhow::Result<()> {
+    cli::doctor::run_doctor(json)?;
     Ok(())
 }
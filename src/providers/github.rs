@@ -9,7 +9,10 @@
 #![allow(dead_code)] // Allow during early development
 
 use crate::error::{Error, Result};
-use crate::providers::{CreateReviewParams, Provider, ProviderType, Review, UpdateReviewParams};
+use crate::providers::{
+    CreateReviewParams, Discussion, MergeReviewParams, Provider, ProviderType, Review,
+    ReviewChecks, UpdateReviewParams,
+};
 
 /// GitHub provider using REST API (stub)
 pub struct GitHubProvider {}
@@ -47,6 +50,8 @@ impl Provider for GitHubProvider {
 
     fn create_review(&mut self, _params: CreateReviewParams) -> Result<Review> {
         // TODO: Implement PR creation via GitHub REST API
+        // Reviewers and assignees are GitHub logins and can be passed as-is to
+        // POST /pulls/:number/requested_reviewers and POST /issues/:number/assignees
         Err(Error::provider_op("GitHub PR creation not yet implemented"))
     }
 
@@ -62,6 +67,35 @@ impl Provider for GitHubProvider {
         ))
     }
 
+    fn close_review(&mut self, _review_id: &str) -> Result<Review> {
+        // TODO: Implement closing a PR via GitHub REST API
+        Err(Error::provider_op("GitHub PR closing not yet implemented"))
+    }
+
+    fn merge_review(&mut self, _params: MergeReviewParams) -> Result<Review> {
+        // TODO: Implement PR merging via GitHub REST API
+        Err(Error::provider_op("GitHub PR merging not yet implemented"))
+    }
+
+    fn get_review_checks(&mut self, _review_id: &str) -> Result<ReviewChecks> {
+        // TODO: Implement via GitHub REST API:
+        // - approvals from GET /repos/:owner/:repo/pulls/:number/reviews
+        // - pipeline from GET /repos/:owner/:repo/commits/:sha/check-runs
+        // - mergeability from the PR's `mergeable` and `mergeable_state`
+        Err(Error::provider_op(
+            "GitHub PR checks retrieval not yet implemented",
+        ))
+    }
+
+    fn list_discussions(&mut self, _review_id: &str) -> Result<Vec<Discussion>> {
+        // TODO: Implement via GitHub REST API: group
+        // GET /repos/:owner/:repo/pulls/:number/comments by `in_reply_to_id`,
+        // and use the GraphQL `reviewThreads` field for resolution state
+        Err(Error::provider_op(
+            "GitHub PR comments retrieval not yet implemented",
+        ))
+    }
+
     fn find_review_for_branch(&mut self, _branch: &str) -> Result<Option<Review>> {
         // TODO: Implement finding PR by branch via GitHub REST API
         Err(Error::provider_op(
@@ -12,11 +12,22 @@
 //!    - Offer CLI auth (if glab is available) or manual PAT entry
 //! 3. Store successful token in metadata for future use
 
+use crate::core::config::HttpConfig;
 use crate::error::{Error, Result};
 use crate::providers::gitlab_api::GitLabClient;
+use crate::providers::http;
 use crate::providers::{
-    CreateReviewParams, Provider, ProviderType, Review, ReviewState, UpdateReviewParams,
+    Comment, CreateReviewParams, Discussion, MergeMethod, MergeReviewParams, MergeStatus,
+    PipelineStatus, Provider, ProviderType, Review, ReviewChecks, ReviewState, UpdateReviewParams,
 };
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long to wait for a server-side rebase before giving up
+const REBASE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Delay between polls while waiting for a server-side rebase
+const REBASE_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 /// GitLab provider using REST API
 pub struct GitLabProvider {
@@ -26,6 +37,10 @@ pub struct GitLabProvider {
     project_path: Option<String>,
     /// Whether we've successfully authenticated
     authenticated: bool,
+    /// Most requests in flight at once for batch operations
+    concurrency: usize,
+    /// User IDs already resolved from usernames
+    user_ids: HashMap<String, u64>,
 }
 
 impl GitLabProvider {
@@ -41,13 +56,25 @@ impl GitLabProvider {
     /// let provider = GitLabProvider::new("https://gitlab.com")?;
     /// ```
     pub fn new(base_url: &str) -> Result<Self> {
-        let client = GitLabClient::new(base_url)
+        Self::with_http_config(base_url, &HttpConfig::default())
+    }
+
+    /// Create a GitLab provider with custom timeout and retry settings
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - Base URL of the GitLab instance (e.g., "https://gitlab.com")
+    /// * `http` - The `[http]` section of the config
+    pub fn with_http_config(base_url: &str, http: &HttpConfig) -> Result<Self> {
+        let client = GitLabClient::with_http_config(base_url, http)
             .map_err(|e| Error::provider_op(format!("Failed to create GitLab client: {}", e)))?;
 
         Ok(Self {
             client,
             project_path: None,
             authenticated: false,
+            concurrency: http.concurrency,
+            user_ids: HashMap::new(),
         })
     }
 
@@ -76,6 +103,197 @@ impl GitLabProvider {
         }
     }
 
+    /// Resolve usernames to GitLab user IDs
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a username doesn't exist or the lookup fails
+    fn resolve_user_ids(&mut self, usernames: &[String]) -> Result<Vec<u64>> {
+        let mut ids = Vec::with_capacity(usernames.len());
+
+        for username in usernames {
+            let username = username.trim_start_matches('@');
+            if let Some(id) = self.user_ids.get(username) {
+                ids.push(*id);
+                continue;
+            }
+
+            let user = self
+                .client
+                .find_user(username)
+                .map_err(|e| Error::provider_op(format!("Failed to look up user: {}", e)))?
+                .ok_or_else(|| {
+                    Error::provider_op(format!("GitLab user '{}' not found", username))
+                })?;
+            self.user_ids.insert(username.to_string(), user.id);
+            ids.push(user.id);
+        }
+
+        Ok(ids)
+    }
+
+    /// Resolve a milestone title to a GitLab milestone ID
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the milestone doesn't exist or the lookup fails
+    fn resolve_milestone_id(&self, title: &str) -> Result<u64> {
+        let project_path = self.get_project_path()?;
+
+        self.client
+            .find_milestone(project_path, title)
+            .map_err(|e| Error::provider_op(format!("Failed to look up milestone: {}", e)))?
+            .map(|milestone| milestone.id)
+            .ok_or_else(|| Error::provider_op(format!("GitLab milestone '{}' not found", title)))
+    }
+
+    /// Build the API parameters of a new merge request
+    ///
+    /// Resolves reviewers, assignees and the milestone, caching user IDs.
+    fn merge_request_params(
+        &mut self,
+        params: CreateReviewParams,
+    ) -> Result<crate::providers::gitlab_api::CreateMergeRequestParams> {
+        let reviewer_ids = self.resolve_user_ids(&params.reviewers)?;
+        let assignee_ids = self.resolve_user_ids(&params.assignees)?;
+        let milestone_id = params
+            .milestone
+            .as_deref()
+            .map(|title| self.resolve_milestone_id(title))
+            .transpose()?;
+
+        Ok(crate::providers::gitlab_api::CreateMergeRequestParams {
+            source_branch: params.source_branch,
+            target_branch: params.target_branch,
+            title: params.title,
+            description: params.description,
+            draft: Some(params.draft),
+            reviewer_ids: Some(reviewer_ids).filter(|ids| !ids.is_empty()),
+            assignee_ids: Some(assignee_ids).filter(|ids| !ids.is_empty()),
+            labels: Some(params.labels.join(",")).filter(|labels| !labels.is_empty()),
+            milestone_id,
+        })
+    }
+
+    /// Create a merge request from resolved parameters
+    fn create_merge_request(
+        &self,
+        params: crate::providers::gitlab_api::CreateMergeRequestParams,
+    ) -> Result<Review> {
+        let project_path = self.get_project_path()?;
+
+        let mr = self
+            .client
+            .create_merge_request(project_path, params)
+            .map_err(|e| Error::provider_op(format!("Failed to create merge request: {}", e)))?;
+
+        Ok(Self::mr_to_review(mr))
+    }
+
+    /// Fetch a merge request by IID
+    fn fetch_review(&self, review_id: &str) -> Result<Review> {
+        let project_path = self.get_project_path()?;
+
+        // Parse the review ID as u64 (GitLab MR IID)
+        let mr_iid: u64 = review_id
+            .parse()
+            .map_err(|_| Error::provider_op(format!("Invalid MR ID: {}", review_id)))?;
+
+        let mr = self
+            .client
+            .get_merge_request(project_path, mr_iid)
+            .map_err(|e| Error::provider_op(format!("Failed to get merge request: {}", e)))?;
+
+        Ok(Self::mr_to_review(mr))
+    }
+
+    /// Convert a GitLab pipeline status to PipelineStatus
+    fn parse_pipeline_status(status: &str) -> PipelineStatus {
+        match status {
+            "running" => PipelineStatus::Running,
+            "success" => PipelineStatus::Success,
+            "failed" => PipelineStatus::Failed,
+            "canceled" => PipelineStatus::Canceled,
+            "skipped" => PipelineStatus::Skipped,
+            "manual" => PipelineStatus::Manual,
+            // created, waiting_for_resource, preparing, pending, scheduled
+            _ => PipelineStatus::Pending,
+        }
+    }
+
+    /// Convert GitLab's mergeability fields to MergeStatus
+    ///
+    /// Prefers `detailed_merge_status` and falls back to the legacy
+    /// `merge_status` on older GitLab versions. Statuses that only last
+    /// until the head pipeline finishes are reported as `Checking`, so
+    /// callers can wait for them instead of giving up.
+    fn parse_merge_status(
+        detailed: Option<&str>,
+        legacy: Option<&str>,
+        pipeline: Option<PipelineStatus>,
+    ) -> MergeStatus {
+        let pipeline_running = matches!(
+            pipeline,
+            Some(PipelineStatus::Pending | PipelineStatus::Running)
+        );
+        match detailed {
+            Some("mergeable") => MergeStatus::Mergeable,
+            Some("conflict" | "broken_status") => MergeStatus::Conflicts,
+            Some("need_rebase") => MergeStatus::NeedsRebase,
+            Some(
+                "checking" | "unchecked" | "preparing" | "approvals_syncing" | "ci_still_running",
+            ) => MergeStatus::Checking,
+            Some("ci_must_pass") if pipeline_running => MergeStatus::Checking,
+            Some(_) => MergeStatus::Blocked,
+            None => match legacy {
+                Some("can_be_merged") => MergeStatus::Mergeable,
+                Some("cannot_be_merged") => MergeStatus::Conflicts,
+                Some("unchecked" | "checking" | "cannot_be_merged_recheck") => {
+                    MergeStatus::Checking
+                }
+                _ => MergeStatus::Unknown,
+            },
+        }
+    }
+
+    /// Convert a GitLab discussion to Discussion
+    ///
+    /// Returns `None` for discussions made only of system notes.
+    fn to_discussion(discussion: crate::providers::gitlab_api::Discussion) -> Option<Discussion> {
+        let notes: Vec<_> = discussion
+            .notes
+            .into_iter()
+            .filter(|note| !note.system)
+            .collect();
+        let first = notes.first()?;
+
+        let position = first.position.as_ref();
+        let file = position.and_then(|p| p.new_path.clone().or_else(|| p.old_path.clone()));
+        let line = position.and_then(|p| p.new_line.or(p.old_line));
+        let resolvable = notes.iter().any(|note| note.resolvable);
+        let resolved = resolvable
+            && notes
+                .iter()
+                .filter(|note| note.resolvable)
+                .all(|note| note.resolved);
+
+        Some(Discussion {
+            id: discussion.id,
+            resolvable,
+            resolved,
+            file,
+            line,
+            comments: notes
+                .into_iter()
+                .map(|note| Comment {
+                    author: note.author.username,
+                    body: note.body,
+                    created_at: note.created_at,
+                })
+                .collect(),
+        })
+    }
+
     /// Convert GitLab MR to Review
     fn mr_to_review(mr: crate::providers::gitlab_api::MergeRequest) -> Review {
         Review {
@@ -87,6 +305,10 @@ impl GitLabProvider {
             source_branch: mr.source_branch,
             target_branch: mr.target_branch,
             draft: mr.draft,
+            reviewers: mr.reviewers.into_iter().map(|user| user.username).collect(),
+            assignees: mr.assignees.into_iter().map(|user| user.username).collect(),
+            labels: mr.labels,
+            milestone: mr.milestone.map(|milestone| milestone.title),
         }
     }
 }
@@ -113,6 +335,22 @@ impl GitLabProvider {
     pub fn set_auth_token(&mut self, token: String) {
         self.client.set_token(token);
     }
+
+    /// Check that the token set with `set_auth_token` is active and has
+    /// the scopes basalt needs
+    ///
+    /// Unlike `authenticate`, this never looks for or prompts for another
+    /// token.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ProviderOperationFailed` if the token is invalid,
+    /// lacks a scope or can't be checked
+    pub fn verify_token(&self) -> Result<()> {
+        self.client
+            .verify_token_scopes()
+            .map_err(|e| Error::provider_op(e.to_string()))
+    }
 }
 
 impl Provider for GitLabProvider {
@@ -149,37 +387,42 @@ impl Provider for GitLabProvider {
     }
 
     fn create_review(&mut self, params: CreateReviewParams) -> Result<Review> {
-        let project_path = self.get_project_path()?;
-
-        let mr = self
-            .client
-            .create_merge_request(
-                project_path,
-                &params.source_branch,
-                &params.target_branch,
-                &params.title,
-                params.description.as_deref(),
-                params.draft,
-            )
-            .map_err(|e| Error::provider_op(format!("Failed to create merge request: {}", e)))?;
-
-        Ok(Self::mr_to_review(mr))
+        let params = self.merge_request_params(params)?;
+        self.create_merge_request(params)
     }
 
     fn update_review(&mut self, params: UpdateReviewParams) -> Result<Review> {
-        let project_path = self.get_project_path()?;
-
         // Parse the review ID as u64 (GitLab MR IID)
         let mr_iid: u64 = params
             .review_id
             .parse()
             .map_err(|_| Error::provider_op(format!("Invalid MR ID: {}", params.review_id)))?;
 
+        let reviewer_ids = params
+            .reviewers
+            .map(|reviewers| self.resolve_user_ids(&reviewers))
+            .transpose()?;
+        let assignee_ids = params
+            .assignees
+            .map(|assignees| self.resolve_user_ids(&assignees))
+            .transpose()?;
+        let milestone_id = params
+            .milestone
+            .as_deref()
+            .map(|title| self.resolve_milestone_id(title))
+            .transpose()?;
+        let project_path = self.get_project_path()?;
+
         let update_params = crate::providers::gitlab_api::UpdateMergeRequestParams {
             title: params.title,
             description: params.description,
             target_branch: params.target_branch,
             draft: None, // We don't update draft status during regular updates
+            state_event: None,
+            reviewer_ids,
+            assignee_ids,
+            labels: params.labels.map(|labels| labels.join(",")),
+            milestone_id,
         };
 
         let mr = self
@@ -191,9 +434,111 @@ impl Provider for GitLabProvider {
     }
 
     fn get_review(&mut self, review_id: &str) -> Result<Review> {
+        self.fetch_review(review_id)
+    }
+
+    fn close_review(&mut self, review_id: &str) -> Result<Review> {
+        let project_path = self.get_project_path()?;
+
+        let mr_iid: u64 = review_id
+            .parse()
+            .map_err(|_| Error::provider_op(format!("Invalid MR ID: {}", review_id)))?;
+
+        let update_params = crate::providers::gitlab_api::UpdateMergeRequestParams {
+            state_event: Some("close".to_string()),
+            ..Default::default()
+        };
+
+        let mr = self
+            .client
+            .update_merge_request(project_path, mr_iid, update_params)
+            .map_err(|e| Error::provider_op(format!("Failed to close merge request: {}", e)))?;
+
+        Ok(Self::mr_to_review(mr))
+    }
+
+    fn merge_review(&mut self, params: MergeReviewParams) -> Result<Review> {
+        let project_path = self.get_project_path()?;
+
+        let mr_iid: u64 = params
+            .review_id
+            .parse()
+            .map_err(|_| Error::provider_op(format!("Invalid MR ID: {}", params.review_id)))?;
+
+        let mut sha = params.sha;
+        if params.method == MergeMethod::Rebase {
+            // The rebase moves the head, so check it beforehand and pin
+            // the merge to the rebased commit
+            if let Some(expected) = &sha {
+                let mr = self
+                    .client
+                    .get_merge_request(project_path, mr_iid)
+                    .map_err(|e| {
+                        Error::provider_op(format!("Failed to get merge request: {}", e))
+                    })?;
+                if mr.sha.as_ref() != Some(expected) {
+                    return Err(Error::provider_op(format!(
+                        "Merge request !{} has changed: its head is {}, expected {}",
+                        mr_iid,
+                        mr.sha.as_deref().unwrap_or("unknown"),
+                        expected
+                    )));
+                }
+            }
+
+            self.client
+                .rebase_merge_request(project_path, mr_iid)
+                .map_err(|e| {
+                    Error::provider_op(format!("Failed to rebase merge request: {}", e))
+                })?;
+
+            let started = Instant::now();
+            loop {
+                let mr = self
+                    .client
+                    .get_merge_request(project_path, mr_iid)
+                    .map_err(|e| {
+                        Error::provider_op(format!("Failed to get merge request: {}", e))
+                    })?;
+
+                if let Some(error) = mr.merge_error.filter(|e| !e.is_empty()) {
+                    return Err(Error::provider_op(format!(
+                        "Failed to rebase merge request !{}: {}",
+                        mr_iid, error
+                    )));
+                }
+                if !mr.rebase_in_progress.unwrap_or(false) {
+                    if sha.is_some() {
+                        sha = mr.sha;
+                    }
+                    break;
+                }
+                if started.elapsed() > REBASE_TIMEOUT {
+                    return Err(Error::provider_op(format!(
+                        "Timed out waiting for merge request !{} to be rebased",
+                        mr_iid
+                    )));
+                }
+                std::thread::sleep(REBASE_POLL_INTERVAL);
+            }
+        }
+
+        let merge_params = crate::providers::gitlab_api::AcceptMergeRequestParams {
+            squash: Some(params.method == MergeMethod::Squash),
+            sha,
+        };
+
+        let mr = self
+            .client
+            .accept_merge_request(project_path, mr_iid, merge_params)
+            .map_err(|e| Error::provider_op(format!("Failed to merge merge request: {}", e)))?;
+
+        Ok(Self::mr_to_review(mr))
+    }
+
+    fn get_review_checks(&mut self, review_id: &str) -> Result<ReviewChecks> {
         let project_path = self.get_project_path()?;
 
-        // Parse the review ID as u64 (GitLab MR IID)
         let mr_iid: u64 = review_id
             .parse()
             .map_err(|_| Error::provider_op(format!("Invalid MR ID: {}", review_id)))?;
@@ -202,8 +547,45 @@ impl Provider for GitLabProvider {
             .client
             .get_merge_request(project_path, mr_iid)
             .map_err(|e| Error::provider_op(format!("Failed to get merge request: {}", e)))?;
+        let approvals = self
+            .client
+            .get_merge_request_approvals(project_path, mr_iid)
+            .map_err(|e| Error::provider_op(format!("Failed to get approvals: {}", e)))?;
+
+        let pipeline = mr
+            .head_pipeline
+            .map(|pipeline| Self::parse_pipeline_status(&pipeline.status));
+        Ok(ReviewChecks {
+            approvals: approvals.approved_by.len() as u32,
+            approvals_required: approvals.approvals_required,
+            // Only approvals from eligible approvers count towards the rules
+            approvals_left: approvals.approvals_left,
+            pipeline,
+            has_conflicts: mr.has_conflicts,
+            merge_status: Self::parse_merge_status(
+                mr.detailed_merge_status.as_deref(),
+                mr.merge_status.as_deref(),
+                pipeline,
+            ),
+        })
+    }
 
-        Ok(Self::mr_to_review(mr))
+    fn list_discussions(&mut self, review_id: &str) -> Result<Vec<Discussion>> {
+        let project_path = self.get_project_path()?;
+
+        let mr_iid: u64 = review_id
+            .parse()
+            .map_err(|_| Error::provider_op(format!("Invalid MR ID: {}", review_id)))?;
+
+        let discussions = self
+            .client
+            .list_merge_request_discussions(project_path, mr_iid)
+            .map_err(|e| Error::provider_op(format!("Failed to list discussions: {}", e)))?;
+
+        Ok(discussions
+            .into_iter()
+            .filter_map(Self::to_discussion)
+            .collect())
     }
 
     fn find_review_for_branch(&mut self, _branch: &str) -> Result<Option<Review>> {
@@ -214,6 +596,23 @@ impl Provider for GitLabProvider {
             "GitLab MR lookup by branch not yet implemented",
         ))
     }
+
+    fn get_reviews(&mut self, review_ids: &[String]) -> Vec<Result<Review>> {
+        http::concurrently(review_ids.iter().collect(), self.concurrency, |id| {
+            self.fetch_review(id)
+        })
+    }
+
+    fn create_reviews(&mut self, params: Vec<CreateReviewParams>) -> Vec<Result<Review>> {
+        // Users are resolved first, so that each is looked up only once
+        let prepared: Vec<_> = params
+            .into_iter()
+            .map(|params| self.merge_request_params(params))
+            .collect();
+        http::concurrently(prepared, self.concurrency, |params| {
+            self.create_merge_request(params?)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -253,6 +652,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_pipeline_status() {
+        assert_eq!(
+            GitLabProvider::parse_pipeline_status("success"),
+            PipelineStatus::Success
+        );
+        assert_eq!(
+            GitLabProvider::parse_pipeline_status("failed"),
+            PipelineStatus::Failed
+        );
+        assert_eq!(
+            GitLabProvider::parse_pipeline_status("waiting_for_resource"),
+            PipelineStatus::Pending
+        );
+    }
+
+    #[test]
+    fn test_parse_merge_status() {
+        let parse = GitLabProvider::parse_merge_status;
+        let running = Some(PipelineStatus::Running);
+        let failed = Some(PipelineStatus::Failed);
+
+        assert_eq!(
+            parse(Some("mergeable"), Some("can_be_merged"), None),
+            MergeStatus::Mergeable
+        );
+        assert_eq!(
+            parse(Some("not_approved"), Some("can_be_merged"), None),
+            MergeStatus::Blocked
+        );
+        assert_eq!(
+            parse(Some("discussions_not_resolved"), None, None),
+            MergeStatus::Blocked
+        );
+        assert_eq!(
+            parse(Some("need_rebase"), None, None),
+            MergeStatus::NeedsRebase
+        );
+
+        // Waiting for the pipeline is temporary, a failed one isn't
+        assert_eq!(
+            parse(Some("ci_still_running"), None, running),
+            MergeStatus::Checking
+        );
+        assert_eq!(
+            parse(Some("ci_must_pass"), None, running),
+            MergeStatus::Checking
+        );
+        assert_eq!(
+            parse(Some("ci_must_pass"), None, failed),
+            MergeStatus::Blocked
+        );
+        assert_eq!(parse(Some("checking"), None, None), MergeStatus::Checking);
+
+        assert_eq!(
+            parse(None, Some("cannot_be_merged"), None),
+            MergeStatus::Conflicts
+        );
+        assert_eq!(parse(None, None, None), MergeStatus::Unknown);
+    }
+
     #[test]
     fn test_project_path_not_set() {
         let provider = GitLabProvider::new("https://gitlab.com").unwrap();
@@ -16,6 +16,12 @@
 //! - `POST /projects/:id/merge_requests` - Create MR
 //! - `PUT /projects/:id/merge_requests/:mr_iid` - Update MR
 //! - `GET /projects/:id/merge_requests/:mr_iid` - Get MR details
+//! - `PUT /projects/:id/merge_requests/:mr_iid/merge` - Merge an MR
+//! - `PUT /projects/:id/merge_requests/:mr_iid/rebase` - Rebase an MR onto its target
+//! - `GET /projects/:id/merge_requests/:mr_iid/approvals` - Get MR approval status
+//! - `GET /projects/:id/merge_requests/:mr_iid/discussions` - List MR discussion threads
+//! - `GET /users?username=:username` - Resolve a username to a user ID
+//! - `GET /projects/:id/milestones?title=:title` - Resolve a milestone title to an ID
 //!
 //! # Example
 //!
@@ -25,14 +31,20 @@
 //!
 //! let mr = client.create_merge_request(
 //!     "owner/repo",
-//!     "feature-branch",
-//!     "main",
-//!     "My Feature",
-//!     "Description",
-//!     true, // draft
+//!     CreateMergeRequestParams {
+//!         source_branch: "feature-branch".to_string(),
+//!         target_branch: "main".to_string(),
+//!         title: "My Feature".to_string(),
+//!         description: Some("Description".to_string()),
+//!         draft: Some(true),
+//!         ..Default::default()
+//!     },
 //! )?;
 //! ```
 
+use crate::core::config::HttpConfig;
+use crate::core::trace;
+use crate::providers::http::{self, HttpClient, Retry};
 use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
 use std::process::Command;
@@ -64,8 +76,13 @@ pub enum GitLabError {
     #[error("Merge request not found: !{0}")]
     MergeRequestNotFound(u64),
 
-    #[error("API error ({status}): {message}")]
-    ApiError { status: u16, message: String },
+    #[error("API error ({status}): {message}{}", request_id_suffix(.request_id))]
+    ApiError {
+        status: u16,
+        message: String,
+        /// Id the server assigned to the request, for support tickets
+        request_id: Option<String>,
+    },
 
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
@@ -76,18 +93,38 @@ pub enum GitLabError {
 
 pub type Result<T> = std::result::Result<T, GitLabError>;
 
+/// Format the request id of an API error, if the server sent one
+fn request_id_suffix(request_id: &Option<String>) -> String {
+    request_id
+        .as_ref()
+        .map(|id| format!(" (request id: {})", id))
+        .unwrap_or_default()
+}
+
+/// Build an API error from an unsuccessful response
+fn api_error(response: reqwest::blocking::Response) -> GitLabError {
+    let status = response.status().as_u16();
+    let request_id = http::request_id(&response);
+    let message = response.text().unwrap_or_default();
+    GitLabError::ApiError {
+        status,
+        message,
+        request_id,
+    }
+}
+
 /// GitLab API client
 pub struct GitLabClient {
     /// Base API URL (e.g., "https://gitlab.com/api/v4")
     api_url: String,
-    /// HTTP client
-    client: reqwest::blocking::Client,
+    /// HTTP client with retries and rate limit handling
+    client: HttpClient,
     /// Authentication token (set after successful authentication)
     token: Option<String>,
 }
 
-/// GitLab user information (for auth verification)
-#[derive(Debug, Deserialize)]
+/// GitLab user information
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GitLabUser {
     pub id: u64,
     pub username: String,
@@ -113,10 +150,109 @@ pub struct MergeRequest {
     pub source_branch: String,
     pub target_branch: String,
     pub draft: bool,
+    /// Head commit of the source branch
+    #[serde(default)]
+    pub sha: Option<String>,
+    /// Whether a rebase requested through the API is still running
+    #[serde(default)]
+    pub rebase_in_progress: Option<bool>,
+    /// Error message from the last failed merge or rebase attempt
+    #[serde(default)]
+    pub merge_error: Option<String>,
+    /// Whether the source branch conflicts with the target branch
+    #[serde(default)]
+    pub has_conflicts: bool,
+    /// Mergeability, e.g. "mergeable", "not_approved", "ci_must_pass"
+    #[serde(default)]
+    pub detailed_merge_status: Option<String>,
+    /// Legacy mergeability, e.g. "can_be_merged" (older GitLab versions)
+    #[serde(default)]
+    pub merge_status: Option<String>,
+    /// Latest pipeline for the source branch
+    #[serde(default)]
+    pub head_pipeline: Option<Pipeline>,
+    #[serde(default)]
+    pub reviewers: Vec<GitLabUser>,
+    #[serde(default)]
+    pub assignees: Vec<GitLabUser>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
+}
+
+/// GitLab milestone
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Milestone {
+    pub id: u64,
+    pub title: String,
+}
+
+/// GitLab pipeline summary
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Pipeline {
+    pub id: u64,
+    /// Pipeline status, e.g. "running", "success", "failed"
+    pub status: String,
+}
+
+/// GitLab merge request approval status
+#[derive(Debug, Deserialize)]
+pub struct MergeRequestApprovals {
+    #[serde(default)]
+    pub approvals_required: u32,
+    #[serde(default)]
+    pub approvals_left: u32,
+    #[serde(default)]
+    pub approved_by: Vec<Approver>,
+}
+
+/// GitLab merge request discussion thread
+#[derive(Debug, Deserialize)]
+pub struct Discussion {
+    pub id: String,
+    /// Whether this is a standalone comment rather than a thread
+    #[serde(default)]
+    pub individual_note: bool,
+    pub notes: Vec<Note>,
+}
+
+/// A note (comment) in a GitLab discussion
+#[derive(Debug, Deserialize)]
+pub struct Note {
+    pub id: u64,
+    pub body: String,
+    pub author: GitLabUser,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Whether the note was generated by GitLab (e.g. "added 1 commit")
+    #[serde(default)]
+    pub system: bool,
+    #[serde(default)]
+    pub resolvable: bool,
+    #[serde(default)]
+    pub resolved: bool,
+    /// Diff position, for comments on a file
+    #[serde(default)]
+    pub position: Option<NotePosition>,
+}
+
+/// Position of a diff note
+#[derive(Debug, Deserialize)]
+pub struct NotePosition {
+    pub new_path: Option<String>,
+    pub old_path: Option<String>,
+    pub new_line: Option<u32>,
+    pub old_line: Option<u32>,
+}
+
+/// A user who approved a merge request
+#[derive(Debug, Deserialize)]
+pub struct Approver {
+    pub user: GitLabUser,
 }
 
 /// Parameters for creating a merge request
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 pub struct CreateMergeRequestParams {
     pub source_branch: String,
     pub target_branch: String,
@@ -125,10 +261,19 @@ pub struct CreateMergeRequestParams {
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub draft: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviewer_ids: Option<Vec<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignee_ids: Option<Vec<u64>>,
+    /// Comma-separated label names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestone_id: Option<u64>,
 }
 
 /// Parameters for updating a merge request
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 pub struct UpdateMergeRequestParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
@@ -138,6 +283,28 @@ pub struct UpdateMergeRequestParams {
     pub target_branch: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub draft: Option<bool>,
+    /// State transition ("close" or "reopen")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_event: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviewer_ids: Option<Vec<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignee_ids: Option<Vec<u64>>,
+    /// Comma-separated label names (replaces existing labels)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestone_id: Option<u64>,
+}
+
+/// Parameters for merging (accepting) a merge request
+#[derive(Debug, Serialize)]
+pub struct AcceptMergeRequestParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub squash: Option<bool>,
+    /// Only merge if the source branch is still at this commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha: Option<String>,
 }
 
 impl GitLabClient {
@@ -153,16 +320,21 @@ impl GitLabClient {
     /// let client = GitLabClient::new("https://gitlab.com")?;
     /// ```
     pub fn new(base_url: &str) -> Result<Self> {
-        let api_url = format!("{}/api/v4", base_url.trim_end_matches('/'));
+        Self::with_http_config(base_url, &HttpConfig::default())
+    }
 
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("basalt-cli")
-            .timeout(std::time::Duration::from_secs(30))
-            .build()?;
+    /// Create a GitLab API client with custom timeout and retry settings
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - Base URL of the GitLab instance (e.g., "https://gitlab.com")
+    /// * `http` - The `[http]` section of the config
+    pub fn with_http_config(base_url: &str, http: &HttpConfig) -> Result<Self> {
+        let api_url = format!("{}/api/v4", base_url.trim_end_matches('/'));
 
         Ok(Self {
             api_url,
-            client,
+            client: HttpClient::new(http).map_err(|e| GitLabError::Other(e.to_string()))?,
             token: None,
         })
     }
@@ -223,6 +395,28 @@ impl GitLabClient {
         Ok(user)
     }
 
+    /// Send a request, retrying it on transient failures and rate limits
+    ///
+    /// See [`HttpClient::send`].
+    fn send(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response> {
+        Ok(self.client.send(request)?)
+    }
+
+    /// Send a request that must not be repeated once the server may have
+    /// acted on it
+    ///
+    /// Merging and rebasing use PUT but aren't idempotent: retrying a merge
+    /// that went through fails because the MR is no longer open.
+    fn send_once(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response> {
+        Ok(self.client.send_with(request, Retry::Unprocessed)?)
+    }
+
     /// Verify authentication by calling GET /user
     fn verify_auth(&self) -> Result<GitLabUser> {
         let token = self
@@ -230,17 +424,21 @@ impl GitLabClient {
             .as_ref()
             .ok_or_else(|| GitLabError::NoTokenAvailable)?;
 
-        let response = self
-            .client
-            .get(format!("{}/user", self.api_url))
-            .header("PRIVATE-TOKEN", token)
-            .send()?;
+        let response = self.send(
+            self.client
+                .get(format!("{}/user", self.api_url))
+                .header("PRIVATE-TOKEN", token),
+        )?;
 
         if response.status() == 401 {
             return Err(GitLabError::AuthenticationFailed);
         }
 
-        let user = response.error_for_status()?.json::<GitLabUser>()?;
+        if !response.status().is_success() {
+            return Err(api_error(response));
+        }
+
+        let user = response.json::<GitLabUser>()?;
         Ok(user)
     }
 
@@ -248,24 +446,28 @@ impl GitLabClient {
     ///
     /// Checks that the token has the 'api' scope which is required for
     /// creating and managing merge requests.
-    fn verify_token_scopes(&self) -> Result<()> {
+    pub fn verify_token_scopes(&self) -> Result<()> {
         let token = self
             .token
             .as_ref()
             .ok_or_else(|| GitLabError::NoTokenAvailable)?;
 
         // Check token info via the personal access tokens API
-        let response = self
-            .client
-            .get(format!("{}/personal_access_tokens/self", self.api_url))
-            .header("PRIVATE-TOKEN", token)
-            .send()?;
+        let response = self.send(
+            self.client
+                .get(format!("{}/personal_access_tokens/self", self.api_url))
+                .header("PRIVATE-TOKEN", token),
+        )?;
 
         if response.status() == 401 {
             return Err(GitLabError::AuthenticationFailed);
         }
 
-        let token_info: GitLabToken = response.error_for_status()?.json()?;
+        if !response.status().is_success() {
+            return Err(api_error(response));
+        }
+
+        let token_info: GitLabToken = response.json()?;
 
         // Check if token has 'api' scope
         if !token_info.scopes.contains(&"api".to_string()) {
@@ -311,6 +513,8 @@ impl GitLabClient {
 
         let input = format!("protocol=https\nhost={}\n\n", host);
 
+        // The credential itself is never traced, only the exit status
+        let _span = tracing::debug_span!("subprocess", command = "git credential fill").entered();
         let output = Command::new("git")
             .args(["credential", "fill"])
             .stdin(std::process::Stdio::piped())
@@ -328,6 +532,7 @@ impl GitLabClient {
                     .output()
                     .ok()
             })?;
+        trace::record_exit(&output.status);
 
         if !output.status.success() {
             return None;
@@ -372,7 +577,11 @@ impl GitLabClient {
                 eprintln!("Running 'glab auth login'...");
                 eprintln!();
 
-                let status = Command::new("glab").args(["auth", "login"]).status().ok()?;
+                let mut command = Command::new("glab");
+                command.args(["auth", "login"]);
+                let _span = trace::subprocess_span(&command).entered();
+                let status = command.status().ok()?;
+                trace::record_exit(&status);
 
                 if status.success() {
                     // Try to read the token that was just created
@@ -425,11 +634,7 @@ impl GitLabClient {
     /// # Arguments
     ///
     /// * `project_path` - Project path (e.g., "owner/repo")
-    /// * `source_branch` - Source branch name
-    /// * `target_branch` - Target branch name
-    /// * `title` - MR title
-    /// * `description` - MR description (optional)
-    /// * `draft` - Whether to create as draft
+    /// * `params` - Branches, title, description, draft status and triage
     ///
     /// # Errors
     ///
@@ -441,11 +646,7 @@ impl GitLabClient {
     pub fn create_merge_request(
         &self,
         project_path: &str,
-        source_branch: &str,
-        target_branch: &str,
-        title: &str,
-        description: Option<&str>,
-        draft: bool,
+        params: CreateMergeRequestParams,
     ) -> Result<MergeRequest> {
         let token = self
             .token
@@ -455,28 +656,15 @@ impl GitLabClient {
         let project_id = urlencoding::encode(project_path);
         let url = format!("{}/projects/{}/merge_requests", self.api_url, project_id);
 
-        let params = CreateMergeRequestParams {
-            source_branch: source_branch.to_string(),
-            target_branch: target_branch.to_string(),
-            title: title.to_string(),
-            description: description.map(String::from),
-            draft: Some(draft),
-        };
-
-        let response = self
-            .client
-            .post(&url)
-            .header("PRIVATE-TOKEN", token)
-            .json(&params)
-            .send()?;
+        let response = self.send(
+            self.client
+                .post(&url)
+                .header("PRIVATE-TOKEN", token)
+                .json(&params),
+        )?;
 
         if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let body = response.text().unwrap_or_default();
-            return Err(GitLabError::ApiError {
-                status,
-                message: body,
-            });
+            return Err(api_error(response));
         }
 
         let mr = response.json::<MergeRequest>()?;
@@ -514,20 +702,15 @@ impl GitLabClient {
             self.api_url, project_id, mr_iid
         );
 
-        let response = self
-            .client
-            .put(&url)
-            .header("PRIVATE-TOKEN", token)
-            .json(&params)
-            .send()?;
+        let response = self.send(
+            self.client
+                .put(&url)
+                .header("PRIVATE-TOKEN", token)
+                .json(&params),
+        )?;
 
         if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let body = response.text().unwrap_or_default();
-            return Err(GitLabError::ApiError {
-                status,
-                message: body,
-            });
+            return Err(api_error(response));
         }
 
         let mr = response.json::<MergeRequest>()?;
@@ -555,32 +738,268 @@ impl GitLabClient {
 
         let project_id = urlencoding::encode(project_path);
         let url = format!(
-            "{}/projects/{}/merge_requests/{}",
+            "{}/projects/{}/merge_requests/{}?include_rebase_in_progress=true",
             self.api_url, project_id, mr_iid
         );
 
-        let response = self
-            .client
-            .get(&url)
-            .header("PRIVATE-TOKEN", token)
-            .send()?;
+        let response = self.send(self.client.get(&url).header("PRIVATE-TOKEN", token))?;
 
         if response.status() == 404 {
             return Err(GitLabError::MergeRequestNotFound(mr_iid));
         }
 
         if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let body = response.text().unwrap_or_default();
-            return Err(GitLabError::ApiError {
-                status,
-                message: body,
-            });
+            return Err(api_error(response));
         }
 
         let mr = response.json::<MergeRequest>()?;
         Ok(mr)
     }
+
+    /// Merge a merge request
+    ///
+    /// # Arguments
+    ///
+    /// * `project_path` - Project path (e.g., "owner/repo")
+    /// * `mr_iid` - Merge request IID (internal ID, not global ID)
+    /// * `params` - Merge parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Not authenticated
+    /// - MR doesn't exist
+    /// - MR can't be merged (not approved, pipeline failed, conflicts, draft)
+    /// - Network error occurs
+    pub fn accept_merge_request(
+        &self,
+        project_path: &str,
+        mr_iid: u64,
+        params: AcceptMergeRequestParams,
+    ) -> Result<MergeRequest> {
+        let token = self
+            .token
+            .as_ref()
+            .ok_or_else(|| GitLabError::NoTokenAvailable)?;
+
+        let project_id = urlencoding::encode(project_path);
+        let url = format!(
+            "{}/projects/{}/merge_requests/{}/merge",
+            self.api_url, project_id, mr_iid
+        );
+
+        let response = self.send_once(
+            self.client
+                .put(&url)
+                .header("PRIVATE-TOKEN", token)
+                .json(&params),
+        )?;
+
+        if response.status() == 404 {
+            return Err(GitLabError::MergeRequestNotFound(mr_iid));
+        }
+
+        if !response.status().is_success() {
+            return Err(api_error(response));
+        }
+
+        let mr = response.json::<MergeRequest>()?;
+        Ok(mr)
+    }
+
+    /// Rebase a merge request's source branch onto its target branch
+    ///
+    /// The rebase runs asynchronously on the server; poll
+    /// [`get_merge_request`](Self::get_merge_request) until
+    /// `rebase_in_progress` is false.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Not authenticated
+    /// - MR doesn't exist
+    /// - Network error occurs
+    pub fn rebase_merge_request(&self, project_path: &str, mr_iid: u64) -> Result<()> {
+        let token = self
+            .token
+            .as_ref()
+            .ok_or_else(|| GitLabError::NoTokenAvailable)?;
+
+        let project_id = urlencoding::encode(project_path);
+        let url = format!(
+            "{}/projects/{}/merge_requests/{}/rebase",
+            self.api_url, project_id, mr_iid
+        );
+
+        let response = self.send_once(self.client.put(&url).header("PRIVATE-TOKEN", token))?;
+
+        if response.status() == 404 {
+            return Err(GitLabError::MergeRequestNotFound(mr_iid));
+        }
+
+        if !response.status().is_success() {
+            return Err(api_error(response));
+        }
+
+        Ok(())
+    }
+
+    /// Get the approval status of a merge request
+    ///
+    /// On GitLab tiers without approval rules, `approvals_required` is 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Not authenticated
+    /// - MR doesn't exist
+    /// - Network error occurs
+    pub fn get_merge_request_approvals(
+        &self,
+        project_path: &str,
+        mr_iid: u64,
+    ) -> Result<MergeRequestApprovals> {
+        let token = self
+            .token
+            .as_ref()
+            .ok_or_else(|| GitLabError::NoTokenAvailable)?;
+
+        let project_id = urlencoding::encode(project_path);
+        let url = format!(
+            "{}/projects/{}/merge_requests/{}/approvals",
+            self.api_url, project_id, mr_iid
+        );
+
+        let response = self.send(self.client.get(&url).header("PRIVATE-TOKEN", token))?;
+
+        if response.status() == 404 {
+            return Err(GitLabError::MergeRequestNotFound(mr_iid));
+        }
+
+        if !response.status().is_success() {
+            return Err(api_error(response));
+        }
+
+        let approvals = response.json::<MergeRequestApprovals>()?;
+        Ok(approvals)
+    }
+
+    /// List all discussion threads of a merge request
+    ///
+    /// Follows pagination until every discussion has been fetched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Not authenticated
+    /// - MR doesn't exist
+    /// - Network error occurs
+    pub fn list_merge_request_discussions(
+        &self,
+        project_path: &str,
+        mr_iid: u64,
+    ) -> Result<Vec<Discussion>> {
+        const PER_PAGE: usize = 100;
+
+        let token = self
+            .token
+            .as_ref()
+            .ok_or_else(|| GitLabError::NoTokenAvailable)?;
+
+        let project_id = urlencoding::encode(project_path);
+        let mut discussions = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let url = format!(
+                "{}/projects/{}/merge_requests/{}/discussions?per_page={}&page={}",
+                self.api_url, project_id, mr_iid, PER_PAGE, page
+            );
+
+            let response = self.send(self.client.get(&url).header("PRIVATE-TOKEN", token))?;
+
+            if response.status() == 404 {
+                return Err(GitLabError::MergeRequestNotFound(mr_iid));
+            }
+
+            if !response.status().is_success() {
+                return Err(api_error(response));
+            }
+
+            let batch = response.json::<Vec<Discussion>>()?;
+            let done = batch.len() < PER_PAGE;
+            discussions.extend(batch);
+            if done {
+                return Ok(discussions);
+            }
+            page += 1;
+        }
+    }
+
+    /// Find a user by username
+    ///
+    /// Returns `None` if no user has this username.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Not authenticated
+    /// - Network error occurs
+    pub fn find_user(&self, username: &str) -> Result<Option<GitLabUser>> {
+        let token = self
+            .token
+            .as_ref()
+            .ok_or_else(|| GitLabError::NoTokenAvailable)?;
+
+        let url = format!(
+            "{}/users?username={}",
+            self.api_url,
+            urlencoding::encode(username)
+        );
+
+        let response = self.send(self.client.get(&url).header("PRIVATE-TOKEN", token))?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response));
+        }
+
+        let users = response.json::<Vec<GitLabUser>>()?;
+        Ok(users.into_iter().next())
+    }
+
+    /// Find a project milestone by title
+    ///
+    /// Returns `None` if the project has no milestone with this title.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Not authenticated
+    /// - Project doesn't exist
+    /// - Network error occurs
+    pub fn find_milestone(&self, project_path: &str, title: &str) -> Result<Option<Milestone>> {
+        let token = self
+            .token
+            .as_ref()
+            .ok_or_else(|| GitLabError::NoTokenAvailable)?;
+
+        let project_id = urlencoding::encode(project_path);
+        let url = format!(
+            "{}/projects/{}/milestones?title={}",
+            self.api_url,
+            project_id,
+            urlencoding::encode(title)
+        );
+
+        let response = self.send(self.client.get(&url).header("PRIVATE-TOKEN", token))?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response));
+        }
+
+        let milestones = response.json::<Vec<Milestone>>()?;
+        Ok(milestones.into_iter().next())
+    }
 }
 
 #[cfg(test)]
@@ -603,6 +1022,38 @@ mod tests {
         assert_eq!(client.extract_host_from_api_url(), "gitlab.example.com");
     }
 
+    #[test]
+    fn test_parse_discussion() {
+        let json = r#"{
+            "id": "6a9c1750b37d513a43987b574953fceb50b03ce7",
+            "individual_note": false,
+            "notes": [{
+                "id": 1126,
+                "body": "Should this be configurable?",
+                "author": {"id": 1, "username": "alice", "name": "Alice"},
+                "created_at": "2024-03-03T09:10:11.000Z",
+                "system": false,
+                "resolvable": true,
+                "resolved": false,
+                "position": {
+                    "new_path": "src/main.rs",
+                    "old_path": "src/main.rs",
+                    "new_line": 42,
+                    "old_line": null
+                }
+            }]
+        }"#;
+
+        let discussion: Discussion = serde_json::from_str(json).unwrap();
+        assert_eq!(discussion.notes.len(), 1);
+        let note = &discussion.notes[0];
+        assert_eq!(note.author.username, "alice");
+        assert!(note.resolvable && !note.resolved);
+        let position = note.position.as_ref().unwrap();
+        assert_eq!(position.new_path.as_deref(), Some("src/main.rs"));
+        assert_eq!(position.new_line, Some(42));
+    }
+
     #[test]
     fn test_api_url_normalization() {
         let client = GitLabClient::new("https://gitlab.com/").unwrap();
@@ -0,0 +1,534 @@
+//! HTTP request layer shared by provider API clients
+//!
+//! Wraps a blocking `reqwest` client with:
+//!
+//! - a configurable timeout (`[http] timeout` in `.basalt.toml`)
+//! - an optional proxy, extra root certificates and a client certificate
+//!   for mutual TLS, taken from git config and the environment
+//! - retries with exponential backoff for idempotent requests that fail
+//!   with a connection error, a timeout, or a 502, 503 or 504; requests
+//!   sent with [`Retry::Unprocessed`] are only retried when the server
+//!   can't have acted on them
+//! - rate limit handling: 429 responses are retried for any method after
+//!   waiting for `Retry-After` or the `RateLimit-Reset` time, and requests
+//!   pause once `RateLimit-Remaining` reaches zero
+//! - tracing of every attempt with method, URL, status and latency
+//! - [`concurrently`], to send independent requests in parallel with a
+//!   bounded number in flight
+//!
+//! Both GitLab (`RateLimit-*`) and GitHub (`X-RateLimit-*`) header names
+//! are understood.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use crate::providers::http::HttpClient;
+//!
+//! let client = HttpClient::new(&config::load_http_config(base_url)?)?;
+//! let response = client.send(client.get("https://gitlab.com/api/v4/user"))?;
+//! println!("Request id: {:?}", http::request_id(&response));
+//! ```
+
+use crate::core::config::HttpConfig;
+use crate::core::trace;
+use crate::error::{Error, Result};
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::{Certificate, Identity, Method, NoProxy, Proxy, StatusCode};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Delay before the first retry; doubled for each later one
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest delay between two attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Longest wait for a rate limit to reset before giving up
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(120);
+
+/// Headers providers use to identify a request in their logs
+const REQUEST_ID_HEADERS: &[&str] = &["x-request-id", "x-github-request-id"];
+
+/// Which failed requests may be sent again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retry {
+    /// Retry idempotent methods on transient failures
+    ByMethod,
+    /// Only retry failures that guarantee the request wasn't acted on:
+    /// connection errors and 429s
+    ///
+    /// For requests with side effects that a 5xx or a timeout could hide,
+    /// such as merging, even if their method is PUT.
+    Unprocessed,
+}
+
+/// HTTP client with retries and rate limit handling
+pub struct HttpClient {
+    client: Client,
+    max_retries: u32,
+    /// When requests may resume after the rate limit was exhausted
+    paused_until: Mutex<Option<Instant>>,
+}
+
+impl HttpClient {
+    /// Create a client with the given settings
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the proxy URL or a certificate is invalid, or
+    /// the TLS backend can't be initialized
+    pub fn new(config: &HttpConfig) -> Result<Self> {
+        let builder = Client::builder()
+            .user_agent("basalt-cli")
+            .timeout(Duration::from_secs(config.timeout));
+        let client = configure_tls(configure_proxy(builder, config)?, config)?
+            .build()
+            .map_err(|e| Error::config(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            max_retries: config.max_retries,
+            paused_until: Mutex::new(None),
+        })
+    }
+
+    /// Start building a GET request
+    pub fn get(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// Start building a POST request
+    pub fn post(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    /// Start building a PUT request
+    pub fn put(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.client.put(url)
+    }
+
+    /// Send a request, retrying it when its method is idempotent
+    ///
+    /// See [`send_with`](Self::send_with).
+    ///
+    /// # Errors
+    ///
+    /// Returns the transport error of the last attempt if none got a
+    /// response
+    pub fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        self.send_with(request, Retry::ByMethod)
+    }
+
+    /// Send a request, retrying it as `retry` allows
+    ///
+    /// Returns the last response, even if its status is an error, so that
+    /// callers can report the provider's message.
+    ///
+    /// # Errors
+    ///
+    /// Returns the transport error of the last attempt if none got a
+    /// response
+    pub fn send_with(&self, request: RequestBuilder, retry: Retry) -> reqwest::Result<Response> {
+        let request = request.build()?;
+        let idempotent = retry == Retry::ByMethod && is_idempotent(request.method());
+        let span = tracing::debug_span!(
+            "http",
+            method = %request.method(),
+            url = %trace::redact(request.url().as_str()),
+        );
+        let _span = span.enter();
+
+        let mut attempt = 0;
+        loop {
+            self.wait_for_rate_limit();
+
+            // Bodies are buffered, so requests can always be cloned
+            let retry_request = request.try_clone();
+            let start = Instant::now();
+            let result = match retry_request {
+                Some(request) => self.client.execute(request),
+                None => return self.client.execute(request),
+            };
+            let latency_ms = start.elapsed().as_millis() as u64;
+
+            let retry_after = match &result {
+                Ok(response) => {
+                    tracing::debug!(
+                        attempt,
+                        status = response.status().as_u16(),
+                        latency_ms,
+                        request_id = request_id(response).as_deref(),
+                        "response"
+                    );
+                    self.record_rate_limit(response.headers());
+                    retry_delay(response.status(), response.headers(), idempotent, attempt)
+                }
+                Err(e) => {
+                    tracing::debug!(attempt, error = %e, latency_ms, "request failed");
+                    let retryable = e.is_connect() || (idempotent && e.is_timeout());
+                    retryable.then(|| backoff(attempt))
+                }
+            };
+
+            match retry_after {
+                Some(delay) if attempt < self.max_retries && delay <= MAX_RATE_LIMIT_WAIT => {
+                    tracing::debug!(delay_ms = delay.as_millis() as u64, "retrying");
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// Sleep until the rate limit resets, if it was exhausted
+    ///
+    /// The deadline is shared by every thread sending requests, so it is
+    /// only cleared once it has passed.
+    fn wait_for_rate_limit(&self) {
+        let paused_until = {
+            let mut paused_until = self
+                .paused_until
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if paused_until.is_some_and(|until| Instant::now() >= until) {
+                *paused_until = None;
+            }
+            *paused_until
+        };
+        if let Some(until) = paused_until {
+            let wait = until.saturating_duration_since(Instant::now());
+            if !wait.is_zero() && wait <= MAX_RATE_LIMIT_WAIT {
+                tracing::debug!(
+                    wait_ms = wait.as_millis() as u64,
+                    "rate limit exhausted, waiting"
+                );
+                thread::sleep(wait);
+            }
+        }
+    }
+
+    /// Remember when to resume if the response used up the rate limit
+    fn record_rate_limit(&self, headers: &HeaderMap) {
+        let remaining = header_u64(headers, &["ratelimit-remaining", "x-ratelimit-remaining"]);
+        if remaining == Some(0) {
+            if let Some(wait) = rate_limit_reset(headers) {
+                *self
+                    .paused_until
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now() + wait);
+            }
+        }
+    }
+}
+
+/// Route requests through the configured proxy
+///
+/// Without one, reqwest reads `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`
+/// itself. Hosts in `NO_PROXY` bypass a configured proxy too.
+fn configure_proxy(builder: ClientBuilder, config: &HttpConfig) -> Result<ClientBuilder> {
+    let Some(url) = &config.proxy else {
+        return Ok(builder);
+    };
+    let proxy = Proxy::all(url)
+        .map_err(|e| Error::config(format!("Invalid proxy '{}': {}", trace::redact(url), e)))?
+        .no_proxy(NoProxy::from_env());
+    Ok(builder.proxy(proxy))
+}
+
+/// Trust the configured root certificates and present the client certificate
+fn configure_tls(mut builder: ClientBuilder, config: &HttpConfig) -> Result<ClientBuilder> {
+    if let Some(path) = &config.ca_cert {
+        let certificates =
+            Certificate::from_pem_bundle(&read_pem(path)?).map_err(|e| invalid_pem(path, e))?;
+        if certificates.is_empty() {
+            return Err(Error::config(format!(
+                "No certificates found in {}",
+                path.display()
+            )));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    match (&config.client_cert, &config.client_key) {
+        (Some(cert), Some(key)) => {
+            let identity = Identity::from_pkcs8_pem(&read_pem(cert)?, &read_pem(key)?)
+                .map_err(|e| invalid_pem(cert, e))?;
+            builder = builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(Error::config(
+                "http.sslCert and http.sslKey (or GIT_SSL_CERT and GIT_SSL_KEY) must be set together",
+            ));
+        }
+    }
+
+    Ok(builder)
+}
+
+/// Read a PEM file named in the configuration
+fn read_pem(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| {
+        Error::config(format!(
+            "Failed to read certificate {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Describe a PEM file that couldn't be parsed
+fn invalid_pem(path: &Path, error: reqwest::Error) -> Error {
+    Error::config(format!("Invalid certificate {}: {}", path.display(), error))
+}
+
+/// Apply `f` to every item, running at most `limit` calls at once
+///
+/// Meant for independent blocking requests, such as fetching the reviews
+/// of a whole stack. Results are in the order of `items`.
+pub fn concurrently<T, R, F>(items: Vec<T>, limit: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let count = items.len();
+    let workers = limit.min(count);
+    if workers <= 1 {
+        return items.into_iter().map(f).collect();
+    }
+
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..count).map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let next = queue
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .next();
+                    let Some((index, item)) = next else {
+                        break;
+                    };
+                    let result = f(item);
+                    results
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .into_iter()
+        .map(|result| result.expect("every item is processed"))
+        .collect()
+}
+
+/// Get the id the provider assigned to a request, for error reports
+pub fn request_id(response: &Response) -> Option<String> {
+    REQUEST_ID_HEADERS.iter().find_map(|name| {
+        response
+            .headers()
+            .get(*name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    })
+}
+
+/// Check whether repeating a request has the same effect as sending it once
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+/// Get how long to wait before retrying a response, if it should be
+///
+/// Rate limited requests weren't processed, so they are retried whatever
+/// their method.
+fn retry_delay(
+    status: StatusCode,
+    headers: &HeaderMap,
+    idempotent: bool,
+    attempt: u32,
+) -> Option<Duration> {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => Some(
+            retry_after(headers)
+                .or_else(|| rate_limit_reset(headers))
+                .unwrap_or_else(|| backoff(attempt)),
+        ),
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+            if idempotent =>
+        {
+            Some(retry_after(headers).unwrap_or_else(|| backoff(attempt)))
+        }
+        _ => None,
+    }
+}
+
+/// Get the exponential backoff before retry number `attempt + 1`
+///
+/// Up to a quarter of the delay is added as jitter, so that parallel
+/// requests don't retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let delay = INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.subsec_nanos())
+        .unwrap_or_default();
+    delay + delay.mul_f64(f64::from(nanos % 1000) / 4000.0)
+}
+
+/// Parse the `Retry-After` header, in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get("retry-after")?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let seconds = (date.timestamp() - chrono::Utc::now().timestamp()).max(0);
+    Some(Duration::from_secs(seconds as u64))
+}
+
+/// Get how long until the rate limit resets
+///
+/// `RateLimit-Reset` is a Unix timestamp on GitLab and GitHub.
+fn rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+    let reset = header_u64(headers, &["ratelimit-reset", "x-ratelimit-reset"])?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(Duration::from_secs(reset.saturating_sub(now)))
+}
+
+/// Get the first of the given headers that holds a number
+fn header_u64(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+    names.iter().find_map(|name| {
+        headers
+            .get(*name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let none = HeaderMap::new();
+        assert!(retry_delay(StatusCode::BAD_GATEWAY, &none, true, 0).is_some());
+        assert!(retry_delay(StatusCode::BAD_GATEWAY, &none, false, 0).is_none());
+        assert!(retry_delay(StatusCode::NOT_FOUND, &none, true, 0).is_none());
+
+        let limited = headers(&[("retry-after", "7")]);
+        assert_eq!(
+            retry_delay(StatusCode::TOO_MANY_REQUESTS, &limited, false, 0),
+            Some(Duration::from_secs(7))
+        );
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let reset = headers(&[("ratelimit-reset", &(now + 20).to_string())]);
+        let delay = retry_delay(StatusCode::TOO_MANY_REQUESTS, &reset, true, 0).unwrap();
+        assert!(delay > Duration::from_secs(18) && delay <= Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_invalid_tls_config_is_rejected() {
+        let only_cert = HttpConfig {
+            client_cert: Some("client.pem".into()),
+            ..HttpConfig::default()
+        };
+        assert!(
+            HttpClient::new(&only_cert)
+                .err()
+                .unwrap()
+                .to_string()
+                .contains("must be set together")
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let ca_cert = dir.path().join("ca.pem");
+        fs::write(&ca_cert, "not a certificate").unwrap();
+        let bad_ca = HttpConfig {
+            ca_cert: Some(ca_cert),
+            ..HttpConfig::default()
+        };
+        assert!(HttpClient::new(&bad_ca).is_err());
+
+        let proxy = HttpConfig {
+            proxy: Some("http://proxy.corp:3128".to_string()),
+            ..HttpConfig::default()
+        };
+        assert!(HttpClient::new(&proxy).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_pause_is_shared() {
+        let client = HttpClient::new(&HttpConfig::default()).unwrap();
+        let until = Instant::now() + Duration::from_millis(200);
+        *client.paused_until.lock().unwrap() = Some(until);
+
+        // Every worker waits, not just the first one
+        let resumed = concurrently(vec![(); 3], 3, |_| {
+            client.wait_for_rate_limit();
+            Instant::now()
+        });
+        assert!(resumed.iter().all(|resumed| *resumed >= until));
+
+        client.wait_for_rate_limit();
+        assert!(client.paused_until.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_concurrently_keeps_order_and_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let results = concurrently((0..20).collect(), 3, |n: u32| {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(5));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            n * 2
+        });
+
+        assert_eq!(results, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert!(concurrently(Vec::<u32>::new(), 3, |n| n).is_empty());
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        assert!(backoff(0) >= INITIAL_BACKOFF && backoff(0) < INITIAL_BACKOFF * 2);
+        assert!(backoff(2) >= INITIAL_BACKOFF * 4);
+        assert!(backoff(20) <= MAX_BACKOFF + MAX_BACKOFF / 4);
+    }
+}
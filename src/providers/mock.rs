@@ -7,9 +7,10 @@
 
 use crate::error::{Error, Result};
 use crate::providers::{
-    CreateReviewParams, Provider, ProviderType, Review, ReviewState, UpdateReviewParams,
+    CreateReviewParams, Discussion, MergeReviewParams, MergeStatus, Provider, ProviderType, Review,
+    ReviewChecks, ReviewState, UpdateReviewParams,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
 /// Mock provider for testing
@@ -30,6 +31,12 @@ struct MockProviderState {
     reviews: HashMap<String, Review>,
     /// Reviews by branch name
     branch_to_review: HashMap<String, String>,
+    /// Simulated checks by review ID (reviews without an entry are mergeable)
+    checks: HashMap<String, ReviewChecks>,
+    /// Checks returned before `checks`, one per call, by review ID
+    queued_checks: HashMap<String, VecDeque<ReviewChecks>>,
+    /// Discussion threads by review ID
+    discussions: HashMap<String, Vec<Discussion>>,
     /// Next review ID counter
     next_id: u32,
     /// Whether authentication is valid
@@ -38,6 +45,7 @@ struct MockProviderState {
     should_fail_create: bool,
     should_fail_update: bool,
     should_fail_get: bool,
+    should_fail_merge: bool,
 }
 
 impl MockProvider {
@@ -83,6 +91,48 @@ impl MockProvider {
         self.state.lock().unwrap().should_fail_get = true;
     }
 
+    /// Make merge_review fail on next call
+    pub fn fail_next_merge(&self) {
+        self.state.lock().unwrap().should_fail_merge = true;
+    }
+
+    /// Simulate the approval, pipeline and mergeability status of a review
+    ///
+    /// `merge_review` refuses to merge a review whose checks have blockers.
+    pub fn set_review_checks(&self, review_id: &str, checks: ReviewChecks) {
+        self.state
+            .lock()
+            .unwrap()
+            .checks
+            .insert(review_id.to_string(), checks);
+    }
+
+    /// Queue checks returned by the next `get_review_checks` call
+    ///
+    /// Queued checks are returned once each, in order, before the ones set
+    /// with [`MockProvider::set_review_checks`]. `merge_review` refuses to
+    /// merge while the next queued checks are pending.
+    pub fn queue_review_checks(&self, review_id: &str, checks: ReviewChecks) {
+        self.state
+            .lock()
+            .unwrap()
+            .queued_checks
+            .entry(review_id.to_string())
+            .or_default()
+            .push_back(checks);
+    }
+
+    /// Add a discussion thread to a review
+    pub fn add_discussion(&self, review_id: &str, discussion: Discussion) {
+        self.state
+            .lock()
+            .unwrap()
+            .discussions
+            .entry(review_id.to_string())
+            .or_default()
+            .push(discussion);
+    }
+
     /// Get all reviews
     pub fn get_all_reviews(&self) -> Vec<Review> {
         self.state
@@ -99,6 +149,8 @@ impl MockProvider {
         let mut state = self.state.lock().unwrap();
         state.reviews.clear();
         state.branch_to_review.clear();
+        state.checks.clear();
+        state.discussions.clear();
         state.next_id = 1;
     }
 
@@ -169,6 +221,10 @@ impl Provider for MockProvider {
             target_branch: params.target_branch,
             draft: params.draft,
             state: ReviewState::Open,
+            reviewers: params.reviewers,
+            assignees: params.assignees,
+            labels: params.labels,
+            milestone: params.milestone,
         };
 
         state.reviews.insert(review_id.clone(), review.clone());
@@ -207,6 +263,18 @@ impl Provider for MockProvider {
         if let Some(draft) = params.draft {
             review.draft = draft;
         }
+        if let Some(reviewers) = params.reviewers {
+            review.reviewers = reviewers;
+        }
+        if let Some(assignees) = params.assignees {
+            review.assignees = assignees;
+        }
+        if let Some(labels) = params.labels {
+            review.labels = labels;
+        }
+        if let Some(milestone) = params.milestone {
+            review.milestone = Some(milestone);
+        }
 
         Ok(review.clone())
     }
@@ -228,6 +296,127 @@ impl Provider for MockProvider {
             })
     }
 
+    fn close_review(&mut self, review_id: &str) -> Result<Review> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.should_fail_update {
+            state.should_fail_update = false;
+            return Err(Error::provider_op("Simulated update failure"));
+        }
+
+        let review = state
+            .reviews
+            .get_mut(review_id)
+            .ok_or_else(|| Error::ReviewNotFound {
+                branch: review_id.to_string(),
+            })?;
+        review.state = ReviewState::Closed;
+
+        Ok(review.clone())
+    }
+
+    fn merge_review(&mut self, params: MergeReviewParams) -> Result<Review> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.should_fail_merge {
+            state.should_fail_merge = false;
+            return Err(Error::provider_op("Simulated merge failure"));
+        }
+
+        // Like GitLab, refuse merging while the checks are still pending
+        let pending = state
+            .queued_checks
+            .get(&params.review_id)
+            .and_then(VecDeque::front)
+            .is_some_and(ReviewChecks::is_pending);
+        if pending {
+            return Err(Error::provider_op(format!(
+                "Review {} cannot be merged yet: checks are pending",
+                params.review_id
+            )));
+        }
+
+        let blockers = state
+            .checks
+            .get(&params.review_id)
+            .map(ReviewChecks::blockers)
+            .unwrap_or_default();
+
+        let review =
+            state
+                .reviews
+                .get_mut(&params.review_id)
+                .ok_or_else(|| Error::ReviewNotFound {
+                    branch: params.review_id.clone(),
+                })?;
+
+        if !blockers.is_empty() {
+            return Err(Error::provider_op(format!(
+                "Review {} cannot be merged: {}",
+                review.id,
+                blockers.join(", ")
+            )));
+        }
+        if review.state != ReviewState::Open {
+            return Err(Error::provider_op(format!(
+                "Review {} is {}",
+                review.id, review.state
+            )));
+        }
+        review.state = ReviewState::Merged;
+
+        Ok(review.clone())
+    }
+
+    fn get_review_checks(&mut self, review_id: &str) -> Result<ReviewChecks> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.should_fail_get {
+            return Err(Error::provider_op("Simulated get failure"));
+        }
+        if !state.reviews.contains_key(review_id) {
+            return Err(Error::ReviewNotFound {
+                branch: review_id.to_string(),
+            });
+        }
+
+        let queued = state
+            .queued_checks
+            .get_mut(review_id)
+            .and_then(VecDeque::pop_front);
+        if let Some(checks) = queued {
+            return Ok(checks);
+        }
+
+        Ok(state
+            .checks
+            .get(review_id)
+            .cloned()
+            .unwrap_or_else(|| ReviewChecks {
+                merge_status: MergeStatus::Mergeable,
+                ..Default::default()
+            }))
+    }
+
+    fn list_discussions(&mut self, review_id: &str) -> Result<Vec<Discussion>> {
+        let state = self.state.lock().unwrap();
+
+        if state.should_fail_get {
+            return Err(Error::provider_op("Simulated get failure"));
+        }
+        if !state.reviews.contains_key(review_id) {
+            return Err(Error::ReviewNotFound {
+                branch: review_id.to_string(),
+            });
+        }
+
+        Ok(state
+            .discussions
+            .get(review_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
     fn find_review_for_branch(&mut self, branch: &str) -> Result<Option<Review>> {
         let state = self.state.lock().unwrap();
 
@@ -273,6 +462,7 @@ mod tests {
             title: "Test MR".to_string(),
             description: Some("Test description".to_string()),
             draft: true,
+            ..Default::default()
         };
 
         let review = provider.create_review(params).unwrap();
@@ -295,6 +485,7 @@ mod tests {
             title: "Test PR".to_string(),
             description: Some("Test description".to_string()),
             draft: false,
+            ..Default::default()
         };
 
         let review = provider.create_review(params).unwrap();
@@ -312,6 +503,7 @@ mod tests {
             title: "Original Title".to_string(),
             description: Some("Original description".to_string()),
             draft: true,
+            ..Default::default()
         };
 
         let review = provider.create_review(params).unwrap();
@@ -323,6 +515,7 @@ mod tests {
             description: None,
             target_branch: None,
             draft: Some(false),
+            ..Default::default()
         };
 
         let updated = provider.update_review(update_params).unwrap();
@@ -344,6 +537,7 @@ mod tests {
             title: "Original Title".to_string(),
             description: Some("Original description".to_string()),
             draft: true,
+            ..Default::default()
         };
 
         let review = provider.create_review(params).unwrap();
@@ -355,6 +549,7 @@ mod tests {
             description: None,
             target_branch: None,
             draft: Some(false),
+            ..Default::default()
         };
 
         let updated = provider.update_review(update_params).unwrap();
@@ -366,6 +561,38 @@ mod tests {
         assert!(!updated.draft);
     }
 
+    #[test]
+    fn test_review_triage() {
+        let mut provider = MockProvider::new_gitlab();
+
+        let params = CreateReviewParams {
+            source_branch: "feature".to_string(),
+            target_branch: "main".to_string(),
+            title: "Test MR".to_string(),
+            reviewers: vec!["alice".to_string()],
+            labels: vec!["backend".to_string()],
+            milestone: Some("v1.0".to_string()),
+            ..Default::default()
+        };
+        let review = provider.create_review(params).unwrap();
+        assert_eq!(review.reviewers, vec!["alice"]);
+        assert_eq!(review.labels, vec!["backend"]);
+        assert_eq!(review.milestone.as_deref(), Some("v1.0"));
+        assert!(review.assignees.is_empty());
+
+        let updated = provider
+            .update_review(UpdateReviewParams {
+                review_id: review.id.clone(),
+                reviewers: Some(vec!["alice".to_string(), "bob".to_string()]),
+                assignees: Some(vec!["carol".to_string()]),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(updated.reviewers, vec!["alice", "bob"]);
+        assert_eq!(updated.assignees, vec!["carol"]);
+        assert_eq!(updated.labels, vec!["backend"]);
+    }
+
     #[test]
     fn test_get_review() {
         let mut provider = MockProvider::new_gitlab();
@@ -376,6 +603,7 @@ mod tests {
             title: "Test MR".to_string(),
             description: Some("Test description".to_string()),
             draft: true,
+            ..Default::default()
         };
 
         let created = provider.create_review(params).unwrap();
@@ -395,6 +623,7 @@ mod tests {
             title: "Test MR".to_string(),
             description: Some("Test description".to_string()),
             draft: true,
+            ..Default::default()
         };
 
         let created = provider.create_review(params).unwrap();
@@ -406,6 +635,135 @@ mod tests {
         assert!(not_found.is_none());
     }
 
+    #[test]
+    fn test_close_review() {
+        let mut provider = MockProvider::new_gitlab();
+
+        let params = CreateReviewParams {
+            source_branch: "feature".to_string(),
+            target_branch: "main".to_string(),
+            title: "Test MR".to_string(),
+            description: None,
+            draft: true,
+            ..Default::default()
+        };
+
+        let created = provider.create_review(params).unwrap();
+        let closed = provider.close_review(&created.id).unwrap();
+        assert_eq!(closed.state, ReviewState::Closed);
+        assert!(provider.close_review("!999").is_err());
+    }
+
+    #[test]
+    fn test_merge_review() {
+        let mut provider = MockProvider::new_gitlab();
+
+        let params = CreateReviewParams {
+            source_branch: "feature".to_string(),
+            target_branch: "main".to_string(),
+            title: "Test MR".to_string(),
+            description: None,
+            draft: false,
+            ..Default::default()
+        };
+        let created = provider.create_review(params).unwrap();
+
+        provider.fail_next_merge();
+        let merge = MergeReviewParams {
+            review_id: created.id.clone(),
+            method: crate::providers::MergeMethod::Squash,
+            sha: None,
+        };
+        assert!(provider.merge_review(merge.clone()).is_err());
+
+        let merged = provider.merge_review(merge.clone()).unwrap();
+        assert_eq!(merged.state, ReviewState::Merged);
+
+        // Merging twice fails
+        assert!(provider.merge_review(merge).is_err());
+    }
+
+    #[test]
+    fn test_review_checks() {
+        let mut provider = MockProvider::new_gitlab();
+
+        let params = CreateReviewParams {
+            source_branch: "feature".to_string(),
+            target_branch: "main".to_string(),
+            title: "Test MR".to_string(),
+            description: None,
+            draft: false,
+            ..Default::default()
+        };
+        let created = provider.create_review(params).unwrap();
+
+        let checks = provider.get_review_checks(&created.id).unwrap();
+        assert_eq!(checks.merge_status, MergeStatus::Mergeable);
+        assert!(checks.blockers().is_empty());
+
+        provider.set_review_checks(
+            &created.id,
+            ReviewChecks {
+                approvals: 0,
+                approvals_required: 1,
+                approvals_left: 1,
+                pipeline: Some(crate::providers::PipelineStatus::Failed),
+                has_conflicts: false,
+                merge_status: MergeStatus::Blocked,
+            },
+        );
+        let checks = provider.get_review_checks(&created.id).unwrap();
+        assert!(!checks.is_approved());
+
+        let merge = MergeReviewParams {
+            review_id: created.id.clone(),
+            method: crate::providers::MergeMethod::Merge,
+            sha: None,
+        };
+        let err = provider.merge_review(merge).unwrap_err().to_string();
+        assert!(err.contains("approval(s) required"));
+        assert!(err.contains("pipeline failed"));
+
+        assert!(provider.get_review_checks("!999").is_err());
+    }
+
+    #[test]
+    fn test_list_discussions() {
+        let mut provider = MockProvider::new_gitlab();
+
+        let params = CreateReviewParams {
+            source_branch: "feature".to_string(),
+            target_branch: "main".to_string(),
+            title: "Test MR".to_string(),
+            description: None,
+            draft: false,
+            ..Default::default()
+        };
+        let created = provider.create_review(params).unwrap();
+        assert!(provider.list_discussions(&created.id).unwrap().is_empty());
+
+        provider.add_discussion(
+            &created.id,
+            Discussion {
+                id: "d1".to_string(),
+                resolvable: true,
+                resolved: false,
+                file: Some("src/lib.rs".to_string()),
+                line: Some(7),
+                comments: vec![crate::providers::Comment {
+                    author: "alice".to_string(),
+                    body: "Typo".to_string(),
+                    created_at: chrono::Utc::now(),
+                }],
+            },
+        );
+        let discussions = provider.list_discussions(&created.id).unwrap();
+        assert_eq!(discussions.len(), 1);
+        assert!(discussions[0].is_unresolved());
+
+        assert!(provider.list_discussions("!999").is_err());
+    }
+
     #[test]
     fn test_simulated_failures() {
         let mut provider = MockProvider::new_gitlab();
@@ -417,6 +775,7 @@ mod tests {
             title: "Test MR".to_string(),
             description: Some("Test description".to_string()),
             draft: true,
+            ..Default::default()
         };
         assert!(provider.create_review(params).is_err());
     }
@@ -431,6 +790,7 @@ mod tests {
             title: "Test MR".to_string(),
             description: Some("Test description".to_string()),
             draft: true,
+            ..Default::default()
         };
 
         provider.create_review(params).unwrap();
@@ -450,6 +810,7 @@ mod tests {
             title: "First MR".to_string(),
             description: Some("First description".to_string()),
             draft: true,
+            ..Default::default()
         };
 
         let params2 = CreateReviewParams {
@@ -458,6 +819,7 @@ mod tests {
             title: "Second MR".to_string(),
             description: Some("Second description".to_string()),
             draft: true,
+            ..Default::default()
         };
 
         let review1 = provider.create_review(params1).unwrap();
@@ -466,4 +828,31 @@ mod tests {
         assert_eq!(review1.id, "!1");
         assert_eq!(review2.id, "!2");
     }
+
+    #[test]
+    fn test_batch_operations() {
+        let mut provider = MockProvider::new_gitlab();
+        let params = |branch: &str| CreateReviewParams {
+            source_branch: branch.to_string(),
+            target_branch: "main".to_string(),
+            title: branch.to_string(),
+            ..Default::default()
+        };
+
+        // A failed creation doesn't stop the others
+        provider.fail_next_create();
+        let created = provider.create_reviews(vec![params("a"), params("b"), params("c")]);
+        assert!(created[0].is_err());
+        assert_eq!(created[1].as_ref().unwrap().source_branch, "b");
+        assert_eq!(created[2].as_ref().unwrap().source_branch, "c");
+
+        let ids: Vec<String> = created[1..]
+            .iter()
+            .rev()
+            .map(|review| review.as_ref().unwrap().id.clone())
+            .collect();
+        let fetched = provider.get_reviews(&ids);
+        assert_eq!(fetched[0].as_ref().unwrap().source_branch, "c");
+        assert_eq!(fetched[1].as_ref().unwrap().source_branch, "b");
+    }
 }
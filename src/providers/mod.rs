@@ -58,16 +58,21 @@
 //! - [`gitlab::GitLabProvider`] - GitLab provider using REST API (in progress)
 //! - [`github::GitHubProvider`] - GitHub provider using REST API (planned)
 //! - [`mock::MockProvider`] - Mock provider for testing (complete)
+//!
+//! Provider API clients send requests through [`http::HttpClient`], which
+//! handles timeouts, retries and rate limits.
 
 #![allow(dead_code)] // Allow during early development
 
 use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
 pub mod github;
 pub mod gitlab;
 pub mod gitlab_api;
+pub mod http;
 pub mod mock;
 
 /// Supported provider types
@@ -225,6 +230,18 @@ pub struct Review {
     pub draft: bool,
     /// Review state (open, merged, closed)
     pub state: ReviewState,
+    /// Usernames of the requested reviewers
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+    /// Usernames of the assignees
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    /// Labels
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Milestone title
+    #[serde(default)]
+    pub milestone: Option<String>,
 }
 
 /// State of a code review
@@ -250,7 +267,10 @@ impl fmt::Display for ReviewState {
 }
 
 /// Parameters for creating a new review
-#[derive(Debug, Clone)]
+///
+/// Reviewers and assignees are usernames; each provider resolves them to
+/// its own user identifiers.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct CreateReviewParams {
     /// Source branch name
     pub source_branch: String,
@@ -259,24 +279,259 @@ pub struct CreateReviewParams {
     /// Review title
     pub title: String,
     /// Review description (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     /// Whether to create as draft
     pub draft: bool,
+    /// Usernames of the reviewers to request
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reviewers: Vec<String>,
+    /// Usernames of the users to assign
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub assignees: Vec<String>,
+    /// Labels to add
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Milestone title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<String>,
 }
 
 /// Parameters for updating an existing review
-#[derive(Debug, Clone)]
+///
+/// List fields replace the review's current values when set.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct UpdateReviewParams {
     /// Review ID to update
     pub review_id: String,
     /// New title (if changing)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
     /// New description (if changing)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     /// New target branch (if changing)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub target_branch: Option<String>,
     /// Change draft status (if changing)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub draft: Option<bool>,
+    /// New reviewer usernames (if changing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviewers: Option<Vec<String>>,
+    /// New assignee usernames (if changing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignees: Option<Vec<String>>,
+    /// New labels (if changing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    /// New milestone title (if changing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<String>,
+}
+
+/// How a review's commits are merged into its target branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMethod {
+    /// Use the project's default merge method
+    #[default]
+    Merge,
+    /// Squash all commits into one before merging
+    Squash,
+    /// Rebase the source branch onto the target branch before merging
+    Rebase,
+}
+
+impl fmt::Display for MergeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeMethod::Merge => write!(f, "merge"),
+            MergeMethod::Squash => write!(f, "squash"),
+            MergeMethod::Rebase => write!(f, "rebase"),
+        }
+    }
+}
+
+/// Parameters for merging a review
+#[derive(Debug, Clone)]
+pub struct MergeReviewParams {
+    /// Review ID to merge
+    pub review_id: String,
+    /// How to merge the review
+    pub method: MergeMethod,
+    /// Only merge if the review's source branch is still at this commit
+    pub sha: Option<String>,
+}
+
+/// Status of the latest CI pipeline of a review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PipelineStatus {
+    /// Created or waiting to run
+    Pending,
+    /// Currently running
+    Running,
+    /// Finished successfully
+    Success,
+    /// Finished with failures
+    Failed,
+    /// Canceled before finishing
+    Canceled,
+    /// Skipped entirely
+    Skipped,
+    /// Waiting for a manual action
+    Manual,
+}
+
+impl fmt::Display for PipelineStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineStatus::Pending => write!(f, "pending"),
+            PipelineStatus::Running => write!(f, "running"),
+            PipelineStatus::Success => write!(f, "success"),
+            PipelineStatus::Failed => write!(f, "failed"),
+            PipelineStatus::Canceled => write!(f, "canceled"),
+            PipelineStatus::Skipped => write!(f, "skipped"),
+            PipelineStatus::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+/// Whether the provider would accept merging a review right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStatus {
+    /// The review can be merged
+    Mergeable,
+    /// The source branch conflicts with the target branch
+    Conflicts,
+    /// The source branch must be rebased onto the target branch first
+    NeedsRebase,
+    /// A project rule blocks the merge (approvals, CI, discussions, ...)
+    Blocked,
+    /// The provider is still computing mergeability, or waiting for the
+    /// pipeline to finish
+    Checking,
+    /// The provider didn't report a status
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for MergeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeStatus::Mergeable => write!(f, "mergeable"),
+            MergeStatus::Conflicts => write!(f, "conflicts"),
+            MergeStatus::NeedsRebase => write!(f, "needs rebase"),
+            MergeStatus::Blocked => write!(f, "blocked"),
+            MergeStatus::Checking => write!(f, "checking"),
+            MergeStatus::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Approval, CI and mergeability status of a review
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewChecks {
+    /// Number of approvals given, for display
+    ///
+    /// Not every approval counts towards the rules (e.g. the author's own),
+    /// so this can't tell whether the review is approved.
+    pub approvals: u32,
+    /// Number of approvals required by the project's rules
+    pub approvals_required: u32,
+    /// Number of approvals the rules still require
+    pub approvals_left: u32,
+    /// Status of the latest pipeline, if any ran
+    pub pipeline: Option<PipelineStatus>,
+    /// Whether the source branch conflicts with the target branch
+    pub has_conflicts: bool,
+    /// Overall mergeability reported by the provider
+    pub merge_status: MergeStatus,
+}
+
+impl ReviewChecks {
+    /// Check whether the review has all the approvals it needs
+    pub fn is_approved(&self) -> bool {
+        self.approvals_left == 0
+    }
+
+    /// Check whether the checks are still changing on their own
+    ///
+    /// True while the pipeline is pending or running, or while the provider
+    /// is still computing mergeability.
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self.pipeline,
+            Some(PipelineStatus::Pending | PipelineStatus::Running)
+        ) || self.merge_status == MergeStatus::Checking
+    }
+
+    /// Describe everything that currently prevents merging the review
+    ///
+    /// Returns an empty list if nothing is known to block the merge.
+    /// A pipeline that is still running only blocks the merge if the
+    /// provider reports the review as blocked.
+    pub fn blockers(&self) -> Vec<String> {
+        let mut blockers = Vec::new();
+
+        if !self.is_approved() {
+            blockers.push(format!("{} more approval(s) required", self.approvals_left));
+        }
+        if let Some(status @ (PipelineStatus::Failed | PipelineStatus::Canceled)) = self.pipeline {
+            blockers.push(format!("pipeline {}", status));
+        }
+        if self.has_conflicts || self.merge_status == MergeStatus::Conflicts {
+            blockers.push("conflicts with the target branch".to_string());
+        }
+        match self.merge_status {
+            MergeStatus::NeedsRebase => {
+                blockers.push("needs a rebase onto the target branch".to_string())
+            }
+            MergeStatus::Blocked if blockers.is_empty() => {
+                blockers.push("blocked by the project's merge rules".to_string())
+            }
+            _ => {}
+        }
+
+        blockers
+    }
+}
+
+/// A comment in a review discussion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comment {
+    /// Username of the comment's author
+    pub author: String,
+    /// Comment text (Markdown)
+    pub body: String,
+    /// When the comment was posted
+    pub created_at: DateTime<Utc>,
+}
+
+/// A discussion thread on a review
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Discussion {
+    /// Provider-specific discussion ID
+    pub id: String,
+    /// Whether the thread can be resolved (e.g. diff comments)
+    pub resolvable: bool,
+    /// Whether the thread has been resolved
+    pub resolved: bool,
+    /// File the thread is attached to, for diff comments
+    pub file: Option<String>,
+    /// Line the thread is attached to, for diff comments
+    pub line: Option<u32>,
+    /// Comments in the thread, oldest first
+    pub comments: Vec<Comment>,
+}
+
+impl Discussion {
+    /// Check whether the thread still needs to be addressed
+    pub fn is_unresolved(&self) -> bool {
+        self.resolvable && !self.resolved
+    }
 }
 
 /// Core provider trait that all providers must implement
@@ -313,8 +568,47 @@ pub trait Provider: Send + Sync {
     /// Get review details by ID
     fn get_review(&mut self, review_id: &str) -> Result<Review>;
 
+    /// Close a review without merging it
+    fn close_review(&mut self, review_id: &str) -> Result<Review>;
+
+    /// Merge a review into its target branch
+    ///
+    /// Fails if the provider refuses the merge (missing approvals, failed
+    /// pipeline, conflicts, ...). The returned review may still be open if
+    /// the provider merges asynchronously.
+    fn merge_review(&mut self, params: MergeReviewParams) -> Result<Review>;
+
+    /// Get the approval, pipeline and mergeability status of a review
+    fn get_review_checks(&mut self, review_id: &str) -> Result<ReviewChecks>;
+
+    /// List the discussion threads of a review, oldest first
+    ///
+    /// System-generated notes (e.g. "added 1 commit") are not included.
+    fn list_discussions(&mut self, review_id: &str) -> Result<Vec<Discussion>>;
+
     /// Check if a review exists for the given branch
     fn find_review_for_branch(&mut self, branch: &str) -> Result<Option<Review>>;
+
+    /// Get several reviews, returning one result per ID in the same order
+    ///
+    /// Providers may fetch them concurrently; by default they are fetched
+    /// one at a time.
+    fn get_reviews(&mut self, review_ids: &[String]) -> Vec<Result<Review>> {
+        review_ids.iter().map(|id| self.get_review(id)).collect()
+    }
+
+    /// Create several independent reviews, returning one result per review
+    /// in the same order
+    ///
+    /// A failure doesn't prevent the other reviews from being created.
+    /// Providers may create them concurrently; by default they are created
+    /// one at a time.
+    fn create_reviews(&mut self, params: Vec<CreateReviewParams>) -> Vec<Result<Review>> {
+        params
+            .into_iter()
+            .map(|params| self.create_review(params))
+            .collect()
+    }
 }
 
 /// Create a provider instance for the given provider type
@@ -396,6 +690,52 @@ mod tests {
         assert_eq!(ReviewState::Closed.to_string(), "closed");
     }
 
+    #[test]
+    fn test_merge_method_display() {
+        assert_eq!(MergeMethod::Merge.to_string(), "merge");
+        assert_eq!(MergeMethod::Squash.to_string(), "squash");
+        assert_eq!(MergeMethod::Rebase.to_string(), "rebase");
+        assert_eq!(MergeMethod::default(), MergeMethod::Merge);
+    }
+
+    #[test]
+    fn test_review_checks_blockers() {
+        let checks = ReviewChecks {
+            merge_status: MergeStatus::Mergeable,
+            pipeline: Some(PipelineStatus::Success),
+            ..Default::default()
+        };
+        assert!(checks.is_approved());
+        assert!(checks.blockers().is_empty());
+
+        let checks = ReviewChecks {
+            approvals: 2,
+            approvals_required: 2,
+            approvals_left: 1,
+            pipeline: Some(PipelineStatus::Failed),
+            has_conflicts: true,
+            merge_status: MergeStatus::Blocked,
+        };
+        assert!(!checks.is_approved());
+        assert_eq!(
+            checks.blockers(),
+            vec![
+                "1 more approval(s) required",
+                "pipeline failed",
+                "conflicts with the target branch",
+            ]
+        );
+
+        let checks = ReviewChecks {
+            merge_status: MergeStatus::Blocked,
+            ..Default::default()
+        };
+        assert_eq!(
+            checks.blockers(),
+            vec!["blocked by the project's merge rules"]
+        );
+    }
+
     #[test]
     fn test_extract_base_url() {
         // GitLab HTTPS
@@ -0,0 +1,995 @@
+//! Integration tests for stack manipulation commands
+//!
+//! Each test builds a temporary repository with a small stack of branches
+//! tracked in `.git/basalt/metadata.yml`, runs `bt`, and checks both the
+//! resulting git history and the metadata.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Run a git command in the repository and return its stdout
+fn git(repo_path: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo_path)
+        .output()
+        .expect("Failed to run git");
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Create a commit adding `file` on the current branch
+fn commit_file(repo_path: &Path, file: &str, message: &str) {
+    fs::write(repo_path.join(file), format!("{}\n", message)).unwrap();
+    git(repo_path, &["add", file]);
+    git(repo_path, &["commit", "-m", message]);
+}
+
+/// Create a branch from `parent` with a single commit
+fn create_branch(repo_path: &Path, name: &str, parent: &str) {
+    git(repo_path, &["checkout", "-q", "-b", name, parent]);
+    commit_file(
+        repo_path,
+        &format!("{}.txt", name),
+        &format!("Add {}", name),
+    );
+}
+
+/// Create an initialized repository with the given `(branch, parent)` stack
+///
+/// Branches are created in order, so parents must come before children.
+fn create_stack_repo(branches: &[(&str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let repo_path = temp_dir.path();
+
+    git(repo_path, &["init", "-q", "-b", "main"]);
+    git(repo_path, &["config", "user.email", "test@example.com"]);
+    git(repo_path, &["config", "user.name", "Test User"]);
+    commit_file(repo_path, "README.md", "Initial commit");
+
+    let output = run_bt(
+        repo_path,
+        &[
+            "init",
+            "--provider",
+            "gitlab",
+            "--base-branch",
+            "main",
+            "--skip-auth",
+        ],
+    );
+    assert!(output.is_ok(), "Init should succeed: {:?}", output);
+
+    let mut yaml = String::from("branches:\n");
+    for (name, parent) in branches {
+        create_branch(repo_path, name, parent);
+        yaml.push_str(&format!(
+            "  {}:\n    parent: {}\n    created_at: \"2024-01-01T00:00:00Z\"\n",
+            name, parent
+        ));
+    }
+
+    let metadata_path = repo_path.join(".git/basalt/metadata.yml");
+    let metadata = fs::read_to_string(&metadata_path).unwrap();
+    fs::write(&metadata_path, metadata.replace("branches: {}\n", &yaml)).unwrap();
+
+    temp_dir
+}
+
+/// Run bt with the given arguments, returning stdout or stderr
+fn run_bt(repo_path: &Path, args: &[&str]) -> Result<String, String> {
+    run_bt_with_input(repo_path, args, "")
+}
+
+/// Run bt with the given arguments and stdin contents
+fn run_bt_with_input(repo_path: &Path, args: &[&str], input: &str) -> Result<String, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bt"))
+        .args(args)
+        .current_dir(repo_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute bt");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().expect("Failed to wait for bt");
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+}
+
+/// Read the metadata file as a YAML value
+fn read_metadata(repo_path: &Path) -> serde_yaml::Value {
+    let contents = fs::read_to_string(repo_path.join(".git/basalt/metadata.yml")).unwrap();
+    serde_yaml::from_str(&contents).unwrap()
+}
+
+/// Get the recorded parent of a tracked branch
+fn parent_of(repo_path: &Path, branch: &str) -> String {
+    read_metadata(repo_path)["branches"][branch]["parent"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Get the commit subjects reachable from `branch`, newest first
+fn log_subjects(repo_path: &Path, branch: &str) -> Vec<String> {
+    git(repo_path, &["log", "--format=%s", branch])
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+/// Add a bare repository as the `origin` remote
+fn add_remote(repo_path: &Path) -> TempDir {
+    let remote = TempDir::new().unwrap();
+    git(remote.path(), &["init", "-q", "--bare"]);
+    git(
+        repo_path,
+        &["remote", "add", "origin", remote.path().to_str().unwrap()],
+    );
+    remote
+}
+
+#[test]
+fn test_move_branch_onto_sibling() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "main")]);
+    git(repo.path(), &["checkout", "-q", "b"]);
+
+    let result = run_bt(repo.path(), &["move", "--onto", "c"]);
+    assert!(result.is_ok(), "Move should succeed: {:?}", result);
+
+    assert_eq!(parent_of(repo.path(), "b"), "c");
+    assert_eq!(
+        log_subjects(repo.path(), "b"),
+        vec!["Add b", "Add c", "Initial commit"]
+    );
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "b");
+}
+
+#[test]
+fn test_move_restacks_descendants() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "b"), ("d", "main")]);
+    git(repo.path(), &["checkout", "-q", "main"]);
+
+    let result = run_bt(repo.path(), &["move", "b", "--onto", "d"]);
+    assert!(result.is_ok(), "Move should succeed: {:?}", result);
+
+    assert_eq!(parent_of(repo.path(), "b"), "d");
+    assert_eq!(parent_of(repo.path(), "c"), "b");
+    assert_eq!(
+        log_subjects(repo.path(), "c"),
+        vec!["Add c", "Add b", "Add d", "Initial commit"]
+    );
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "main");
+}
+
+#[test]
+fn test_move_onto_descendant_fails() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+
+    let result = run_bt(repo.path(), &["move", "a", "--onto", "b"]);
+    assert!(result.is_err(), "Moving onto a descendant should fail");
+    assert!(result.unwrap_err().contains("upstack"));
+    assert_eq!(parent_of(repo.path(), "a"), "main");
+}
+
+#[test]
+fn test_fold_into_parent() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "b"), ("d", "a")]);
+    git(repo.path(), &["checkout", "-q", "b"]);
+
+    let result = run_bt(repo.path(), &["fold"]);
+    assert!(result.is_ok(), "Fold should succeed: {:?}", result);
+
+    let branches = git(repo.path(), &["branch", "--format=%(refname:short)"]);
+    assert!(!branches.lines().any(|b| b == "b"), "b should be deleted");
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "a");
+
+    let metadata = read_metadata(repo.path());
+    assert!(metadata["branches"]["b"].is_null());
+    assert_eq!(parent_of(repo.path(), "c"), "a");
+    assert_eq!(parent_of(repo.path(), "d"), "a");
+    assert_eq!(
+        log_subjects(repo.path(), "a"),
+        vec!["Add b", "Add a", "Initial commit"]
+    );
+    assert_eq!(
+        log_subjects(repo.path(), "d"),
+        vec!["Add d", "Add b", "Add a", "Initial commit"]
+    );
+}
+
+#[test]
+fn test_fold_keep_child_name() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("d", "a")]);
+    git(repo.path(), &["checkout", "-q", "b"]);
+
+    let result = run_bt(repo.path(), &["fold", "--keep"]);
+    assert!(result.is_ok(), "Fold should succeed: {:?}", result);
+
+    let branches = git(repo.path(), &["branch", "--format=%(refname:short)"]);
+    assert!(!branches.lines().any(|b| b == "a"), "a should be deleted");
+    assert_eq!(parent_of(repo.path(), "b"), "main");
+    assert_eq!(parent_of(repo.path(), "d"), "b");
+    assert_eq!(
+        log_subjects(repo.path(), "d"),
+        vec!["Add d", "Add b", "Add a", "Initial commit"]
+    );
+}
+
+#[test]
+fn test_fold_into_base_branch_fails() {
+    let repo = create_stack_repo(&[("a", "main")]);
+
+    let result = run_bt(repo.path(), &["fold"]);
+    assert!(result.is_err(), "Folding into the base branch should fail");
+}
+
+#[test]
+fn test_squash_restacks_children() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "More a");
+
+    let result = run_bt(repo.path(), &["squash", "-m", "Squashed a"]);
+    assert!(result.is_ok(), "Squash should succeed: {:?}", result);
+
+    assert_eq!(
+        log_subjects(repo.path(), "a"),
+        vec!["Squashed a", "Initial commit"]
+    );
+    assert_eq!(
+        log_subjects(repo.path(), "b"),
+        vec!["Add b", "Squashed a", "Initial commit"]
+    );
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "a");
+}
+
+#[test]
+fn test_squash_no_edit_combines_messages() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    commit_file(repo.path(), "a2.txt", "More a");
+
+    let result = run_bt(repo.path(), &["squash", "--no-edit"]);
+    assert!(result.is_ok(), "Squash should succeed: {:?}", result);
+
+    let message = git(repo.path(), &["log", "-1", "--format=%B", "a"]);
+    assert_eq!(message, "Add a\n\nMore a");
+}
+
+#[test]
+fn test_split_by_commit() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "Second a");
+    commit_file(repo.path(), "a3.txt", "Third a");
+    git(repo.path(), &["checkout", "-q", "b"]);
+    git(repo.path(), &["rebase", "-q", "a"]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+
+    let result = run_bt_with_input(
+        repo.path(),
+        &["split", "--by-commit"],
+        "1,2\na-part-1\na-part-2\n",
+    );
+    assert!(result.is_ok(), "Split should succeed: {:?}", result);
+
+    assert_eq!(parent_of(repo.path(), "a-part-1"), "main");
+    assert_eq!(parent_of(repo.path(), "a-part-2"), "a-part-1");
+    assert_eq!(parent_of(repo.path(), "a"), "a-part-2");
+    assert_eq!(parent_of(repo.path(), "b"), "a");
+    assert_eq!(
+        log_subjects(repo.path(), "a-part-1"),
+        vec!["Add a", "Initial commit"]
+    );
+    assert_eq!(
+        log_subjects(repo.path(), "a-part-2"),
+        vec!["Second a", "Add a", "Initial commit"]
+    );
+}
+
+#[test]
+fn test_split_by_commit_single_commit_fails() {
+    let repo = create_stack_repo(&[("a", "main")]);
+
+    let result = run_bt_with_input(repo.path(), &["split"], "1\n");
+    assert!(result.is_err(), "Splitting a single commit should fail");
+    assert!(result.unwrap_err().contains("--by-hunk"));
+}
+
+#[test]
+fn test_delete_reparents_and_restacks_children() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "b")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+
+    let result = run_bt(repo.path(), &["delete", "--force"]);
+    assert!(result.is_ok(), "Delete should succeed: {:?}", result);
+
+    let metadata = read_metadata(repo.path());
+    assert!(metadata["branches"]["a"].is_null());
+    assert_eq!(parent_of(repo.path(), "b"), "main");
+    assert_eq!(parent_of(repo.path(), "c"), "b");
+    assert_eq!(
+        log_subjects(repo.path(), "c"),
+        vec!["Add c", "Add b", "Initial commit"]
+    );
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "main");
+}
+
+#[test]
+fn test_delete_unmerged_requires_force() {
+    let repo = create_stack_repo(&[("a", "main")]);
+
+    let result = run_bt(repo.path(), &["delete", "a"]);
+    assert!(result.is_err(), "Deleting an unmerged branch should fail");
+    assert!(result.unwrap_err().contains("--force"));
+    assert_eq!(parent_of(repo.path(), "a"), "main");
+}
+
+#[test]
+fn test_rename_updates_metadata_and_children() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+
+    let result = run_bt(repo.path(), &["rename", "renamed"]);
+    assert!(result.is_ok(), "Rename should succeed: {:?}", result);
+
+    let metadata = read_metadata(repo.path());
+    assert!(metadata["branches"]["a"].is_null());
+    assert_eq!(parent_of(repo.path(), "renamed"), "main");
+    assert_eq!(parent_of(repo.path(), "b"), "renamed");
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "renamed");
+}
+
+#[test]
+fn test_rename_to_existing_branch_fails() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+
+    let result = run_bt(repo.path(), &["rename", "a"]);
+    assert!(
+        result.is_err(),
+        "Renaming onto an existing branch should fail"
+    );
+    assert_eq!(parent_of(repo.path(), "b"), "a");
+}
+
+#[test]
+fn test_rename_pushes_and_records_pushed_commit() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    let remote = add_remote(repo.path());
+    git(repo.path(), &["push", "-q", "-u", "origin", "a"]);
+
+    let result = run_bt(repo.path(), &["rename", "renamed"]);
+    assert!(result.is_ok(), "Rename should succeed: {:?}", result);
+
+    let commit = git(repo.path(), &["rev-parse", "renamed"]);
+    assert_eq!(git(remote.path(), &["rev-parse", "renamed"]), commit);
+    assert_eq!(
+        read_metadata(repo.path())["branches"]["renamed"]["pushed_sha"].as_str(),
+        Some(commit.as_str())
+    );
+    // The old name is gone from the remote
+    assert_eq!(git(remote.path(), &["branch", "--list", "a"]), "");
+}
+
+#[test]
+fn test_push_refuses_to_overwrite_remote_changes() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    let remote = add_remote(repo.path());
+    git(repo.path(), &["push", "-q", "-u", "origin", "a"]);
+    // Someone else pushes a branch with the name we're about to use
+    git(
+        repo.path(),
+        &["push", "-q", "origin", "main:refs/heads/renamed"],
+    );
+
+    let result = run_bt(repo.path(), &["rename", "renamed"]);
+    let error = result.expect_err("Pushing over someone else's branch should fail");
+    assert!(error.contains("Remote branch changed"), "{}", error);
+    assert!(error.contains("renamed"), "{}", error);
+    assert_eq!(
+        git(remote.path(), &["rev-parse", "renamed"]),
+        git(repo.path(), &["rev-parse", "main"])
+    );
+}
+
+#[test]
+fn test_restack_after_parent_changed() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "b")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "More a");
+
+    let result = run_bt(repo.path(), &["restack"]);
+    assert!(result.is_ok(), "Restack should succeed: {:?}", result);
+
+    assert_eq!(
+        log_subjects(repo.path(), "c"),
+        vec!["Add c", "Add b", "More a", "Add a", "Initial commit"]
+    );
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "a");
+
+    let output = run_bt(repo.path(), &["restack"]).expect("Restack should succeed");
+    assert!(output.contains("up to date"), "{}", output);
+}
+
+#[test]
+fn test_restack_without_checking_out_branches() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "b")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "More a");
+    git(repo.path(), &["checkout", "-q", "main"]);
+    let head_log = git(repo.path(), &["reflog", "HEAD"]);
+    let author = git(repo.path(), &["log", "-1", "--format=%an %ae %at", "c"]);
+
+    let result = run_bt(repo.path(), &["restack"]);
+    assert!(result.is_ok(), "Restack should succeed: {:?}", result);
+
+    assert_eq!(git(repo.path(), &["reflog", "HEAD"]), head_log);
+    assert_eq!(
+        log_subjects(repo.path(), "c"),
+        vec!["Add c", "Add b", "More a", "Add a", "Initial commit"]
+    );
+    assert_eq!(
+        git(repo.path(), &["log", "-1", "--format=%an %ae %at", "c"]),
+        author
+    );
+    assert_eq!(git(repo.path(), &["status", "--porcelain"]), "");
+}
+
+/// Commit `contents` to `file` on the current branch
+fn commit_contents(repo_path: &Path, file: &str, contents: &str, message: &str) {
+    fs::write(repo_path.join(file), contents).unwrap();
+    git(repo_path, &["add", file]);
+    git(repo_path, &["commit", "-q", "-m", message]);
+}
+
+#[test]
+fn test_restack_after_parent_squash_merged() {
+    let repo = create_stack_repo(&[]);
+    let path = repo.path();
+    commit_contents(path, "f.txt", "1\n", "Add f");
+    git(path, &["checkout", "-q", "-b", "a"]);
+    commit_contents(path, "f.txt", "2\n", "Two");
+    commit_contents(path, "f.txt", "3\n", "Three");
+    git(path, &["checkout", "-q", "-b", "b"]);
+    commit_contents(path, "f.txt", "4\n", "Four");
+    let metadata_path = path.join(".git/basalt/metadata.yml");
+    let metadata = fs::read_to_string(&metadata_path).unwrap();
+    fs::write(
+        &metadata_path,
+        metadata.replace(
+            "branches:\n",
+            "branches:\n  a:\n    parent: main\n    created_at: \"2024-01-01T00:00:00Z\"\n  b:\n    parent: a\n    created_at: \"2024-01-01T00:00:00Z\"\n",
+        ),
+    )
+    .unwrap();
+
+    // The review for 'a' was squash-merged into main
+    git(path, &["checkout", "-q", "main"]);
+    git(path, &["merge", "-q", "--squash", "a"]);
+    git(path, &["commit", "-q", "-m", "Squashed a"]);
+    git(path, &["checkout", "-q", "b"]);
+
+    let output = run_bt(path, &["restack"]).expect("Restack should succeed");
+    assert!(output.contains("already upstream"), "{}", output);
+    assert_eq!(
+        git(path, &["rev-parse", "a"]),
+        git(path, &["rev-parse", "main"])
+    );
+    assert_eq!(
+        log_subjects(path, "b"),
+        vec!["Four", "Squashed a", "Add f", "Initial commit"]
+    );
+    assert_eq!(fs::read_to_string(path.join("f.txt")).unwrap(), "4\n");
+}
+
+#[test]
+fn test_restack_skips_cherry_picked_commits() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    let path = repo.path();
+    commit_contents(path, "x.txt", "x\n", "Add x");
+    commit_file(path, "y.txt", "Add y");
+
+    // 'Add x' was cherry-picked to main, then changed there
+    git(path, &["checkout", "-q", "main"]);
+    git(path, &["cherry-pick", "a~1"]);
+    commit_contents(path, "x.txt", "x changed\n", "Change x");
+
+    let output = run_bt(path, &["restack"]).expect("Restack should succeed");
+    assert!(output.contains("skipping 1 commit"), "{}", output);
+    assert_eq!(
+        log_subjects(path, "a"),
+        vec!["Add y", "Add a", "Change x", "Add x", "Initial commit"]
+    );
+}
+
+#[test]
+fn test_restack_continue_after_conflict() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    fs::write(repo.path().join("b.txt"), "from a\n").unwrap();
+    git(repo.path(), &["add", "b.txt"]);
+    git(repo.path(), &["commit", "-q", "-m", "Conflict with b"]);
+
+    let result = run_bt(repo.path(), &["restack"]);
+    assert!(result.is_err(), "Restack should stop on conflicts");
+    assert!(result.unwrap_err().contains("bt restack --continue"));
+
+    fs::write(repo.path().join("b.txt"), "resolved\n").unwrap();
+    git(repo.path(), &["add", "b.txt"]);
+
+    let result = run_bt(repo.path(), &["restack", "--continue"]);
+    assert!(result.is_ok(), "Continue should succeed: {:?}", result);
+    assert_eq!(
+        log_subjects(repo.path(), "b"),
+        vec!["Add b", "Conflict with b", "Add a", "Initial commit"]
+    );
+}
+
+#[test]
+fn test_restack_rejects_merge_commits() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("side", "a")]);
+    git(repo.path(), &["checkout", "-q", "b"]);
+    git(
+        repo.path(),
+        &["merge", "-q", "--no-ff", "--no-edit", "side"],
+    );
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "More a");
+    let tip = git(repo.path(), &["rev-parse", "b"]);
+
+    let result = run_bt(repo.path(), &["restack"]);
+    let error = result.expect_err("Restacking a merge commit should fail");
+    assert!(error.contains("merge commit"), "{}", error);
+    assert!(error.contains("git rebase a"), "{}", error);
+    assert_eq!(git(repo.path(), &["rev-parse", "b"]), tip);
+}
+
+#[test]
+fn test_restack_empty_branch_requires_allow_empty() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "b"]);
+    git(repo.path(), &["reset", "-q", "--hard", "a"]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "More a");
+
+    let result = run_bt(repo.path(), &["restack"]);
+    let error = result.expect_err("Restacking an empty branch should fail");
+    assert!(error.contains("No commits"), "{}", error);
+
+    let result = run_bt(repo.path(), &["restack", "--allow-empty"]);
+    assert!(result.is_ok(), "Restack should succeed: {:?}", result);
+    assert_eq!(
+        git(repo.path(), &["rev-parse", "b"]),
+        git(repo.path(), &["rev-parse", "a"])
+    );
+}
+
+#[test]
+fn test_restack_lists_uncommitted_changes() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    fs::write(repo.path().join("b.txt"), "changed\n").unwrap();
+    fs::write(repo.path().join("a.txt"), "staged\n").unwrap();
+    git(repo.path(), &["add", "a.txt"]);
+    fs::write(repo.path().join("notes.txt"), "untracked\n").unwrap();
+
+    let error = run_bt(repo.path(), &["restack"]).expect_err("Restack should refuse");
+    assert!(error.contains("uncommitted changes"), "{}", error);
+    assert!(error.contains("staged     a.txt"), "{}", error);
+    assert!(error.contains("unstaged   b.txt"), "{}", error);
+    assert!(error.contains("untracked  notes.txt"), "{}", error);
+}
+
+#[test]
+fn test_restack_updates_branch_checked_out_in_other_worktree() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    let worktrees = TempDir::new().unwrap();
+    let other = worktrees.path().join("b");
+    git(repo.path(), &["checkout", "-q", "a"]);
+    git(
+        repo.path(),
+        &["worktree", "add", "-q", other.to_str().unwrap(), "b"],
+    );
+    commit_file(repo.path(), "a2.txt", "More a");
+
+    // Metadata is shared, so the linked worktree sees the stack
+    let status = run_bt(&other, &["status"]).expect("Status should succeed");
+    assert!(status.contains("◉ b  (needs restack)"), "{}", status);
+
+    let status = run_bt(repo.path(), &["status"]).expect("Status should succeed");
+    let other = other.canonicalize().unwrap();
+    assert!(
+        status.contains(&format!("[worktree: {}]", other.display())),
+        "{}",
+        status
+    );
+
+    let result = run_bt(repo.path(), &["restack"]);
+    assert!(result.is_ok(), "Restack should succeed: {:?}", result);
+    assert_eq!(
+        log_subjects(repo.path(), "b"),
+        vec!["Add b", "More a", "Add a", "Initial commit"]
+    );
+    assert!(other.join("a2.txt").exists());
+    assert_eq!(git(&other, &["status", "--porcelain"]), "");
+}
+
+#[test]
+fn test_log_graph_shows_tree_and_commits() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "main")]);
+    git(repo.path(), &["checkout", "-q", "b"]);
+
+    let output = run_bt(repo.path(), &["log"]).expect("Log should succeed");
+    assert!(output.starts_with("main\n"), "{}", output);
+    assert!(output.contains("├── ◯ a"), "{}", output);
+    assert!(output.contains("│   └── ◉ b"), "{}", output);
+    assert!(output.contains("└── ◯ c"), "{}", output);
+    assert!(output.contains("Add b"), "{}", output);
+}
+
+#[test]
+fn test_log_short_marks_needs_restack() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "More a");
+
+    let output = run_bt(repo.path(), &["log", "--short"]).expect("Log should succeed");
+    assert!(output.contains("◯ b  (needs restack)"), "{}", output);
+    assert!(!output.contains("More a"), "{}", output);
+}
+
+#[test]
+fn test_log_json_current_stack() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "main")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+
+    let output = run_bt(repo.path(), &["log", "--stack", "--json"]).expect("Log should succeed");
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+    let names: Vec<&str> = json["branches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["a", "b"]);
+    assert_eq!(json["current_branch"], "a");
+    assert_eq!(json["branches"][0]["current"], true);
+    assert_eq!(json["branches"][1]["commits"][0]["subject"], "Add b");
+}
+
+#[test]
+fn test_log_without_cached_token_skips_review_states() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    let metadata_path = repo.path().join(".git/basalt/metadata.yml");
+    let metadata = fs::read_to_string(&metadata_path).unwrap();
+    fs::write(
+        &metadata_path,
+        metadata.replace(
+            "    parent: main\n",
+            "    parent: main\n    review_id: '7'\n",
+        ),
+    )
+    .unwrap();
+
+    // Nothing to authenticate with: the log must neither prompt nor warn
+    let output = Command::new(env!("CARGO_BIN_EXE_bt"))
+        .args(["log", "--json"])
+        .current_dir(repo.path())
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to execute bt");
+    assert!(output.status.success(), "Log should succeed: {:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["branches"][0]["review"]["id"], "7");
+    assert!(json["branches"][0]["review"].get("state").is_none());
+}
+
+#[test]
+fn test_status_without_cached_token_skips_review_states() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    let metadata_path = repo.path().join(".git/basalt/metadata.yml");
+    let metadata = fs::read_to_string(&metadata_path).unwrap();
+    fs::write(
+        &metadata_path,
+        metadata.replace(
+            "    parent: main\n",
+            "    parent: main\n    review_id: '7'\n",
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bt"))
+        .args(["status", "--json"])
+        .current_dir(repo.path())
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to execute bt");
+    assert!(
+        output.status.success(),
+        "Status should succeed: {:?}",
+        output
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["branches"][0]["review_id"], "7");
+    assert!(json["branches"][0].get("review_state").is_none());
+}
+
+#[test]
+fn test_land_without_review_fails() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+
+    let result = run_bt(repo.path(), &["land", "--all"]);
+    assert!(result.is_err(), "Landing without reviews should fail");
+    assert!(
+        result
+            .unwrap_err()
+            .contains("Review not found for branch: a"),
+        "Should point at the bottom branch"
+    );
+    assert_eq!(parent_of(repo.path(), "b"), "a");
+}
+
+#[test]
+fn test_comments_without_reviews() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+
+    let output = run_bt(repo.path(), &["comments"]).expect("comments should succeed");
+    assert!(output.contains("No reviews in the current stack"));
+
+    let output = run_bt(repo.path(), &["comments", "--json"]).expect("comments should succeed");
+    assert_eq!(output.trim(), "[]");
+}
+
+#[test]
+fn test_submit_rejects_invalid_config() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    fs::write(
+        repo.path().join(".basalt.toml"),
+        "[submit]\nreviewer = \"alice\"\n",
+    )
+    .unwrap();
+
+    let result = run_bt(repo.path(), &["submit", "--label", "stacked"]);
+    assert!(result.is_err(), "Unknown config keys should be rejected");
+    assert!(result.unwrap_err().contains("reviewer"));
+}
+
+#[test]
+fn test_submit_scope_requires_pushed_parent() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "b"]);
+
+    let result = run_bt(repo.path(), &["submit", "--branch"]);
+    let error = result.expect_err("Submitting above an unpushed parent should fail");
+    assert!(
+        error.contains("its parent 'a' hasn't been pushed"),
+        "{}",
+        error
+    );
+
+    let result = run_bt(repo.path(), &["submit", "--branch", "--upstack"]);
+    assert!(result.is_err(), "Scope flags should be mutually exclusive");
+}
+
+#[test]
+fn test_dry_run_changes_nothing() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    let tip = git(repo.path(), &["rev-parse", "b"]);
+
+    let output = run_bt(repo.path(), &["move", "--onto", "main", "--dry-run"])
+        .expect("Dry run should succeed");
+    assert!(
+        output.contains("Set the parent of 'b' to 'main'"),
+        "{}",
+        output
+    );
+    assert!(output.contains("Rebase 'b' onto 'main'"), "{}", output);
+
+    let output = run_bt(repo.path(), &["--dry-run=json", "delete", "a", "--force"])
+        .expect("Dry run should succeed");
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["command"], "delete");
+    let actions: Vec<&str> = json["actions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|action| action["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            "restack",
+            "checkout",
+            "delete_branch",
+            "untrack_branch",
+            "set_parent"
+        ]
+    );
+
+    assert_eq!(parent_of(repo.path(), "b"), "a");
+    assert_eq!(git(repo.path(), &["rev-parse", "b"]), tip);
+    assert!(git(repo.path(), &["branch", "--list", "a"]).contains('a'));
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "b");
+}
+
+#[test]
+fn test_dry_run_unsupported_for_split_by_hunk() {
+    let repo = create_stack_repo(&[("a", "main")]);
+
+    let result = run_bt(repo.path(), &["split", "--by-hunk", "--dry-run"]);
+    assert!(result.is_err(), "Splitting by hunk can't be planned");
+    assert!(result.unwrap_err().contains("--dry-run"));
+}
+
+#[test]
+fn test_undo_restores_branches_and_metadata() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a"), ("c", "b")]);
+    let tips: Vec<String> = ["a", "b", "c"]
+        .iter()
+        .map(|branch| git(repo.path(), &["rev-parse", branch]))
+        .collect();
+    git(repo.path(), &["checkout", "-q", "a"]);
+
+    let result = run_bt(repo.path(), &["delete", "--force"]);
+    assert!(result.is_ok(), "Delete should succeed: {:?}", result);
+
+    let output = run_bt(repo.path(), &["oplog"]).expect("oplog should succeed");
+    assert!(output.contains("bt delete"), "{}", output);
+    assert!(output.contains("a  deleted"), "{}", output);
+
+    let result = run_bt(repo.path(), &["undo"]);
+    assert!(result.is_ok(), "Undo should succeed: {:?}", result);
+
+    for (branch, tip) in ["a", "b", "c"].iter().zip(&tips) {
+        assert_eq!(&git(repo.path(), &["rev-parse", branch]), tip);
+    }
+    assert_eq!(parent_of(repo.path(), "a"), "main");
+    assert_eq!(parent_of(repo.path(), "b"), "a");
+    assert_eq!(git(repo.path(), &["branch", "--show-current"]), "a");
+
+    let result = run_bt(repo.path(), &["undo"]);
+    assert!(result.is_err(), "Nothing should be left to undo");
+    assert!(result.unwrap_err().contains("Nothing to undo"));
+}
+
+#[test]
+fn test_undo_refuses_after_new_commits() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "More a");
+
+    let result = run_bt(repo.path(), &["restack"]);
+    assert!(result.is_ok(), "Restack should succeed: {:?}", result);
+
+    git(repo.path(), &["checkout", "-q", "b"]);
+    commit_file(repo.path(), "b2.txt", "More b");
+
+    let result = run_bt(repo.path(), &["undo"]);
+    assert!(result.is_err(), "Undo should not drop new commits");
+    assert!(result.unwrap_err().contains("'b' changed"));
+    assert_eq!(log_subjects(repo.path(), "b")[0], "More b");
+}
+
+#[test]
+fn test_doctor_reports_each_check() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    let _remote = add_remote(repo.path());
+    git(repo.path(), &["checkout", "-q", "main"]);
+    git(repo.path(), &["branch", "-q", "-D", "b"]);
+
+    let output = run_bt(repo.path(), &["doctor", "--json"]).expect("Doctor should pass");
+    let checks: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let status = |name: &str| {
+        checks
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|check| check["name"] == name)
+            .map(|check| check["status"].as_str().unwrap().to_string())
+    };
+    assert_eq!(status("metadata").as_deref(), Some("pass"));
+    assert_eq!(status("base branch").as_deref(), Some("pass"));
+    assert_eq!(status("remote").as_deref(), Some("pass"));
+    assert_eq!(status("branches").as_deref(), Some("warn"));
+    assert_eq!(status("token").as_deref(), Some("warn"));
+}
+
+#[test]
+fn test_doctor_fails_on_untracked_parent() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    let metadata_path = repo.path().join(".git/basalt/metadata.yml");
+    let metadata = fs::read_to_string(&metadata_path).unwrap();
+    fs::write(
+        &metadata_path,
+        metadata.replace("parent: main", "parent: gone"),
+    )
+    .unwrap();
+
+    let error = run_bt(repo.path(), &["doctor"]).expect_err("Doctor should fail");
+    assert!(error.contains("1 check(s) failed"), "{}", error);
+}
+
+#[test]
+fn test_output_json_reports_applied_actions() {
+    let repo = create_stack_repo(&[("a", "main"), ("b", "a")]);
+    git(repo.path(), &["checkout", "-q", "a"]);
+    commit_file(repo.path(), "a2.txt", "More a");
+
+    let output =
+        run_bt(repo.path(), &["restack", "--output", "json"]).expect("Restack should succeed");
+    let result: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(result["command"], "restack");
+    assert_eq!(result["actions"][0]["action"], "restack");
+    assert_eq!(result["actions"][0]["rebases"][0]["branch"], "b");
+
+    let output = run_bt(repo.path(), &["--output", "json", "status"]).unwrap();
+    let status: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(status["current_branch"], "a");
+}
+
+#[test]
+fn test_output_json_errors_have_kind_and_exit_code() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    fs::write(repo.path().join("a.txt"), "changed\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bt"))
+        .args(["--output", "json", "restack"])
+        .current_dir(repo.path())
+        .output()
+        .expect("Failed to execute bt");
+
+    assert_eq!(output.status.code(), Some(7));
+    assert!(output.stdout.is_empty());
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["kind"], "uncommitted_changes");
+    assert_eq!(error["exit_code"], 7);
+}
+
+#[test]
+fn test_trace_file_records_git_operations() {
+    let repo = create_stack_repo(&[("a", "main")]);
+    let trace_file = repo.path().join(".git/trace.jsonl");
+
+    let result = run_bt(
+        repo.path(),
+        &["status", "--trace-file", trace_file.to_str().unwrap()],
+    );
+    assert!(result.is_ok(), "Status should succeed: {:?}", result);
+
+    let events: Vec<serde_json::Value> = fs::read_to_string(&trace_file)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(
+        events
+            .iter()
+            .any(|event| event["span"]["name"] == "get_current_branch"),
+        "{:?}",
+        events
+    );
+}